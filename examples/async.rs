@@ -94,7 +94,7 @@ async fn x_process(receiver: Receiver<[u8; 3]>) -> breadx::Result<()> {
         .await?;
 
     let wdw = conn
-        .intern_atom_immediate_async("WM_DELETE_WINDOW".to_owned(), false)
+        .intern_atom_immediate_async("WM_DELETE_WINDOW", false)
         .await?;
     win.set_wm_protocols_async(&mut conn, &[wdw]).await?;
 
@@ -126,6 +126,7 @@ async fn x_process(receiver: Receiver<[u8; 3]>) -> breadx::Result<()> {
 
                 // send an exposure event to force the window to redraw itself
                 conn.send_event_async(
+                    false,
                     win,
                     EventMask::EXPOSURE,
                     Event::Expose(ExposeEvent {
@@ -64,7 +64,7 @@ fn main() {
 
     // set up an exit atom
     let wm_delete_window = conn
-        .intern_atom_immediate("WM_DELETE_WINDOW".to_owned(), false)
+        .intern_atom_immediate("WM_DELETE_WINDOW", false)
         .unwrap();
     window
         .set_wm_protocols(&mut conn, &[wm_delete_window])
@@ -94,7 +94,7 @@ fn main() -> Result<(), BreadError> {
     )?;
 
     // Set up the exit atom.
-    let wdw = conn.intern_atom_immediate("WM_DELETE_WINDOW".to_owned(), false)?;
+    let wdw = conn.intern_atom_immediate("WM_DELETE_WINDOW", false)?;
     window.set_wm_protocols(&mut conn, &[wdw])?;
 
     // Event loop
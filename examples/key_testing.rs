@@ -23,7 +23,7 @@ fn main() -> breadx::Result {
     window.set_title(&mut conn, "Hello world!")?;
     window.set_event_mask(&mut conn, EventMask::KEY_PRESS)?;
 
-    let wm_delete_window = conn.intern_atom_immediate("WM_DELETE_WINDOW".to_owned(), false)?;
+    let wm_delete_window = conn.intern_atom_immediate("WM_DELETE_WINDOW", false)?;
     window.set_wm_protocols(&mut conn, &[wm_delete_window])?;
 
     let mut keystate = KeyboardState::new(&mut conn)?;
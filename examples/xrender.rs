@@ -34,7 +34,7 @@ fn main() -> Result {
     window.set_title(&mut conn, "Render")?;
     window.map(&mut conn)?;
     window.set_event_mask(&mut conn, EventMask::EXPOSURE)?;
-    let wdw = conn.intern_atom_immediate("WM_DELETE_WINDOW".to_owned(), false)?;
+    let wdw = conn.intern_atom_immediate("WM_DELETE_WINDOW", false)?;
     window.set_wm_protocols(&mut conn, &[wdw])?;
     let attrs = window.window_attributes_immediate(&mut conn)?;
 
@@ -264,6 +264,7 @@ fn main() -> Result {
                     }
 
                     conn.send_event(
+                        false,
                         win,
                         EventMask::EXPOSURE,
                         Event::Expose(ExposeEvent {
@@ -234,6 +234,7 @@ fn main() -> Result {
                 }
                 if redraw {
                     conn.send_event(
+                        false,
                         win,
                         EventMask::EXPOSURE,
                         Event::Expose(ExposeEvent {
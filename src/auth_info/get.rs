@@ -2,7 +2,7 @@
 
 use super::{family, AuthInfo};
 use itoa::Buffer;
-use std::{io::Error, ops::ControlFlow};
+use std::io::Error;
 
 #[cfg(feature = "async")]
 use futures_lite::{Stream, StreamExt};
@@ -18,20 +18,20 @@ pub(crate) fn get_auth(
     let display = buffer.format(display);
 
     // TODO: use try_find() once it is stabilized
-    match auths.try_fold((), |(), entry| match entry {
+    match auths.try_for_each(|entry| match entry {
         Ok(entry)
             if addr_match(family, address, entry.family, &entry.address)
                 && display_match(&entry.number, display.as_bytes())
                 && entry.name == b"MIT-MAGIC-COOKIE-1" =>
         {
-            ControlFlow::Break(Ok(entry))
+            Err(Ok(entry))
         }
-        Ok(_) => ControlFlow::Continue(()),
-        Err(e) => ControlFlow::Break(Err(e)),
+        Ok(_) => Ok(()),
+        Err(e) => Err(Err(e)),
     }) {
-        ControlFlow::Break(Ok(b)) => Ok(Some(b)),
-        ControlFlow::Break(Err(e)) => Err(e),
-        ControlFlow::Continue(()) => Ok(None),
+        Err(Ok(b)) => Ok(Some(b)),
+        Err(Err(e)) => Err(e),
+        Ok(()) => Ok(None),
     }
 }
 
@@ -4,13 +4,26 @@ use super::{
     bigreq, input, output, Connection, Display, DisplayBase, PendingItem, RequestInfo, StaticSetup,
     EXT_KEY_SIZE,
 };
-use crate::{auth_info::AuthInfo, event::Event, log_trace, XidGenerator, XID};
+use crate::{auth_info::AuthInfo, event::Event, log_trace, Extension, XidGenerator, XID};
 use alloc::{borrow::Cow, collections::VecDeque};
-use core::num::NonZeroU32;
-use hashbrown::HashMap;
+use core::{mem, num::NonZeroU32};
+use hashbrown::{HashMap, HashSet};
 
 #[cfg(feature = "std")]
 use super::name::NameConnection;
+#[cfg(feature = "std")]
+use super::prelude::*;
+#[cfg(feature = "std")]
+use alloc::{format, string::String};
+
+#[cfg(all(feature = "std", feature = "async"))]
+use crate::xid::XidType;
+#[cfg(all(feature = "std", feature = "async"))]
+use alloc::boxed::Box;
+#[cfg(all(feature = "std", feature = "async"))]
+use core::{future::Future, pin::Pin};
+#[cfg(all(feature = "std", feature = "async"))]
+use futures_lite::future;
 
 #[cfg(feature = "async")]
 use super::{
@@ -21,10 +34,7 @@ use super::{
 #[cfg(feature = "async")]
 use alloc::{vec, vec::Vec};
 #[cfg(feature = "async")]
-use core::{
-    mem,
-    task::{Context, Poll},
-};
+use core::task::{Context, Poll};
 
 /// An implementor of `Display` and `AsyncDisplay` that requires &mut access in order to use.
 ///
@@ -66,6 +76,7 @@ use core::{
 /// [`CellDisplay`]: struct.CellDisplay.html
 /// [`SyncDisplay`]: struct.SyncDisplay.html
 #[derive(Debug)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct BasicDisplay<Conn> {
     // NOTE: every field in this structure is pub(crate), because the implementations of From
     //       for CellDisplay and SyncDisplay need to deconstruct it
@@ -105,12 +116,31 @@ pub struct BasicDisplay<Conn> {
     /// Caches the "WM_PROTOCOLS" atom, which tends to be commonly used.
     pub(crate) wm_protocols_atom: Option<NonZeroU32>,
 
+    /// Caches the `_NET_WM_PING` atom. If this is set, pings are automatically responded to.
+    pub(crate) net_wm_ping_atom: Option<NonZeroU32>,
+    /// Whether pings should be automatically responded to. Unlike the atoms above, this survives a reconnect,
+    /// so that the atoms can be interned again on the new connection.
+    pub(crate) auto_ping: bool,
+
     /// If this is true, we store zero-sized replies as pending requests and check for their synchronization.
     /// If false, this discards their replies. It is much faster than checked mode.
     pub(crate) checked: bool,
 
-    /// A hashmap linking the names of extensions to their opcodes.
-    pub(crate) extensions: HashMap<[u8; EXT_KEY_SIZE], u8>,
+    /// If this is true, consecutive motion events are collapsed into the latest one as they are queued.
+    pub(crate) motion_compression: bool,
+
+    /// A hashmap linking the names of extensions to their opcodes, first events and first errors.
+    pub(crate) extensions: HashMap<[u8; EXT_KEY_SIZE], Extension>,
+    /// The names of extensions that the server is known not to support.
+    pub(crate) absent_extensions: HashSet<[u8; EXT_KEY_SIZE]>,
+
+    /// The number of bytes written to the server since the last call to `take_bytes_written`.
+    pub(crate) bytes_written: usize,
+
+    /// The name this display was created with, if it was created with `DisplayConnection::create`. This is
+    /// used to reconnect to the same display.
+    #[cfg(feature = "std")]
+    display_name: Option<String>,
 
     /// Internal buffer for polling for waiting
     #[cfg(feature = "async")]
@@ -144,9 +174,16 @@ impl<Conn> BasicDisplay<Conn> {
             pending_items: HashMap::with_capacity(4),
             request_number: 1,
             wm_protocols_atom: None,
+            net_wm_ping_atom: None,
+            auto_ping: false,
             checked: cfg!(debug_assertions),
+            motion_compression: false,
             //            context: HashMap::new(),
             extensions: HashMap::with_capacity(8),
+            absent_extensions: HashSet::new(),
+            bytes_written: 0,
+            #[cfg(feature = "std")]
+            display_name: None,
             #[cfg(feature = "async")]
             wait_buffer: None,
             #[cfg(feature = "async")]
@@ -193,6 +230,16 @@ impl<Conn> BasicDisplay<Conn> {
     pub fn connection_mut(&mut self) -> &mut Conn {
         self.connection.as_mut().expect("Poisoned!")
     }
+
+    /// Get a reference to the next event in the event queue, without removing it from the queue.
+    ///
+    /// This never reads from the connection, so it only sees events that have already been
+    /// received. Use `wait_and_peek_event` to read from the connection if the queue is empty.
+    #[inline]
+    #[must_use]
+    pub fn peek_event(&self) -> Option<&Event> {
+        self.event_queue.front()
+    }
 }
 
 impl<Conn: Connection> BasicDisplay<Conn> {
@@ -258,6 +305,25 @@ impl<Conn: Connection> BasicDisplay<Conn> {
         this.xid = xid;
         Ok(this)
     }
+
+    /// Get a reference to the next event in the event queue, without removing it from the queue. If
+    /// the queue is empty, this calls `wait` once in order to populate it, which blocks until the
+    /// server sends something.
+    ///
+    /// Note that the queue may still be empty afterwards, if the server sent a reply or an error
+    /// rather than an event.
+    ///
+    /// # Errors
+    ///
+    /// This function can return any error that `wait` can.
+    #[inline]
+    pub fn wait_and_peek_event(&mut self) -> crate::Result<Option<&Event>> {
+        if self.event_queue.is_empty() {
+            self.wait()?;
+        }
+
+        Ok(self.event_queue.front())
+    }
 }
 
 #[cfg(feature = "async")]
@@ -335,7 +401,7 @@ impl<Conn> DisplayBase for BasicDisplay<Conn> {
 
     #[inline]
     fn push_event(&mut self, event: Event) {
-        self.event_queue.push_back(event);
+        input::push_event_compressed(&mut self.event_queue, event, self.motion_compression);
     }
 
     #[inline]
@@ -413,6 +479,16 @@ impl<Conn> DisplayBase for BasicDisplay<Conn> {
         self.checked = checked;
     }
 
+    #[inline]
+    fn motion_compression(&self) -> bool {
+        self.motion_compression
+    }
+
+    #[inline]
+    fn set_motion_compression(&mut self, compress: bool) {
+        self.motion_compression = compress;
+    }
+
     #[inline]
     fn bigreq_enabled(&self) -> bool {
         self.bigreq_enabled
@@ -424,13 +500,31 @@ impl<Conn> DisplayBase for BasicDisplay<Conn> {
     }
 
     #[inline]
-    fn get_extension_opcode(&mut self, key: &[u8; EXT_KEY_SIZE]) -> Option<u8> {
+    fn get_extension(&mut self, key: &[u8; EXT_KEY_SIZE]) -> Option<Extension> {
         self.extensions.get(key).copied()
     }
 
     #[inline]
-    fn set_extension_opcode(&mut self, key: [u8; EXT_KEY_SIZE], opcode: u8) {
-        self.extensions.insert(key, opcode);
+    fn set_extension(&mut self, key: [u8; EXT_KEY_SIZE], extension: Extension) {
+        self.extensions.insert(key, extension);
+    }
+
+    #[inline]
+    fn extension_absent(&mut self, key: &[u8; EXT_KEY_SIZE]) -> bool {
+        self.absent_extensions.contains(key)
+    }
+
+    #[inline]
+    fn set_extension_absent(&mut self, key: [u8; EXT_KEY_SIZE]) {
+        self.absent_extensions.insert(key);
+    }
+
+    #[inline]
+    fn extension_name(&mut self, major_opcode: u8) -> Option<[u8; EXT_KEY_SIZE]> {
+        self.extensions
+            .iter()
+            .find(|(_, ext)| ext.major_opcode == major_opcode)
+            .map(|(key, _)| *key)
     }
 
     #[inline]
@@ -442,6 +536,17 @@ impl<Conn> DisplayBase for BasicDisplay<Conn> {
     fn set_wm_protocols_atom(&mut self, a: NonZeroU32) {
         self.wm_protocols_atom = Some(a);
     }
+
+    #[inline]
+    fn net_wm_ping_atom(&self) -> Option<NonZeroU32> {
+        self.net_wm_ping_atom
+    }
+
+    #[inline]
+    fn set_net_wm_ping_atom(&mut self, a: Option<NonZeroU32>) {
+        self.net_wm_ping_atom = a;
+        self.auto_ping = a.is_some();
+    }
 }
 
 impl<Connect: Connection> Display for BasicDisplay<Connect> {
@@ -455,11 +560,17 @@ impl<Connect: Connection> Display for BasicDisplay<Connect> {
 
     #[inline]
     fn send_request_raw(&mut self, request_info: RequestInfo) -> crate::Result<u16> {
-        let mut conn = self.connection.take().expect("Poisoned!");
+        let mut conn = output::CountWritten::new(self.connection.take().expect("Poisoned!"));
         let res = output::send_request(self, &mut conn, request_info);
-        self.connection = Some(conn);
+        self.connection = Some(conn.inner);
+        self.bytes_written += conn.written;
         res
     }
+
+    #[inline]
+    fn take_bytes_written(&mut self) -> usize {
+        mem::take(&mut self.bytes_written)
+    }
 }
 
 #[cfg(feature = "async")]
@@ -534,7 +645,8 @@ impl DisplayConnection {
     /// This function instantiates a new [`NameConnection`] using either the name specification string passed
     /// by the user, or the environment variable "DISPLAY" if the `name` parameter is `None`. The
     /// `NameConnection` is either a TCP stream connected to port `60000 + X` on the host, where `X` is the
-    /// display number, or a Unix socket connection to one of the X11 system sockets.
+    /// display number, or a Unix socket connection to one of the X11 system sockets. If the name ends in a
+    /// screen number (e.g. `:0.1`), that screen is used as the default screen; otherwise, screen zero is used.
     ///
     /// Once the `NameConnection` is created, it is passed into [`BasicDisplay::from_connection`] method to
     /// establish the `BasicDisplay`.
@@ -546,8 +658,9 @@ impl DisplayConnection {
     /// # Errors
     ///
     /// In addition to the errors that `from_connection` can return, this method may return
-    /// `BreadError::UnableToParseConnection` if the name string is improperly formatted, or an IO error if a
-    /// system error occurs while trying to connect to the server.
+    /// `BreadError::UnableToParseConnection` if the name string is improperly formatted, `BreadError::Msg` if
+    /// the server does not have the requested screen, or an IO error if a system error occurs while trying to
+    /// connect to the server.
     ///
     /// # Example
     ///
@@ -575,10 +688,119 @@ impl DisplayConnection {
     /// ```
     #[inline]
     pub fn create(name: Option<Cow<'_, str>>) -> crate::Result<Self> {
-        let (connection, screen, display) = NameConnection::connect_internal(name)?;
+        Self::create_internal(name, None)
+    }
+
+    /// Create a new connection to the X server, using the screen with the given index as the default screen
+    /// rather than the one specified by the name. This is useful for servers with several screens (e.g.
+    /// `:0.0` and `:0.1`), where a program wants to open windows on a specific one.
+    ///
+    /// # Errors
+    ///
+    /// In addition to the errors that `create` can return, this returns `BreadError::Msg` if the server does
+    /// not have a screen with the given index.
+    #[inline]
+    pub fn create_on_screen(name: Option<Cow<'_, str>>, screen: usize) -> crate::Result<Self> {
+        Self::create_internal(name, Some(screen))
+    }
+
+    #[inline]
+    fn create_internal(name: Option<Cow<'_, str>>, screen: Option<usize>) -> crate::Result<Self> {
+        let display_name = name.as_ref().map(|name| String::from(&**name));
+        let (connection, name_screen, display) = NameConnection::connect_internal(name)?;
         let (family, address) = connection.peer_addr()?;
         let auth_info = AuthInfo::get(family, &address, display)?.unwrap_or_else(Default::default);
-        Self::from_connection(connection, screen, auth_info)
+        let mut this = Self::from_connection(connection, screen.unwrap_or(name_screen), auth_info)?;
+        check_screen_index(&this)?;
+        this.display_name = display_name;
+        Ok(this)
+    }
+
+    /// Re-open the connection to the display this was created for, and re-run the handshake. If this was
+    /// created without a name, the "DISPLAY" environment variable is read again.
+    ///
+    /// This is meant to be used after the connection drops, e.g. after `BreadError::ClosedConnection` is
+    /// returned. The display name and the user-visible settings survive the new connection: whether or not the
+    /// display is checked, whether motion compression is enabled, and whether pings are answered
+    /// automatically. XIDs, cached atoms, extension information, queued events and pending requests all
+    /// belong to the old connection and are discarded, since the server may have been restarted. If pings were
+    /// answered automatically, the atoms needed for that are interned again on the new connection. Use
+    /// `reconnect_with` to recreate resources once the new connection is established.
+    ///
+    /// # Errors
+    ///
+    /// This function can return any error that `DisplayConnection::create` can, as well as any error that
+    /// interning the ping atoms can. If connecting fails, the display is left unchanged.
+    #[inline]
+    pub fn reconnect(&mut self) -> crate::Result {
+        self.reconnect_with(|_: &mut Self| Ok(()))
+    }
+
+    /// Reconnect to the display, and then run the given policy in order to restore state, such as interning
+    /// atoms and recreating windows. See `reconnect` for more information.
+    ///
+    /// # Errors
+    ///
+    /// This function can return any error that `DisplayConnection::create` can, as well as any error that the
+    /// policy returns. If the policy fails, the display still uses the new connection.
+    #[inline]
+    pub fn reconnect_with<P: ReconnectPolicy<Self>>(&mut self, mut policy: P) -> crate::Result {
+        let mut new = Self::create_internal(
+            self.display_name.as_deref().map(Cow::Borrowed),
+            Some(self.default_screen),
+        )?;
+        new.inherit_settings(self);
+        *self = new;
+        if self.auto_ping {
+            self.enable_auto_ping_response(true)?;
+        }
+        policy.restore(self)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<Conn> BasicDisplay<Conn> {
+    /// Carry the user-visible settings of the display being replaced over to this freshly connected one. The
+    /// cached atoms are left out, since they may not mean the same thing on the new connection.
+    #[inline]
+    fn inherit_settings(&mut self, old: &Self) {
+        self.checked = old.checked;
+        self.motion_compression = old.motion_compression;
+        self.auto_ping = old.auto_ping;
+    }
+}
+
+/// Make sure that the display's default screen actually exists, since a name like `:0.3` can refer to a
+/// screen that the server does not have.
+#[cfg(feature = "std")]
+#[inline]
+fn check_screen_index<Conn>(display: &BasicDisplay<Conn>) -> crate::Result {
+    let count = display.setup.roots.len();
+    if display.default_screen < count {
+        Ok(())
+    } else {
+        Err(crate::BreadError::Msg(format!(
+            "Screen {} does not exist; the server only has {} screen(s)",
+            display.default_screen, count
+        )))
+    }
+}
+
+/// A hook for restoring state after a display reconnects. See `DisplayConnection::reconnect_with` for more
+/// information.
+///
+/// This is implemented for any closure that takes the display.
+#[cfg(feature = "std")]
+pub trait ReconnectPolicy<Dpy: ?Sized> {
+    /// Restore state on the freshly reconnected display, e.g. by re-interning atoms and recreating windows.
+    fn restore(&mut self, dpy: &mut Dpy) -> crate::Result;
+}
+
+#[cfg(feature = "std")]
+impl<Dpy: ?Sized, F: FnMut(&mut Dpy) -> crate::Result> ReconnectPolicy<Dpy> for F {
+    #[inline]
+    fn restore(&mut self, dpy: &mut Dpy) -> crate::Result {
+        self(dpy)
     }
 }
 
@@ -588,10 +810,246 @@ impl AsyncDisplayConnection {
     /// redox. See `DisplayConnection::create()` for more information regarding this function.
     #[inline]
     pub async fn create_async(name: Option<Cow<'_, str>>) -> crate::Result<Self> {
-        let (connection, screen, display) =
+        Self::create_internal_async(name, None).await
+    }
+
+    /// Create a new connection to the X server on the given screen, async redox. See
+    /// `DisplayConnection::create_on_screen()` for more information regarding this function.
+    #[inline]
+    pub async fn create_on_screen_async(
+        name: Option<Cow<'_, str>>,
+        screen: usize,
+    ) -> crate::Result<Self> {
+        Self::create_internal_async(name, Some(screen)).await
+    }
+
+    #[inline]
+    async fn create_internal_async(
+        name: Option<Cow<'_, str>>,
+        screen: Option<usize>,
+    ) -> crate::Result<Self> {
+        let display_name = name.as_ref().map(|name| String::from(&**name));
+        let (connection, name_screen, display) =
             AsyncNameConnection::connect_internal_async(name).await?;
         let (family, address) = connection.peer_addr()?;
         let auth_info = AuthInfo::get(family, &address, display)?.unwrap_or_else(Default::default);
-        Self::from_connection_async(connection, screen, auth_info).await
+        let mut this =
+            Self::from_connection_async(connection, screen.unwrap_or(name_screen), auth_info)
+                .await?;
+        check_screen_index(&this)?;
+        this.display_name = display_name;
+        Ok(this)
+    }
+
+    /// Re-open the connection to the display this was created for, and re-run the handshake, async redox.
+    /// See `DisplayConnection::reconnect` for more information.
+    #[inline]
+    pub async fn reconnect_async(&mut self) -> crate::Result {
+        self.reconnect_with_async(restore_nothing_async).await
+    }
+
+    /// Reconnect to the display, and then run the given policy in order to restore state, async redox. See
+    /// `DisplayConnection::reconnect_with` for more information.
+    #[inline]
+    pub async fn reconnect_with_async<P: AsyncReconnectPolicy<Self>>(
+        &mut self,
+        mut policy: P,
+    ) -> crate::Result {
+        let mut new = Self::create_internal_async(
+            self.display_name.as_deref().map(Cow::Borrowed),
+            Some(self.default_screen),
+        )
+        .await?;
+        new.inherit_settings(self);
+        *self = new;
+        if self.auto_ping {
+            self.intern_ping_atoms_async().await?;
+        }
+        policy.restore_async(self).await
+    }
+
+    /// Intern the atoms used to answer pings, like `enable_auto_ping_response` does.
+    #[inline]
+    async fn intern_ping_atoms_async(&mut self) -> crate::Result {
+        let wpa = self
+            .intern_atom_immediate_async(Cow::Borrowed("WM_PROTOCOLS"), false)
+            .await?;
+        let nwpa = self
+            .intern_atom_immediate_async(Cow::Borrowed("_NET_WM_PING"), false)
+            .await?;
+        self.wm_protocols_atom = Some(NonZeroU32::new(wpa.xid()).ok_or(
+            crate::BreadError::StaticMsg("Unable to intern WM_PROTOCOLS atom"),
+        )?);
+        self.net_wm_ping_atom = Some(NonZeroU32::new(nwpa.xid()).ok_or(
+            crate::BreadError::StaticMsg("Unable to intern _NET_WM_PING atom"),
+        )?);
+        Ok(())
+    }
+}
+
+#[cfg(all(feature = "std", feature = "async"))]
+#[inline]
+fn restore_nothing_async<Dpy: ?Sized>(
+    _dpy: &mut Dpy,
+) -> Pin<Box<dyn Future<Output = crate::Result> + Send + '_>> {
+    Box::pin(future::ready(Ok(())))
+}
+
+/// A hook for restoring state after a display reconnects, async redox. See `ReconnectPolicy` for more
+/// information.
+///
+/// This is implemented for any function that takes the display and returns a boxed future borrowing it, such
+/// as `fn restore(dpy: &mut AsyncDisplayConnection) -> Pin<Box<dyn Future<Output = breadx::Result> + Send +
+/// '_>>`. Since closures cannot name the lifetime of that borrow, state that needs to be carried into the
+/// policy is best stored in a struct that implements this trait.
+#[cfg(all(feature = "std", feature = "async"))]
+pub trait AsyncReconnectPolicy<Dpy: ?Sized> {
+    /// Restore state on the freshly reconnected display, async redox.
+    fn restore_async<'a>(
+        &'a mut self,
+        dpy: &'a mut Dpy,
+    ) -> Pin<Box<dyn Future<Output = crate::Result> + Send + 'a>>;
+}
+
+#[cfg(all(feature = "std", feature = "async"))]
+impl<Dpy: ?Sized, F> AsyncReconnectPolicy<Dpy> for F
+where
+    F: for<'a> FnMut(&'a mut Dpy) -> Pin<Box<dyn Future<Output = crate::Result> + Send + 'a>>,
+{
+    #[inline]
+    fn restore_async<'a>(
+        &'a mut self,
+        dpy: &'a mut Dpy,
+    ) -> Pin<Box<dyn Future<Output = crate::Result> + Send + 'a>> {
+        self(dpy)
+    }
+}
+
+#[test]
+fn bigreq_raises_the_effective_request_limit() {
+    let mut dpy = BasicDisplay::from_connection_internal((), 0);
+    dpy.max_request_len = 65535 * 4;
+    assert_eq!(dpy.effective_max_request_bytes(), 65535 * 4);
+
+    // the limit reported by bigreq includes the extended length field
+    dpy.bigreq_enabled = true;
+    dpy.max_request_len = 4_194_303 * 4;
+    assert_eq!(dpy.effective_max_request_bytes(), 4_194_303 * 4 - 4);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn reconnecting_keeps_the_display_settings() {
+    let mut old = BasicDisplay::from_connection_internal((), 0);
+    old.checked = false;
+    old.motion_compression = true;
+    old.set_wm_protocols_atom(NonZeroU32::new(300).unwrap());
+    old.set_net_wm_ping_atom(NonZeroU32::new(301));
+
+    let mut new = BasicDisplay::from_connection_internal((), 0);
+    new.inherit_settings(&old);
+    assert!(!new.checked);
+    assert!(new.motion_compression);
+    assert!(new.auto_ping);
+
+    // the atoms may be stale on the new connection, so they are interned again instead
+    assert_eq!(new.wm_protocols_atom, None);
+    assert_eq!(new.net_wm_ping_atom, None);
+}
+
+/// A connection that accepts every request and never has anything to read.
+#[cfg(test)]
+struct Sink;
+
+#[cfg(test)]
+impl Connection for Sink {
+    fn send_packet(
+        &mut self,
+        _bytes: &[u8],
+        _fds: &mut alloc::vec::Vec<crate::Fd>,
+    ) -> crate::Result {
+        Ok(())
     }
+
+    fn read_packet(
+        &mut self,
+        _bytes: &mut [u8],
+        _fds: &mut alloc::vec::Vec<crate::Fd>,
+    ) -> crate::Result {
+        unreachable!("nothing should be read")
+    }
+}
+
+#[test]
+fn fire_and_forget_leaves_checked_mode_alone() {
+    use super::DisplayExt;
+    use crate::auto::xproto::NoOperationRequest;
+
+    let mut dpy = BasicDisplay::from_connection_internal(Sink, 0);
+    dpy.max_request_len = 65535 * 4;
+    dpy.checked = true;
+
+    // the forgotten request is not tracked, but the next one still is
+    dpy.fire_and_forget(NoOperationRequest::default()).unwrap();
+    assert!(dpy.checked);
+    assert!(dpy.pending_items.is_empty());
+    let cookie = dpy.send_request(NoOperationRequest::default()).unwrap();
+    assert!(dpy.pending_items.contains_key(&cookie.sequence()));
+}
+
+#[test]
+fn requests_are_named_by_extension_and_minor_opcode() {
+    use super::opcode::request_name;
+
+    let mut dpy = BasicDisplay::from_connection_internal((), 0);
+    dpy.set_extension(
+        super::output::str_to_key("RANDR"),
+        Extension {
+            major_opcode: 140,
+            first_event: 89,
+            first_error: 147,
+        },
+    );
+
+    assert_eq!(request_name(&mut dpy, 12, None), "ConfigureWindow");
+    assert_eq!(request_name(&mut dpy, 21, Some(140)), "RANDR:21");
+    assert_eq!(request_name(&mut dpy, 3, Some(141)), "141:3");
+}
+
+#[test]
+fn take_bytes_written_counts_the_bytes_sent() {
+    use super::DisplayExt;
+    use crate::auto::xproto::NoOperationRequest;
+
+    let mut dpy = BasicDisplay::from_connection_internal(Sink, 0);
+    dpy.max_request_len = 65535 * 4;
+    dpy.checked = false;
+    dpy.send_request(NoOperationRequest::default()).unwrap();
+    dpy.send_request(NoOperationRequest::default()).unwrap();
+
+    assert_eq!(dpy.take_bytes_written(), 8);
+    assert_eq!(dpy.take_bytes_written(), 0);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn from_connection_over_an_in_memory_stream() {
+    use super::{
+        connection::dummy::{PreprogrammedConnection, Transaction},
+        DisplayExt,
+    };
+    use crate::auto::xproto::NoOperationRequest;
+    use core::iter;
+
+    // setup, a denied BIG-REQUESTS query, and then a request of our own
+    let conn = PreprogrammedConnection::normal_setup(iter::once(Transaction::request(
+        NoOperationRequest::default(),
+    )));
+    let mut dpy = BasicDisplay::from_connection(conn, 0, AuthInfo::default()).unwrap();
+    assert!(!dpy.bigreq_enabled);
+    assert_eq!(dpy.max_request_len, 65535 * 4);
+    assert_eq!(dpy.setup().resource_id_base, 58_720_256);
+
+    dpy.checked = false;
+    dpy.send_request(NoOperationRequest::default()).unwrap();
 }
@@ -4,13 +4,14 @@ use super::{
     input, output, BasicDisplay, Connection, Display, DisplayBase, PendingItem, RequestInfo,
     StaticSetup, EXT_KEY_SIZE,
 };
-use crate::{CellXidGenerator, Event, XID};
+use crate::{CellXidGenerator, Event, Extension, XID};
 use alloc::collections::VecDeque;
 use core::{
     cell::{Cell, RefCell},
+    mem,
     num::NonZeroU32,
 };
-use hashbrown::HashMap;
+use hashbrown::{HashMap, HashSet};
 
 #[cfg(feature = "async")]
 use super::{
@@ -20,10 +21,7 @@ use super::{
 #[cfg(feature = "async")]
 use alloc::{vec, vec::Vec};
 #[cfg(feature = "async")]
-use core::{
-    mem,
-    task::{Context, Poll},
-};
+use core::task::{Context, Poll};
 
 /// An implementor of [`Display`] and [`AsyncDisplay`] that uses `Cell` and `RefCell` in order to allow
 /// for immutable use of the `Display`. The primary downside is that it is not `Sync`.
@@ -87,10 +85,17 @@ pub struct CellDisplay<Conn> {
 
     // store the interned atoms
     wm_protocols_atom: Cell<Option<NonZeroU32>>,
+    net_wm_ping_atom: Cell<Option<NonZeroU32>>,
 
     // tell whether or not we care about the output of zero-sized replies
     checked: Cell<bool>,
 
+    // whether or not consecutive motion events are collapsed
+    motion_compression: Cell<bool>,
+
+    // the number of bytes written since the last call to take_bytes_written
+    bytes_written: Cell<usize>,
+
     // used for polling
     #[cfg(feature = "async")]
     wait_buffer: RefCell<Option<WaitBuffer>>,
@@ -104,7 +109,8 @@ struct Data {
     event_queue: VecDeque<Event>,
     pending_items: HashMap<u16, PendingItem>,
     special_event_queues: HashMap<XID, VecDeque<Event>>,
-    extensions: HashMap<[u8; EXT_KEY_SIZE], u8>,
+    extensions: HashMap<[u8; EXT_KEY_SIZE], Extension>,
+    absent_extensions: HashSet<[u8; EXT_KEY_SIZE]>,
     #[cfg(feature = "async")]
     workarounders: Vec<u16>,
 }
@@ -125,8 +131,12 @@ impl<Conn> From<BasicDisplay<Conn>> for CellDisplay<Conn> {
             special_event_queues,
             request_number,
             wm_protocols_atom,
+            net_wm_ping_atom,
             checked,
+            motion_compression,
             extensions,
+            absent_extensions,
+            bytes_written,
             ..
         } = display;
 
@@ -143,12 +153,16 @@ impl<Conn> From<BasicDisplay<Conn>> for CellDisplay<Conn> {
                 pending_items,
                 special_event_queues,
                 extensions,
+                absent_extensions,
                 #[cfg(feature = "async")]
                 workarounders: vec![],
             }),
             request_number: Cell::new(request_number),
             wm_protocols_atom: Cell::new(wm_protocols_atom),
+            net_wm_ping_atom: Cell::new(net_wm_ping_atom),
             checked: Cell::new(checked),
+            motion_compression: Cell::new(motion_compression),
+            bytes_written: Cell::new(bytes_written),
             #[cfg(feature = "async")]
             wait_buffer: RefCell::new(None),
             #[cfg(feature = "async")]
@@ -193,6 +207,33 @@ impl<Conn> CellDisplay<Conn> {
             panic!("Attempted to re-entrantly use connection");
         }
     }
+
+    /// Get a reference to the next event in the event queue, without removing it from the queue.
+    /// This never reads from the connection. See `BasicDisplay::peek_event` for more information.
+    ///
+    /// This takes `&mut self` so that the reference does not have to be tied to a borrow of the
+    /// internal `RefCell`.
+    #[inline]
+    pub fn peek_event(&mut self) -> Option<&Event> {
+        self.inner.get_mut().event_queue.front()
+    }
+}
+
+impl<Connect: Connection> CellDisplay<Connect> {
+    /// Get a reference to the next event in the event queue, calling `wait` once if the queue is
+    /// empty. See `BasicDisplay::wait_and_peek_event` for more information.
+    ///
+    /// # Errors
+    ///
+    /// This function can return any error that `wait` can.
+    #[inline]
+    pub fn wait_and_peek_event(&mut self) -> crate::Result<Option<&Event>> {
+        if self.inner.get_mut().event_queue.is_empty() {
+            self.wait()?;
+        }
+
+        Ok(self.inner.get_mut().event_queue.front())
+    }
 }
 
 impl<Conn> DisplayBase for CellDisplay<Conn> {
@@ -215,7 +256,8 @@ impl<Conn> DisplayBase for CellDisplay<Conn> {
     }
     #[inline]
     fn push_event(&mut self, event: Event) {
-        self.inner.get_mut().event_queue.push_back(event);
+        let compress = self.motion_compression.get();
+        input::push_event_compressed(&mut self.inner.get_mut().event_queue, event, compress);
     }
     #[inline]
     fn pop_event(&mut self) -> Option<Event> {
@@ -285,6 +327,14 @@ impl<Conn> DisplayBase for CellDisplay<Conn> {
         *self.checked.get_mut() = checked;
     }
     #[inline]
+    fn motion_compression(&self) -> bool {
+        self.motion_compression.get()
+    }
+    #[inline]
+    fn set_motion_compression(&mut self, compress: bool) {
+        *self.motion_compression.get_mut() = compress;
+    }
+    #[inline]
     fn bigreq_enabled(&self) -> bool {
         self.bigreq_enabled
     }
@@ -293,12 +343,29 @@ impl<Conn> DisplayBase for CellDisplay<Conn> {
         self.max_request_len
     }
     #[inline]
-    fn get_extension_opcode(&mut self, key: &[u8; EXT_KEY_SIZE]) -> Option<u8> {
+    fn get_extension(&mut self, key: &[u8; EXT_KEY_SIZE]) -> Option<Extension> {
         self.inner.get_mut().extensions.get(key).copied()
     }
     #[inline]
-    fn set_extension_opcode(&mut self, key: [u8; EXT_KEY_SIZE], opcode: u8) {
-        self.inner.get_mut().extensions.insert(key, opcode);
+    fn set_extension(&mut self, key: [u8; EXT_KEY_SIZE], extension: Extension) {
+        self.inner.get_mut().extensions.insert(key, extension);
+    }
+    #[inline]
+    fn extension_absent(&mut self, key: &[u8; EXT_KEY_SIZE]) -> bool {
+        self.inner.get_mut().absent_extensions.contains(key)
+    }
+    #[inline]
+    fn set_extension_absent(&mut self, key: [u8; EXT_KEY_SIZE]) {
+        self.inner.get_mut().absent_extensions.insert(key);
+    }
+    #[inline]
+    fn extension_name(&mut self, major_opcode: u8) -> Option<[u8; EXT_KEY_SIZE]> {
+        self.inner
+            .get_mut()
+            .extensions
+            .iter()
+            .find(|(_, ext)| ext.major_opcode == major_opcode)
+            .map(|(key, _)| *key)
     }
     #[inline]
     fn wm_protocols_atom(&self) -> Option<NonZeroU32> {
@@ -308,6 +375,14 @@ impl<Conn> DisplayBase for CellDisplay<Conn> {
     fn set_wm_protocols_atom(&mut self, a: NonZeroU32) {
         *self.wm_protocols_atom.get_mut() = Some(a);
     }
+    #[inline]
+    fn net_wm_ping_atom(&self) -> Option<NonZeroU32> {
+        self.net_wm_ping_atom.get()
+    }
+    #[inline]
+    fn set_net_wm_ping_atom(&mut self, a: Option<NonZeroU32>) {
+        *self.net_wm_ping_atom.get_mut() = a;
+    }
 }
 
 impl<Connect: Connection> Display for CellDisplay<Connect> {
@@ -326,14 +401,20 @@ impl<Connect: Connection> Display for CellDisplay<Connect> {
     #[inline]
     fn send_request_raw(&mut self, req: RequestInfo) -> crate::Result<u16> {
         self.lock_internal();
-        let mut connection = self.connection.take().expect("Poisoned!");
+        let mut connection = output::CountWritten::new(self.connection.take().expect("Poisoned!"));
 
         let result = output::send_request(self, &mut connection, req);
 
-        self.connection = Some(connection);
+        self.connection = Some(connection.inner);
+        *self.bytes_written.get_mut() += connection.written;
         *self.io_lock.get_mut() = false;
         result
     }
+
+    #[inline]
+    fn take_bytes_written(&mut self) -> usize {
+        mem::take(self.bytes_written.get_mut())
+    }
 }
 
 #[cfg(feature = "async")]
@@ -428,7 +509,8 @@ impl<'a, Conn> DisplayBase for &'a CellDisplay<Conn> {
     }
     #[inline]
     fn push_event(&mut self, event: Event) {
-        self.inner.borrow_mut().event_queue.push_back(event);
+        let compress = self.motion_compression.get();
+        input::push_event_compressed(&mut self.inner.borrow_mut().event_queue, event, compress);
     }
     #[inline]
     fn pop_event(&mut self) -> Option<Event> {
@@ -500,6 +582,14 @@ impl<'a, Conn> DisplayBase for &'a CellDisplay<Conn> {
         self.checked.set(checked);
     }
     #[inline]
+    fn motion_compression(&self) -> bool {
+        self.motion_compression.get()
+    }
+    #[inline]
+    fn set_motion_compression(&mut self, compress: bool) {
+        self.motion_compression.set(compress);
+    }
+    #[inline]
     fn bigreq_enabled(&self) -> bool {
         self.bigreq_enabled
     }
@@ -508,12 +598,29 @@ impl<'a, Conn> DisplayBase for &'a CellDisplay<Conn> {
         self.max_request_len
     }
     #[inline]
-    fn get_extension_opcode(&mut self, key: &[u8; EXT_KEY_SIZE]) -> Option<u8> {
+    fn get_extension(&mut self, key: &[u8; EXT_KEY_SIZE]) -> Option<Extension> {
         self.inner.borrow_mut().extensions.get(key).copied()
     }
     #[inline]
-    fn set_extension_opcode(&mut self, key: [u8; EXT_KEY_SIZE], opcode: u8) {
-        self.inner.borrow_mut().extensions.insert(key, opcode);
+    fn set_extension(&mut self, key: [u8; EXT_KEY_SIZE], extension: Extension) {
+        self.inner.borrow_mut().extensions.insert(key, extension);
+    }
+    #[inline]
+    fn extension_absent(&mut self, key: &[u8; EXT_KEY_SIZE]) -> bool {
+        self.inner.borrow().absent_extensions.contains(key)
+    }
+    #[inline]
+    fn set_extension_absent(&mut self, key: [u8; EXT_KEY_SIZE]) {
+        self.inner.borrow_mut().absent_extensions.insert(key);
+    }
+    #[inline]
+    fn extension_name(&mut self, major_opcode: u8) -> Option<[u8; EXT_KEY_SIZE]> {
+        self.inner
+            .borrow()
+            .extensions
+            .iter()
+            .find(|(_, ext)| ext.major_opcode == major_opcode)
+            .map(|(key, _)| *key)
     }
     #[inline]
     fn wm_protocols_atom(&self) -> Option<NonZeroU32> {
@@ -523,6 +630,14 @@ impl<'a, Conn> DisplayBase for &'a CellDisplay<Conn> {
     fn set_wm_protocols_atom(&mut self, a: NonZeroU32) {
         self.wm_protocols_atom.set(Some(a));
     }
+    #[inline]
+    fn net_wm_ping_atom(&self) -> Option<NonZeroU32> {
+        self.net_wm_ping_atom.get()
+    }
+    #[inline]
+    fn set_net_wm_ping_atom(&mut self, a: Option<NonZeroU32>) {
+        self.net_wm_ping_atom.set(a);
+    }
 }
 
 impl<'a, Connect> Display for &'a CellDisplay<Connect>
@@ -543,12 +658,20 @@ where
     fn send_request_raw(&mut self, req: RequestInfo) -> crate::Result<u16> {
         self.lock_internal_immutable();
 
-        let result =
-            output::send_request(self, &mut self.connection.as_ref().expect("Poisoned!"), req);
+        let mut connection =
+            output::CountWritten::new(self.connection.as_ref().expect("Poisoned!"));
+        let result = output::send_request(self, &mut connection, req);
 
+        self.bytes_written
+            .set(self.bytes_written.get() + connection.written);
         self.io_lock.set(false);
         result
     }
+
+    #[inline]
+    fn take_bytes_written(&mut self) -> usize {
+        self.bytes_written.replace(0)
+    }
 }
 
 #[cfg(feature = "async")]
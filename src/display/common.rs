@@ -3,9 +3,10 @@
 //! Common async implementation functionality between our connection types.
 
 use super::{
-    decode_reply, input, output, AsyncConnection, AsyncDisplay, PendingReply, RequestInfo,
+    decode_reply, input, opcode::request_name, output, AsyncConnection, AsyncDisplay, PendingReply,
+    RequestInfo,
 };
-use crate::{auto::xproto::QueryExtensionRequest, log_debug, log_trace, Fd};
+use crate::{auto::xproto::QueryExtensionRequest, log_debug, log_trace, Extension, Fd};
 use alloc::{vec, vec::Vec};
 use core::{
     iter, mem,
@@ -226,17 +227,23 @@ impl SendBuffer {
                             };
                             // check to ensure our opcode is actually present
                             if !qer.present {
+                                display.set_extension_absent(output::str_to_key(
+                                    req.extension.unwrap(),
+                                ));
                                 self.dig_hole();
                                 return Poll::Ready(Err(crate::BreadError::ExtensionNotPresent(
                                     req.extension.unwrap().into(),
                                 )));
                             }
-                            // insert the opcode into the display
-                            display.set_extension_opcode(
+                            // insert the extension into the display
+                            display.set_extension(
                                 output::str_to_key(req.extension.unwrap()),
-                                qer.major_opcode,
+                                Extension {
+                                    major_opcode: qer.major_opcode,
+                                    first_event: qer.first_event,
+                                    first_error: qer.first_error,
+                                },
                             );
-                            // TODO: first_event and first_error are probably important too
                             break (req, Some(qer.major_opcode));
                         }
 
@@ -279,6 +286,12 @@ impl SendBuffer {
                             let key = output::str_to_key(extension);
                             match display.get_extension_opcode(&key) {
                                 Some(opcode) => break (req, Some(opcode)),
+                                None if display.extension_absent(&key) => {
+                                    self.dig_hole();
+                                    return Poll::Ready(Err(
+                                        crate::BreadError::ExtensionNotPresent(extension.into()),
+                                    ));
+                                }
                                 None => {
                                     // looks like we have to poll for it
                                     *self = SendBuffer::PollingForExt(
@@ -291,7 +304,7 @@ impl SendBuffer {
                                                         ..Default::default()
                                                     },
                                                     display.bigreq_enabled(),
-                                                    display.max_request_len(),
+                                                    display.effective_max_request_bytes(),
                                                 );
 
                                                 let mut qer =
@@ -315,6 +328,10 @@ impl SendBuffer {
         };
 
         let req = output::preprocess_request(display, req);
+        log::trace!(
+            "Sending {} request",
+            request_name(display, req.opcode, opcode)
+        );
         *self = SendBuffer::Init(InnerSendBuffer::new_internal(req, opcode));
 
         Poll::Ready(Ok(()))
@@ -1,12 +1,9 @@
 // MIT/Apache2 License
 
-use super::{create_setup, AsyncConnection};
+use super::{check_setup_status, create_setup, AsyncConnection};
 use crate::{
     auth_info::AuthInfo,
-    auto::{
-        xproto::{Setup, SetupAuthenticate, SetupFailed},
-        AsByteSequence,
-    },
+    auto::{xproto::Setup, AsByteSequence},
     display::StaticSetup,
     xid::XidGenerator,
 };
@@ -134,28 +131,8 @@ impl<'a, C: AsyncConnection + Unpin + ?Sized> Future for EstablishConnectionFutu
                                 };
                             } else {
                                 // figure out if the setup failed
-                                match buffer[0] {
-                                    0 => {
-                                        let failed = match SetupFailed::from_bytes(&buffer) {
-                                            Some(sf) => sf.0.reason.into_owned(),
-                                            None => {
-                                                "Unable to determine why connection failed".into()
-                                            }
-                                        };
-                                        return Err(crate::BreadError::FailedToConnect(failed))
-                                            .into();
-                                    }
-                                    2 => {
-                                        let authenticate = match SetupAuthenticate::from_bytes(&buffer) {
-                                            Some(sa) => sa.0.reason.into_owned(),
-                                            None => "Unable to determine why connection didn't authenticate".into(),
-                                        };
-                                        return Err(crate::BreadError::FailedToAuthorize(
-                                            authenticate,
-                                        ))
-                                        .into();
-                                    }
-                                    _ => {}
+                                if let Err(e) = check_setup_status(&buffer) {
+                                    return Poll::Ready(Err(e));
                                 }
                                 let (setup, _) = match Setup::from_bytes(&buffer) {
                                     Some(s) => s,
@@ -11,7 +11,7 @@ use crate::{
     xid::XidGenerator,
     Fd,
 };
-use alloc::vec::Vec;
+use alloc::{string::String, vec::Vec};
 use core::iter;
 use tinyvec::TinyVec;
 
@@ -47,6 +47,43 @@ pub(crate) fn create_setup(auth: AuthInfo) -> SetupRequest<'static, 'static> {
     }
 }
 
+const SETUP_FAILED: u8 = 0;
+const SETUP_AUTHENTICATE: u8 = 2;
+
+/// Check the status byte of the server's reply to the setup request. If the server refused the connection
+/// (`Failed`) or wants further authentication (`Authenticate`), this returns `BreadError::FailedToConnect`
+/// or `BreadError::FailedToAuthorize` respectively, containing the reason the server gave (e.g. "No protocol
+/// specified" or "Client is not authorized to connect to Server").
+#[inline]
+pub(crate) fn check_setup_status(bytes: &[u8]) -> crate::Result {
+    match bytes[0] {
+        SETUP_FAILED => {
+            let reason = match SetupFailed::from_bytes(bytes) {
+                Some((sf, _)) => clean_reason(&sf.reason),
+                None => "Unable to determine why connection failed".into(),
+            };
+            Err(crate::BreadError::FailedToConnect(reason))
+        }
+        SETUP_AUTHENTICATE => {
+            let reason = match SetupAuthenticate::from_bytes(bytes) {
+                Some((sa, _)) => clean_reason(&sa.reason),
+                None => "Unable to determine why connection didn't authenticate".into(),
+            };
+            Err(crate::BreadError::FailedToAuthorize(reason))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// The reason strings sent by the server are padded to a multiple of four bytes with nulls, and usually end
+/// with a newline. Strip both so that the reason can be displayed as-is.
+#[inline]
+fn clean_reason(reason: &str) -> String {
+    reason
+        .trim_end_matches(|c: char| c == '\0' || c.is_whitespace())
+        .into()
+}
+
 #[inline]
 pub(crate) fn establish_connection<C: Connection + ?Sized>(
     conn: &mut C,
@@ -71,26 +108,41 @@ pub(crate) fn establish_connection<C: Connection + ?Sized>(
     conn.read_packet(&mut bytes[8..], &mut _fds)?;
 
     // figure out if the setup failed
-    match bytes[0] {
-        0 => {
-            let failed = match SetupFailed::from_bytes(&bytes) {
-                Some(sf) => sf.0.reason.into_owned(),
-                None => "Unable to determine why connection failed".into(),
-            };
-            return Err(crate::BreadError::FailedToConnect(failed));
-        }
-        2 => {
-            let authenticate = match SetupAuthenticate::from_bytes(&bytes) {
-                Some(sa) => sa.0.reason.into_owned(),
-                None => "Unable to determine why connection didn't authenticate".into(),
-            };
-            return Err(crate::BreadError::FailedToAuthorize(authenticate));
-        }
-        _ => {}
-    }
+    check_setup_status(&bytes)?;
 
     let (setup, _) =
         StaticSetup::from_bytes(&bytes).ok_or(crate::BreadError::BadObjectRead(Some("Setup")))?;
     let xid = XidGenerator::new(setup.resource_id_base, setup.resource_id_mask);
     Ok((setup, xid))
 }
+
+#[test]
+fn setup_failure_reasons_are_surfaced() {
+    // a "Failed" reply, as sent by a server with no matching authorization
+    let reason = b"No protocol specified\n";
+    let mut bytes = alloc::vec![SETUP_FAILED, reason.len() as u8];
+    bytes.extend_from_slice(&11u16.to_ne_bytes());
+    bytes.extend_from_slice(&0u16.to_ne_bytes());
+    bytes.extend_from_slice(&6u16.to_ne_bytes());
+    bytes.extend_from_slice(reason);
+    bytes.extend_from_slice(&[0, 0]);
+
+    match check_setup_status(&bytes) {
+        Err(crate::BreadError::FailedToConnect(reason)) => {
+            assert_eq!(reason, "No protocol specified");
+        }
+        res => panic!("Expected FailedToConnect, got {:?}", res),
+    }
+
+    // an "Authenticate" reply, whose reason is padded with nulls
+    let mut bytes = alloc::vec![SETUP_AUTHENTICATE, 0, 0, 0, 0, 0];
+    bytes.extend_from_slice(&2u16.to_ne_bytes());
+    bytes.extend_from_slice(b"Denied\0\0");
+
+    match check_setup_status(&bytes) {
+        Err(crate::BreadError::FailedToAuthorize(reason)) => assert_eq!(reason, "Denied"),
+        res => panic!("Expected FailedToAuthorize, got {:?}", res),
+    }
+
+    assert!(check_setup_status(&[1]).is_ok());
+}
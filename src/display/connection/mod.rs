@@ -13,9 +13,9 @@ mod establish;
 
 #[cfg(feature = "async")]
 pub use async_establish::EstablishConnectionFuture;
-#[cfg(feature = "async")]
-pub(crate) use establish::create_setup;
 pub(crate) use establish::establish_connection;
+#[cfg(feature = "async")]
+pub(crate) use establish::{check_setup_status, create_setup};
 
 #[cfg(feature = "async")]
 pub use async_connection::*;
@@ -19,6 +19,11 @@ use std::net::TcpStream;
 use std::os::unix::{io::AsRawFd, net::UnixStream};
 
 /// Synchronous breadx connection.
+///
+/// This trait only relies on `alloc`, so on platforms without the standard library's socket types (for
+/// instance, a microcontroller talking to a remote X server over its own TCP stack) it can be implemented for
+/// the platform's byte stream and passed to `BasicDisplay::from_connection`. Streams that cannot carry file
+/// descriptors should ignore the ones they are given.
 pub trait Connection {
     /// Send a packet across the connection in a blocking manner.
     fn send_packet(&mut self, bytes: &[u8], fds: &mut Vec<Fd>) -> crate::Result;
@@ -133,6 +133,8 @@ fn read_msg_packet(
         return Ok(());
     }
 
+    // the expansion of this macro is up to nix, and newer versions of it use newer std items
+    #[allow(clippy::incompatible_msrv)]
     let mut cmsg = nix::cmsg_space!([Fd; MAX_FDS]);
     let mut datalen = data.len();
     let mut datavec = [IoVec::from_mut_slice(data)];
@@ -0,0 +1,160 @@
+// MIT/Apache2 License
+
+use super::{ExchangeRequestFuture, SendRequestRawFuture, SynchronizeFuture};
+use crate::{
+    auto::xproto::{CreateWindowRequest, GetWindowAttributesRequest, Window},
+    display::{generate_xid, traits::create_simple_window_checked_request, AsyncDisplay},
+    util::take_mut,
+};
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use futures_lite::prelude::*;
+
+/// The future returned by `AsyncDisplayXprotoExt::create_simple_window_checked_async`. It optionally queries
+/// the parent window's attributes, then creates the window, and then synchronizes with the server, so that any
+/// error caused by the request is returned from this future.
+///
+/// Only the `CreateWindow` request itself is tracked; the display's checked mode is never changed.
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you poll or .await them"]
+pub struct CreateWindowCheckedFuture<'a, D: ?Sized> {
+    inner: Inner<'a, D>,
+}
+
+#[derive(Debug)]
+enum Inner<'a, D: ?Sized> {
+    Validating {
+        erf: ExchangeRequestFuture<'a, D, GetWindowAttributesRequest>,
+        cw: CreateWindowRequest,
+    },
+    Creating {
+        srrf: SendRequestRawFuture<'a, D>,
+        wid: Window,
+    },
+    Synchronizing {
+        sf: SynchronizeFuture<'a, D>,
+        wid: Window,
+        seq: u16,
+    },
+    Failed(crate::BreadError),
+    Complete,
+    Hole,
+}
+
+impl<D: ?Sized> Default for Inner<'_, D> {
+    #[inline]
+    fn default() -> Self {
+        Self::Hole
+    }
+}
+impl<D: ?Sized> Unpin for CreateWindowCheckedFuture<'_, D> {}
+
+impl<'a, D: AsyncDisplay + ?Sized> Inner<'a, D> {
+    /// Generate an XID for the window and start creating it.
+    #[inline]
+    fn create(display: &'a mut D, mut cw: CreateWindowRequest) -> crate::Result<Self> {
+        let wid = Window::const_from_xid(generate_xid(display)?);
+        cw.wid = wid;
+
+        let cw = create_simple_window_checked_request(display, cw);
+        Ok(Inner::Creating {
+            srrf: SendRequestRawFuture::run(display, cw),
+            wid,
+        })
+    }
+}
+
+impl<'a, D: AsyncDisplay + ?Sized> CreateWindowCheckedFuture<'a, D> {
+    #[inline]
+    pub(crate) fn run(display: &'a mut D, cw: CreateWindowRequest, validate_parent: bool) -> Self {
+        let inner = if validate_parent {
+            Inner::Validating {
+                erf: ExchangeRequestFuture::run(
+                    display,
+                    GetWindowAttributesRequest {
+                        window: cw.parent,
+                        ..Default::default()
+                    },
+                ),
+                cw,
+            }
+        } else {
+            // if we're out of XIDs, this is reported the first time the future is polled
+            Inner::create(display, cw).unwrap_or_else(Inner::Failed)
+        };
+
+        Self { inner }
+    }
+}
+
+impl<D: AsyncDisplay + ?Sized> Future for CreateWindowCheckedFuture<'_, D> {
+    type Output = crate::Result<Window>;
+
+    #[inline]
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<crate::Result<Window>> {
+        let mut result = None;
+        loop {
+            take_mut(&mut self.inner, |inner| match inner {
+                Inner::Validating { mut erf, cw } => match erf.poll(cx) {
+                    Poll::Pending => {
+                        result = Some(Poll::Pending);
+                        Inner::Validating { erf, cw }
+                    }
+                    Poll::Ready(Err(e)) => {
+                        result = Some(Poll::Ready(Err(e)));
+                        Inner::Complete
+                    }
+                    Poll::Ready(Ok(_)) => {
+                        Inner::create(erf.cannibalize(), cw).unwrap_or_else(Inner::Failed)
+                    }
+                },
+                Inner::Creating { mut srrf, wid } => match srrf.poll(cx) {
+                    Poll::Pending => {
+                        result = Some(Poll::Pending);
+                        Inner::Creating { srrf, wid }
+                    }
+                    Poll::Ready(Err(e)) => {
+                        result = Some(Poll::Ready(Err(e)));
+                        Inner::Complete
+                    }
+                    Poll::Ready(Ok(seq)) => Inner::Synchronizing {
+                        sf: SynchronizeFuture::run(srrf.cannibalize()),
+                        wid,
+                        seq,
+                    },
+                },
+                Inner::Synchronizing { mut sf, wid, seq } => match sf.poll(cx) {
+                    Poll::Pending => {
+                        result = Some(Poll::Pending);
+                        Inner::Synchronizing { sf, wid, seq }
+                    }
+                    Poll::Ready(Err(e)) => {
+                        result = Some(Poll::Ready(Err(e)));
+                        Inner::Complete
+                    }
+                    Poll::Ready(Ok(())) => {
+                        let display = sf.cannibalize();
+                        display.take_pending_request(seq);
+                        result = Some(Poll::Ready(
+                            display.check_for_pending_error(seq).map(|()| wid),
+                        ));
+                        Inner::Complete
+                    }
+                },
+                Inner::Failed(e) => {
+                    result = Some(Poll::Ready(Err(e)));
+                    Inner::Complete
+                }
+                Inner::Complete => panic!("Attempted to poll future past completion"),
+                Inner::Hole => panic!("Cannot poll an empty hole"),
+            });
+
+            if let Some(result) = result.take() {
+                return result;
+            }
+        }
+    }
+}
@@ -0,0 +1,38 @@
+// MIT/Apache2 License
+
+use super::SendRequestRawFuture;
+use crate::display::{forget_sequence, AsyncDisplay, RequestInfo};
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use futures_lite::prelude::*;
+
+/// The future returned by `AsyncDisplayExt::fire_and_forget_async`. It sends a request that is marked as
+/// unchecked, so that the display does not keep track of it.
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you poll or .await them"]
+pub struct FireAndForgetFuture<'a, D: ?Sized> {
+    inner: SendRequestRawFuture<'a, D>,
+}
+
+impl<D: ?Sized> Unpin for FireAndForgetFuture<'_, D> {}
+
+impl<'a, D: AsyncDisplay + ?Sized> FireAndForgetFuture<'a, D> {
+    #[inline]
+    pub(crate) fn run(display: &'a mut D, request: RequestInfo) -> Self {
+        Self {
+            inner: SendRequestRawFuture::run(display, request),
+        }
+    }
+}
+
+impl<D: AsyncDisplay + ?Sized> Future for FireAndForgetFuture<'_, D> {
+    type Output = crate::Result;
+
+    #[inline]
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<crate::Result> {
+        self.inner.poll(cx).map(forget_sequence)
+    }
+}
@@ -0,0 +1,126 @@
+// MIT/Apache2 License
+
+use super::ExchangeRequestFuture;
+use crate::{
+    auto::xproto::{AllowEventsRequest, SetInputFocusRequest, UngrabButtonRequest},
+    display::AsyncDisplay,
+    util::take_mut,
+};
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use futures_lite::prelude::*;
+
+/// The future returned by `AsyncDisplayXprotoExt::handle_focus_click_async`. It focuses the clicked window,
+/// replays the click and then removes the passive grab, one request after another.
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you poll or .await them"]
+pub struct FocusClickFuture<'a, D: ?Sized> {
+    inner: Inner<'a, D>,
+}
+
+#[derive(Debug)]
+enum Inner<'a, D: ?Sized> {
+    Focusing {
+        erf: ExchangeRequestFuture<'a, D, SetInputFocusRequest>,
+        allow: AllowEventsRequest,
+        ungrab: UngrabButtonRequest,
+    },
+    Replaying {
+        erf: ExchangeRequestFuture<'a, D, AllowEventsRequest>,
+        ungrab: UngrabButtonRequest,
+    },
+    Ungrabbing(ExchangeRequestFuture<'a, D, UngrabButtonRequest>),
+    Complete,
+    Hole,
+}
+
+impl<D: ?Sized> Default for Inner<'_, D> {
+    #[inline]
+    fn default() -> Self {
+        Self::Hole
+    }
+}
+impl<D: ?Sized> Unpin for FocusClickFuture<'_, D> {}
+
+impl<'a, D: AsyncDisplay + ?Sized> FocusClickFuture<'a, D> {
+    #[inline]
+    pub(crate) fn run(
+        display: &'a mut D,
+        (focus, allow, ungrab): (
+            SetInputFocusRequest,
+            AllowEventsRequest,
+            UngrabButtonRequest,
+        ),
+    ) -> Self {
+        Self {
+            inner: Inner::Focusing {
+                erf: ExchangeRequestFuture::run(display, focus),
+                allow,
+                ungrab,
+            },
+        }
+    }
+}
+
+impl<D: AsyncDisplay + ?Sized> Future for FocusClickFuture<'_, D> {
+    type Output = crate::Result;
+
+    #[inline]
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<crate::Result> {
+        let mut result = None;
+        loop {
+            take_mut(&mut self.inner, |inner| match inner {
+                Inner::Focusing {
+                    mut erf,
+                    allow,
+                    ungrab,
+                } => match erf.poll(cx) {
+                    Poll::Pending => {
+                        result = Some(Poll::Pending);
+                        Inner::Focusing { erf, allow, ungrab }
+                    }
+                    Poll::Ready(Err(e)) => {
+                        result = Some(Poll::Ready(Err(e)));
+                        Inner::Complete
+                    }
+                    Poll::Ready(Ok(())) => Inner::Replaying {
+                        erf: ExchangeRequestFuture::run(erf.cannibalize(), allow),
+                        ungrab,
+                    },
+                },
+                Inner::Replaying { mut erf, ungrab } => match erf.poll(cx) {
+                    Poll::Pending => {
+                        result = Some(Poll::Pending);
+                        Inner::Replaying { erf, ungrab }
+                    }
+                    Poll::Ready(Err(e)) => {
+                        result = Some(Poll::Ready(Err(e)));
+                        Inner::Complete
+                    }
+                    Poll::Ready(Ok(())) => {
+                        Inner::Ungrabbing(ExchangeRequestFuture::run(erf.cannibalize(), ungrab))
+                    }
+                },
+                Inner::Ungrabbing(mut erf) => match erf.poll(cx) {
+                    Poll::Pending => {
+                        result = Some(Poll::Pending);
+                        Inner::Ungrabbing(erf)
+                    }
+                    Poll::Ready(res) => {
+                        result = Some(Poll::Ready(res));
+                        Inner::Complete
+                    }
+                },
+                Inner::Complete => panic!("Attempted to poll future past completion"),
+                Inner::Hole => panic!("Cannot poll an empty hole"),
+            });
+
+            if let Some(result) = result.take() {
+                return result;
+            }
+        }
+    }
+}
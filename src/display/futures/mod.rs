@@ -15,12 +15,17 @@
 //! the generics on these futures dictates whether or not they are thread safe.
 
 mod and_then;
+mod create_window_checked;
 mod either;
 mod exchange_request;
+mod fire_and_forget;
+mod focus_click;
 mod get_image;
 mod looping;
 mod map;
+mod net_supported;
 mod put_image;
+mod query_extension;
 mod read_packet;
 mod resolve_request;
 mod resolve_request_raw;
@@ -33,12 +38,17 @@ mod wait_for_event;
 mod wait_for_special_event;
 
 pub use and_then::ExchangeXidFuture;
+pub use create_window_checked::CreateWindowCheckedFuture;
 pub use either::EitherFuture;
 pub use exchange_request::ExchangeRequestFuture;
+pub use fire_and_forget::FireAndForgetFuture;
+pub use focus_click::FocusClickFuture;
 pub use get_image::GetImageFuture;
 pub use looping::{WaitLoopFuture, WaitLoopHandler};
 pub use map::MapFuture;
+pub use net_supported::NetSupportedFuture;
 pub use put_image::PutImageFuture;
+pub use query_extension::QueryExtensionFuture;
 pub use read_packet::ReadPacketFuture;
 pub use resolve_request::ResolveRequestFuture;
 pub use resolve_request_raw::{ResolveRequestRawFuture, ResolveRequestRawHandler};
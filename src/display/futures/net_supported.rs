@@ -0,0 +1,113 @@
+// MIT/Apache2 License
+
+use super::ExchangeRequestFuture;
+use crate::{
+    auto::xproto::{Atom, GetPropertyRequest, InternAtomRequest, ATOM_ATOM},
+    display::{traits::read_u32_property, AsyncDisplay},
+    util::take_mut,
+    XidType,
+};
+use alloc::{borrow::Cow, vec::Vec};
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use futures_lite::prelude::*;
+
+/// The future returned by `AsyncDisplayXprotoExt::net_supported_async`. It interns the `_NET_SUPPORTED` atom,
+/// and then reads the list of atoms stored in that property on the default root window.
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you poll or .await them"]
+pub struct NetSupportedFuture<'a, D: ?Sized> {
+    inner: Inner<'a, D>,
+}
+
+#[derive(Debug)]
+enum Inner<'a, D: ?Sized> {
+    Interning(ExchangeRequestFuture<'a, D, InternAtomRequest<'static>>),
+    Reading(ExchangeRequestFuture<'a, D, GetPropertyRequest>),
+    Complete,
+    Hole,
+}
+
+impl<D: ?Sized> Default for Inner<'_, D> {
+    #[inline]
+    fn default() -> Self {
+        Self::Hole
+    }
+}
+impl<D: ?Sized> Unpin for NetSupportedFuture<'_, D> {}
+
+impl<'a, D: AsyncDisplay + ?Sized> NetSupportedFuture<'a, D> {
+    #[inline]
+    pub(crate) fn run(display: &'a mut D) -> Self {
+        Self {
+            inner: Inner::Interning(ExchangeRequestFuture::run(
+                display,
+                InternAtomRequest {
+                    only_if_exists: true,
+                    name: Cow::Borrowed("_NET_SUPPORTED"),
+                    ..Default::default()
+                },
+            )),
+        }
+    }
+}
+
+impl<D: AsyncDisplay + ?Sized> Future for NetSupportedFuture<'_, D> {
+    type Output = crate::Result<Vec<Atom>>;
+
+    #[inline]
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<crate::Result<Vec<Atom>>> {
+        let mut result = None;
+        loop {
+            take_mut(&mut self.inner, |inner| match inner {
+                Inner::Interning(mut erf) => match erf.poll(cx) {
+                    Poll::Pending => {
+                        result = Some(Poll::Pending);
+                        Inner::Interning(erf)
+                    }
+                    Poll::Ready(Err(e)) => {
+                        result = Some(Poll::Ready(Err(e)));
+                        Inner::Complete
+                    }
+                    // the window manager does not support EWMH
+                    Poll::Ready(Ok(repl)) if repl.atom.xid() == 0 => {
+                        result = Some(Poll::Ready(Ok(Vec::new())));
+                        Inner::Complete
+                    }
+                    Poll::Ready(Ok(repl)) => {
+                        let display = erf.cannibalize();
+                        let request = display
+                            .default_root()
+                            .get_entire_property_request(repl.atom, ATOM_ATOM);
+                        Inner::Reading(ExchangeRequestFuture::run(display, request))
+                    }
+                },
+                Inner::Reading(mut erf) => match erf.poll(cx) {
+                    Poll::Pending => {
+                        result = Some(Poll::Pending);
+                        Inner::Reading(erf)
+                    }
+                    Poll::Ready(res) => {
+                        result = Some(Poll::Ready(res.and_then(|gpr| {
+                            Ok(read_u32_property(&gpr, ATOM_ATOM)?
+                                .unwrap_or_default()
+                                .into_iter()
+                                .map(Atom::const_from_xid)
+                                .collect())
+                        })));
+                        Inner::Complete
+                    }
+                },
+                Inner::Complete => panic!("Attempted to poll future past completion"),
+                Inner::Hole => panic!("Cannot poll an empty hole"),
+            });
+
+            if let Some(result) = result.take() {
+                return result;
+            }
+        }
+    }
+}
@@ -0,0 +1,108 @@
+// MIT/Apache2 License
+
+use super::ExchangeRequestFuture;
+use crate::{
+    auto::xproto::QueryExtensionRequest,
+    display::{output::str_to_key, AsyncDisplay, EXT_KEY_SIZE},
+    util::take_mut,
+    BreadError, Extension,
+};
+use alloc::borrow::Cow;
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use futures_lite::prelude::*;
+
+/// The future returned by `AsyncDisplayXprotoExt::query_extension_immediate_async`. Like
+/// `DisplayXprotoExt::query_extension_immediate`, it checks the display's extension cache first and stores the
+/// result there, including extensions that are not present.
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you poll or .await them"]
+pub struct QueryExtensionFuture<'a, 'b, D: ?Sized> {
+    inner: Inner<'a, 'b, D>,
+}
+
+#[derive(Debug)]
+enum Inner<'a, 'b, D: ?Sized> {
+    Cached(crate::Result<Extension>),
+    Querying {
+        erf: ExchangeRequestFuture<'a, D, QueryExtensionRequest<'b>>,
+        name: Cow<'b, str>,
+        key: [u8; EXT_KEY_SIZE],
+    },
+    Complete,
+    Hole,
+}
+
+impl<D: ?Sized> Default for Inner<'_, '_, D> {
+    #[inline]
+    fn default() -> Self {
+        Self::Hole
+    }
+}
+impl<D: ?Sized> Unpin for QueryExtensionFuture<'_, '_, D> {}
+
+impl<'a, 'b, D: AsyncDisplay + ?Sized> QueryExtensionFuture<'a, 'b, D> {
+    #[inline]
+    pub(crate) fn run(display: &'a mut D, name: Cow<'b, str>) -> Self {
+        let key = str_to_key(&name);
+        let inner = if let Some(ext) = display.get_extension(&key) {
+            Inner::Cached(Ok(ext))
+        } else if display.extension_absent(&key) {
+            Inner::Cached(Err(BreadError::ExtensionNotPresent(
+                name.into_owned().into(),
+            )))
+        } else {
+            Inner::Querying {
+                erf: ExchangeRequestFuture::run(
+                    display,
+                    QueryExtensionRequest {
+                        name: name.clone(),
+                        ..Default::default()
+                    },
+                ),
+                name,
+                key,
+            }
+        };
+
+        Self { inner }
+    }
+}
+
+impl<'a, 'b: 'a, D: AsyncDisplay + ?Sized> Future for QueryExtensionFuture<'a, 'b, D> {
+    type Output = crate::Result<Extension>;
+
+    #[inline]
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<crate::Result<Extension>> {
+        let mut result = Poll::Pending;
+        take_mut(&mut self.inner, |inner| match inner {
+            Inner::Cached(res) => {
+                result = Poll::Ready(res);
+                Inner::Complete
+            }
+            Inner::Querying { mut erf, name, key } => match erf.poll(cx) {
+                Poll::Pending => Inner::Querying { erf, name, key },
+                Poll::Ready(Err(e)) => {
+                    result = Poll::Ready(Err(e));
+                    Inner::Complete
+                }
+                Poll::Ready(Ok(qer)) => {
+                    let display = erf.cannibalize();
+                    let res = Extension::from_reply(qer, &name);
+                    match res {
+                        Ok(ext) => display.set_extension(key, ext),
+                        Err(_) => display.set_extension_absent(key),
+                    }
+                    result = Poll::Ready(res);
+                    Inner::Complete
+                }
+            },
+            Inner::Complete => panic!("Attempted to poll future past completion"),
+            Inner::Hole => panic!("Cannot poll an empty hole"),
+        });
+        result
+    }
+}
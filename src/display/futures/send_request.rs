@@ -27,8 +27,11 @@ impl<'a, D: AsyncDisplay + ?Sized, R: Request> SendRequestFuture<'a, D, R> {
     pub(crate) fn run(display: &'a mut D, request: R) -> Self {
         log::info!("Sending a {} to the server", core::any::type_name::<R>());
 
-        let req =
-            RequestInfo::from_request(request, display.bigreq_enabled(), display.max_request_len());
+        let req = RequestInfo::from_request(
+            request,
+            display.bigreq_enabled(),
+            display.effective_max_request_bytes(),
+        );
         Self {
             inner: SendRequestRawFuture::run(display, req),
             _phantom: PhantomData,
@@ -49,7 +49,7 @@ impl<'a, D: AsyncDisplay + ?Sized> SynchronizeFuture<'a, D> {
         let mut gifr = RequestInfo::from_request(
             GetInputFocusRequest::default(),
             display.bigreq_enabled(),
-            display.max_request_len(),
+            display.effective_max_request_bytes(),
         );
         gifr.discard_reply = true;
 
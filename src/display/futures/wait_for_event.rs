@@ -1,18 +1,102 @@
 // MIT/Apache2 License
 
-use super::{WaitLoopFuture, WaitLoopHandler};
+use super::{ExchangeRequestFuture, WaitLoopFuture, WaitLoopHandler};
 use crate::{
-    display::{AsyncDisplay, DisplayBase},
+    auto::xproto::SendEventRequest,
+    display::{traits::net_wm_pong_request, AsyncDisplay, DisplayBase},
     event::Event,
+    util::take_mut,
 };
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use futures_lite::prelude::*;
+
+/// The future returned by `AsyncDisplayExt::wait_for_event_async`. Like `Display::wait_for_event`, it
+/// answers `_NET_WM_PING` messages itself instead of returning them, if automatic ping responses are enabled.
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you poll or .await them"]
+pub struct WaitForEventFuture<'a, D: ?Sized> {
+    inner: Inner<'a, D>,
+}
 
-/// The future returned by `AsyncDisplayExt::wait_for_event_async`.
-pub type WaitForEventFuture<'a, D> = WaitLoopFuture<'a, D, WaitForEventHandler>;
+#[derive(Debug)]
+enum Inner<'a, D: ?Sized> {
+    Waiting(WaitLoopFuture<'a, D, WaitForEventHandler>),
+    Answering(ExchangeRequestFuture<'a, D, SendEventRequest>),
+    Complete(&'a mut D),
+    Hole,
+}
+
+impl<D: ?Sized> Default for Inner<'_, D> {
+    #[inline]
+    fn default() -> Self {
+        Self::Hole
+    }
+}
+impl<D: ?Sized> Unpin for WaitForEventFuture<'_, D> {}
 
 impl<'a, D: ?Sized> WaitForEventFuture<'a, D> {
     #[inline]
     pub(crate) fn run(display: &'a mut D) -> Self {
-        Self::construct(display, WaitForEventHandler)
+        Self {
+            inner: Inner::Waiting(WaitLoopFuture::construct(display, WaitForEventHandler)),
+        }
+    }
+}
+
+impl<D: AsyncDisplay + ?Sized> Future for WaitForEventFuture<'_, D> {
+    type Output = crate::Result<Event>;
+
+    #[inline]
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<crate::Result<Event>> {
+        let mut result = None;
+        loop {
+            take_mut(&mut self.inner, |inner| match inner {
+                Inner::Waiting(mut wlf) => match wlf.poll(cx) {
+                    Poll::Pending => {
+                        result = Some(Poll::Pending);
+                        Inner::Waiting(wlf)
+                    }
+                    Poll::Ready(Err(e)) => {
+                        result = Some(Poll::Ready(Err(e)));
+                        Inner::Complete(wlf.cannibalize())
+                    }
+                    Poll::Ready(Ok(event)) => {
+                        let display = wlf.cannibalize();
+                        if let Some(pong) = net_wm_pong_request(display, &event) {
+                            Inner::Answering(ExchangeRequestFuture::run(display, pong))
+                        } else {
+                            result = Some(Poll::Ready(Ok(event)));
+                            Inner::Complete(display)
+                        }
+                    }
+                },
+                Inner::Answering(mut erf) => match erf.poll(cx) {
+                    Poll::Pending => {
+                        result = Some(Poll::Pending);
+                        Inner::Answering(erf)
+                    }
+                    Poll::Ready(Err(e)) => {
+                        result = Some(Poll::Ready(Err(e)));
+                        Inner::Complete(erf.cannibalize())
+                    }
+                    // the ping has been answered, so go back to waiting for an event
+                    Poll::Ready(Ok(())) => Inner::Waiting(WaitLoopFuture::construct(
+                        erf.cannibalize(),
+                        WaitForEventHandler,
+                    )),
+                },
+                Inner::Complete(..) => panic!("Attempted to poll future past completion"),
+                Inner::Hole => panic!("Cannot poll an empty hole"),
+            });
+
+            if let Some(result) = result.take() {
+                return result;
+            }
+        }
     }
 }
 
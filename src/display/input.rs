@@ -1,11 +1,15 @@
 // MIT/Apache2 License
 
 use super::{
-    Connection, Display, DisplayBase, PendingReply, PendingRequest, PendingRequestFlags,
-    RequestWorkaround,
+    opcode::opcode_name, Connection, Display, DisplayBase, PendingReply, PendingRequest,
+    PendingRequestFlags, RequestWorkaround,
 };
-use crate::{event::Event, log_debug, log_trace, Fd};
-use alloc::{vec, vec::Vec};
+use crate::{
+    auto::{xproto::MotionNotifyEvent, Event as AutoEvent},
+    event::Event,
+    log_debug, log_trace, Fd,
+};
+use alloc::{collections::VecDeque, vec, vec::Vec};
 use core::iter;
 use tinyvec::TinyVec;
 
@@ -57,6 +61,13 @@ pub(crate) fn process_bytes<D: DisplayBase + ?Sized>(
             return Err(crate::BreadError::ClosedConnection);
         }
 
+        log::debug!(
+            "Received error code {} for {} request (sequence {})",
+            bytes[1],
+            opcode_name(display, bytes[10], u16::from_ne_bytes([bytes[8], bytes[9]])),
+            sequence
+        );
+
         let err = crate::BreadError::from_x_error(bytes);
 
         // if we have a pending request with the given sequence, remove that pending
@@ -195,3 +206,72 @@ pub(crate) fn wait<C: Connection + ?Sized, D: Display + ?Sized>(
     log::debug!("Found {} bytes; now processing them...", bytes.len());
     process_bytes(display, bytes, fds)
 }
+
+/// Push an event onto the back of an event queue. If `compress_motion` is true and both the new event and the
+/// event at the back of the queue are `MotionNotify` events for the same window, the new event replaces the
+/// old one instead. Since only the back of the queue is considered, a motion event is never merged with one
+/// that came before any other kind of event.
+#[inline]
+pub(crate) fn push_event_compressed(
+    queue: &mut VecDeque<Event>,
+    event: Event,
+    compress_motion: bool,
+) {
+    if compress_motion {
+        if let (Some(window), Some(last)) = (motion_window(&event), queue.back_mut()) {
+            if motion_window(last) == Some(window) {
+                log_trace!("Compressing consecutive MotionNotify events");
+                *last = event;
+                return;
+            }
+        }
+    }
+
+    queue.push_back(event);
+}
+
+/// If this event is a `MotionNotify` event, get the bytes of the window it was sent to.
+#[inline]
+fn motion_window(event: &Event) -> Option<&[u8]> {
+    const EVENT_WINDOW: core::ops::Range<usize> = 12..16;
+
+    if event.opcode() == MotionNotifyEvent::OPCODE {
+        event
+            .as_byte_slice()
+            .and_then(|bytes| bytes.get(EVENT_WINDOW))
+    } else {
+        None
+    }
+}
+
+#[test]
+fn motion_compression_stops_at_other_events() {
+    fn event(opcode: u8, window: u8) -> Event {
+        let mut bytes: TinyVec<[u8; 32]> = iter::repeat(0).take(32).collect();
+        bytes[0] = opcode;
+        bytes[12] = window;
+        Event::NoneOfTheAbove { opcode, bytes }
+    }
+
+    let motion = MotionNotifyEvent::OPCODE;
+    let button_press = crate::auto::xproto::ButtonPressEvent::OPCODE;
+    let mut queue = VecDeque::new();
+
+    // consecutive motion events for the same window are merged
+    push_event_compressed(&mut queue, event(motion, 1), true);
+    push_event_compressed(&mut queue, event(motion, 1), true);
+    assert_eq!(queue.len(), 1);
+
+    // motion events for a different window are not
+    push_event_compressed(&mut queue, event(motion, 2), true);
+    assert_eq!(queue.len(), 2);
+
+    // nor are motion events on either side of a button press
+    push_event_compressed(&mut queue, event(button_press, 2), true);
+    push_event_compressed(&mut queue, event(motion, 2), true);
+    assert_eq!(queue.len(), 4);
+
+    // nothing is merged when compression is off
+    push_event_compressed(&mut queue, event(motion, 2), false);
+    assert_eq!(queue.len(), 5);
+}
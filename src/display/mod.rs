@@ -40,6 +40,7 @@ use crate::{
     },
     error::BreadError,
     event::Event,
+    extension::Extension,
     util::expand_or_truncate_to_length,
     Fd, Request, XID,
 };
@@ -56,6 +57,7 @@ mod basic;
 pub(crate) mod bigreq;
 mod cell;
 mod connection;
+mod opcode;
 
 pub mod traits;
 // "traits" contains some important types.
@@ -209,6 +211,26 @@ pub trait DisplayBase {
     /// Set whether or not zero-length replies are checked. See `checked` for more information.
     fn set_checked(&mut self, checked: bool);
 
+    /// Whether or not motion events are compressed.
+    ///
+    /// When this is enabled, a `MotionNotify` event that arrives directly after another `MotionNotify` event
+    /// for the same window replaces it in the event queue, so that only the latest pointer position reaches
+    /// `wait_for_event`. This mirrors Xlib's motion compression, and reduces the load on programs that redraw
+    /// after every motion event. Only events that are truly consecutive are merged; a motion event is never
+    /// moved past a button press or any other event. If the full pointer history is needed, it can still be
+    /// retrieved using the `GetMotionEvents` request.
+    ///
+    /// Displays whose event queue cannot be rewritten in place, such as `SyncDisplay`, never compress motion
+    /// events. This is also what the default implementation does.
+    #[inline]
+    fn motion_compression(&self) -> bool {
+        false
+    }
+
+    /// Set whether or not motion events are compressed. See `motion_compression` for more information.
+    #[inline]
+    fn set_motion_compression(&mut self, _compress: bool) {}
+
     /// Whether or not this display uses the `bigreq` extension, whereas requests consisting of over
     /// 262140 bytes are allowed to be sent over the connection.
     fn bigreq_enabled(&self) -> bool;
@@ -216,14 +238,76 @@ pub trait DisplayBase {
     /// The current maximum request length. This is the maximum number of bytes the server can handle at a time.
     fn max_request_len(&self) -> usize;
 
-    /// Get the opcode for an extension.
+    /// The size, in bytes, of the largest request that can currently be sent. Once `bigreq` is enabled, this
+    /// is the larger limit the server reported when it was enabled, minus the four bytes taken up by the
+    /// extended length field that `bigreq` adds to every request.
     ///
-    /// The implementation is expected to keep a map of extension names to the opcodes of said extensions, as
-    /// revealed by the server.
-    fn get_extension_opcode(&mut self, key: &[u8; EXT_KEY_SIZE]) -> Option<u8>;
+    /// Functions that split their data across several requests should use this limit, so that they only
+    /// split when they have to.
+    #[inline]
+    fn effective_max_request_bytes(&self) -> usize {
+        if self.bigreq_enabled() {
+            self.max_request_len().saturating_sub(4)
+        } else {
+            self.max_request_len()
+        }
+    }
+
+    /// Get the cached information (opcode, first event and first error) for an extension.
+    ///
+    /// The implementation is expected to keep a map of extension names to the information about said
+    /// extensions, as revealed by the server. This cache belongs to the connection rather than to any one
+    /// handle to it: displays that can be shared (e.g. `&CellDisplay` or `&SyncDisplay`) keep it behind the
+    /// same interior mutability as the rest of their state, so that every handle sees extensions queried
+    /// by any other and no extension is queried twice.
+    ///
+    /// The default implementation returns `None`, for displays that only cache opcodes through
+    /// `get_extension_opcode` and `set_extension_opcode`.
+    #[inline]
+    fn get_extension(&mut self, _key: &[u8; EXT_KEY_SIZE]) -> Option<Extension> {
+        None
+    }
 
-    /// Set the opcode for an extension. See `get_extension_opcode` for more information.
-    fn set_extension_opcode(&mut self, key: [u8; EXT_KEY_SIZE], opcode: u8);
+    /// Cache the information for an extension. See `get_extension` for more information.
+    ///
+    /// The default implementation only caches the opcode, via `set_extension_opcode`.
+    #[inline]
+    fn set_extension(&mut self, key: [u8; EXT_KEY_SIZE], extension: Extension) {
+        self.set_extension_opcode(key, extension.major_opcode);
+    }
+
+    /// Whether the server is known not to support an extension. Like the extension information itself,
+    /// this is cached so that an extension that is missing is only queried once per connection.
+    ///
+    /// The default implementation does not cache missing extensions, so they are queried every time.
+    #[inline]
+    fn extension_absent(&mut self, _key: &[u8; EXT_KEY_SIZE]) -> bool {
+        false
+    }
+
+    /// Remember that the server does not support an extension. See `extension_absent` for more information.
+    #[inline]
+    fn set_extension_absent(&mut self, _key: [u8; EXT_KEY_SIZE]) {}
+
+    /// Find the name of the cached extension with the given major opcode. This is used to produce readable
+    /// names for requests in logs. See `get_extension` for more information.
+    ///
+    /// The default implementation returns `None`, so requests to extensions are named by their opcodes.
+    #[inline]
+    fn extension_name(&mut self, _major_opcode: u8) -> Option<[u8; EXT_KEY_SIZE]> {
+        None
+    }
+
+    /// Get the opcode for an extension, if it has been cached. See `get_extension` for more information.
+    #[inline]
+    fn get_extension_opcode(&mut self, key: &[u8; EXT_KEY_SIZE]) -> Option<u8> {
+        self.get_extension(key).map(|ext| ext.major_opcode)
+    }
+
+    /// Cache the opcode for an extension. Displays that implement `set_extension` don't need this, so the
+    /// default implementation does nothing. See `get_extension` for more information.
+    #[inline]
+    fn set_extension_opcode(&mut self, _key: [u8; EXT_KEY_SIZE], _opcode: u8) {}
 
     /// Get the `WM_PROTOCOLS` atom, which we cache in the display.
     ///
@@ -234,6 +318,22 @@ pub trait DisplayBase {
     /// Set the `WM_PROTOCOLS` atom. See `wm_protocols_atom` for more information.
     fn set_wm_protocols_atom(&mut self, a: NonZeroU32);
 
+    /// Get the `_NET_WM_PING` atom, if automatically responding to pings is enabled.
+    ///
+    /// When this is set, `wait_for_event` answers `_NET_WM_PING` messages on its own instead of returning
+    /// them. See `DisplayXprotoExt::enable_auto_ping_response` for more information.
+    ///
+    /// The default implementation never stores the atom, so pings are always returned to the caller.
+    #[inline]
+    fn net_wm_ping_atom(&self) -> Option<NonZeroU32> {
+        None
+    }
+
+    /// Set the `_NET_WM_PING` atom, or `None` to stop responding to pings automatically. See
+    /// `net_wm_ping_atom` for more information.
+    #[inline]
+    fn set_net_wm_ping_atom(&mut self, _a: Option<NonZeroU32>) {}
+
     // -- Item-based functions.
 
     /// Insert a pending request into this display. This simply wraps the `PendingRequest` into a `PendingItem`
@@ -314,6 +414,12 @@ pub trait DisplayBase {
         &self.setup().roots
     }
 
+    /// Get the screen with the given index, or `None` if the server does not have that many screens.
+    #[inline]
+    fn screen(&self, index: usize) -> Option<&StaticScreen> {
+        self.setup().roots.get(index)
+    }
+
     /// Get the default screen in this display.
     #[inline]
     fn default_screen(&self) -> &StaticScreen {
@@ -476,6 +582,16 @@ impl<D: DisplayBase + ?Sized> DisplayBase for &mut D {
         (**self).set_checked(checked);
     }
 
+    #[inline]
+    fn motion_compression(&self) -> bool {
+        (**self).motion_compression()
+    }
+
+    #[inline]
+    fn set_motion_compression(&mut self, compress: bool) {
+        (**self).set_motion_compression(compress);
+    }
+
     #[inline]
     fn bigreq_enabled(&self) -> bool {
         (**self).bigreq_enabled()
@@ -486,6 +602,16 @@ impl<D: DisplayBase + ?Sized> DisplayBase for &mut D {
         (**self).max_request_len()
     }
 
+    #[inline]
+    fn get_extension(&mut self, key: &[u8; EXT_KEY_SIZE]) -> Option<Extension> {
+        (**self).get_extension(key)
+    }
+
+    #[inline]
+    fn set_extension(&mut self, key: [u8; EXT_KEY_SIZE], extension: Extension) {
+        (**self).set_extension(key, extension);
+    }
+
     #[inline]
     fn get_extension_opcode(&mut self, key: &[u8; EXT_KEY_SIZE]) -> Option<u8> {
         (**self).get_extension_opcode(key)
@@ -496,6 +622,21 @@ impl<D: DisplayBase + ?Sized> DisplayBase for &mut D {
         (**self).set_extension_opcode(key, opcode);
     }
 
+    #[inline]
+    fn extension_absent(&mut self, key: &[u8; EXT_KEY_SIZE]) -> bool {
+        (**self).extension_absent(key)
+    }
+
+    #[inline]
+    fn set_extension_absent(&mut self, key: [u8; EXT_KEY_SIZE]) {
+        (**self).set_extension_absent(key);
+    }
+
+    #[inline]
+    fn extension_name(&mut self, major_opcode: u8) -> Option<[u8; EXT_KEY_SIZE]> {
+        (**self).extension_name(major_opcode)
+    }
+
     #[inline]
     fn wm_protocols_atom(&self) -> Option<NonZeroU32> {
         (**self).wm_protocols_atom()
@@ -505,6 +646,16 @@ impl<D: DisplayBase + ?Sized> DisplayBase for &mut D {
     fn set_wm_protocols_atom(&mut self, a: NonZeroU32) {
         (**self).set_wm_protocols_atom(a);
     }
+
+    #[inline]
+    fn net_wm_ping_atom(&self) -> Option<NonZeroU32> {
+        (**self).net_wm_ping_atom()
+    }
+
+    #[inline]
+    fn set_net_wm_ping_atom(&mut self, a: Option<NonZeroU32>) {
+        (**self).set_net_wm_ping_atom(a);
+    }
 }
 
 /// A wrapper around a synchronous connection to the X11 server.
@@ -552,6 +703,17 @@ pub trait Display: DisplayBase {
     /// emitted by the internal system.
     fn send_request_raw(&mut self, request_info: RequestInfo) -> crate::Result<u16>;
 
+    /// Get the number of bytes written to the server since the last call to `take_bytes_written`, and reset
+    /// the count to zero.
+    ///
+    /// The count includes the bytes of any `QueryExtension` requests sent on the caller's behalf. This is
+    /// useful for monitoring how much data a program sends to the server, e.g. once per frame. The default
+    /// implementation does not count anything and returns zero.
+    #[inline]
+    fn take_bytes_written(&mut self) -> usize {
+        0
+    }
+
     /// Synchronize this display, ensuring that all data sent across it has been replied to.
     ///
     /// It is occasionally useful to make sure the display has processed all of the information we have sent it.
@@ -570,7 +732,7 @@ pub trait Display: DisplayBase {
         let mut gifr = RequestInfo::from_request(
             GetInputFocusRequest::default(),
             self.bigreq_enabled(),
-            self.max_request_len(),
+            self.effective_max_request_bytes(),
         );
         gifr.discard_reply = true;
         let sequence = self.send_request_raw(gifr)?;
@@ -615,7 +777,11 @@ pub trait Display: DisplayBase {
     fn wait_for_event(&mut self) -> crate::Result<Event> {
         loop {
             match self.pop_event() {
-                Some(e) => return Ok(e),
+                Some(e) => {
+                    if !traits::answer_net_wm_ping(self, &e)? {
+                        return Ok(e);
+                    }
+                }
                 None => self.wait()?,
             }
         }
@@ -644,6 +810,11 @@ impl<D: Display + ?Sized> Display for &mut D {
     fn send_request_raw(&mut self, request_info: RequestInfo) -> crate::Result<u16> {
         (**self).send_request_raw(request_info)
     }
+
+    #[inline]
+    fn take_bytes_written(&mut self) -> usize {
+        (**self).take_bytes_written()
+    }
 }
 
 /// A wrapper around an asynchronous connection to the X server.
@@ -749,12 +920,34 @@ pub trait DisplayExt {
         log::info!("Resolving request...");
         self.resolve_request(tok)
     }
+
+    /// Send a request that has no reply and forget about it. Unlike `exchange_request`, this never keeps
+    /// track of the request or synchronizes with the server, even if the display is in checked mode. This
+    /// is meant for workloads that consist of many void requests in a row, like drawing animations or
+    /// measuring request throughput.
+    ///
+    /// Since the request is not tracked, any protocol error it causes cannot be attributed to it. The error
+    /// still arrives, but it will be returned from whichever call happens to be waiting on the display when
+    /// it does (e.g. `wait_for_event`).
+    ///
+    /// Requests are written to the connection as soon as they are sent, so there is no buffer that needs to
+    /// be flushed.
+    ///
+    /// # Errors
+    ///
+    /// This function returns every error that `send_request_raw` can return. If the server has closed the
+    /// connection, this returns `BreadError::ClosedConnection`.
+    fn fire_and_forget<R: Request<Reply = ()>>(&mut self, request: R) -> crate::Result;
 }
 
 impl<D: Display + ?Sized> DisplayExt for D {
     #[inline]
     fn send_request<R: Request>(&mut self, request: R) -> crate::Result<RequestCookie<R>> {
-        let r = RequestInfo::from_request(request, self.bigreq_enabled(), self.max_request_len());
+        let r = RequestInfo::from_request(
+            request,
+            self.bigreq_enabled(),
+            self.effective_max_request_bytes(),
+        );
         let req_id = self.send_request_raw(r)?;
         Ok(RequestCookie::from_sequence(req_id))
     }
@@ -778,6 +971,44 @@ impl<D: Display + ?Sized> DisplayExt for D {
         let PendingReply { data, fds } = self.resolve_request_raw(token.sequence())?;
         decode_reply::<R>(&data, fds)
     }
+
+    #[inline]
+    fn fire_and_forget<R: Request<Reply = ()>>(&mut self, request: R) -> crate::Result {
+        let mut r = RequestInfo::from_request(
+            request,
+            self.bigreq_enabled(),
+            self.effective_max_request_bytes(),
+        );
+        // send this one request unchecked, so that it is not tracked
+        r.checked = Some(false);
+        forget_sequence(self.send_request_raw(r))
+    }
+}
+
+/// Drop the sequence number of a request sent by `fire_and_forget`, and report a broken connection as
+/// `BreadError::ClosedConnection`.
+#[inline]
+pub(crate) fn forget_sequence(res: crate::Result<u16>) -> crate::Result {
+    match res {
+        Ok(_) => Ok(()),
+        #[cfg(feature = "std")]
+        Err(BreadError::Io(ref io)) if is_closed_connection(io) => {
+            Err(BreadError::ClosedConnection)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Tell whether an IO error means that the server closed the connection.
+#[cfg(feature = "std")]
+#[inline]
+fn is_closed_connection(io: &std::io::Error) -> bool {
+    use std::io::ErrorKind;
+
+    matches!(
+        io.kind(),
+        ErrorKind::BrokenPipe | ErrorKind::ConnectionReset | ErrorKind::ConnectionAborted
+    )
 }
 
 /// Monomorphized methods we can't put into the `AsyncDisplay` trait proper.
@@ -844,6 +1075,14 @@ pub trait AsyncDisplayExt: AsyncDisplay {
         &mut self,
         to_request: F,
     ) -> ExchangeXidFuture<'_, Self, R, U, F>;
+
+    /// Send a request that has no reply and forget about it. This is the async equivalent of the
+    /// [`DisplayExt::fire_and_forget`] function. See that function for more information on what this is
+    /// expected to do.
+    fn fire_and_forget_async<R: Request<Reply = ()>>(
+        &mut self,
+        request: R,
+    ) -> FireAndForgetFuture<'_, Self>;
 }
 
 #[cfg(feature = "async")]
@@ -909,6 +1148,20 @@ impl<D: AsyncDisplay + ?Sized> AsyncDisplayExt for D {
     ) -> ExchangeXidFuture<'_, Self, R, U, F> {
         ExchangeXidFuture::run(self, to_request)
     }
+
+    #[inline]
+    fn fire_and_forget_async<R: Request<Reply = ()>>(
+        &mut self,
+        request: R,
+    ) -> FireAndForgetFuture<'_, Self> {
+        let mut r = RequestInfo::from_request(
+            request,
+            self.bigreq_enabled(),
+            self.effective_max_request_bytes(),
+        );
+        r.checked = Some(false);
+        FireAndForgetFuture::run(self, r)
+    }
 }
 
 /// Request information, monomorphized from the Request trait.
@@ -926,12 +1179,15 @@ pub struct RequestInfo {
     pub(crate) extension: Option<&'static str>,
     pub(crate) expects_fds: bool,
     pub(crate) discard_reply: bool,
+    /// Overrides the display's checked mode for this request only, if set.
+    pub(crate) checked: Option<bool>,
     pub(crate) sequence: Option<u16>,
 }
 
 impl RequestInfo {
     /// Generate a `RequestInfo` given a specific `Request` to generate from, as well as specs on how to convert
-    /// the `RequestInfo` into bytes.
+    /// the `RequestInfo` into bytes. `max_request_len` is the size of the largest request that can be sent,
+    /// as given by `DisplayBase::effective_max_request_bytes`.
     ///
     /// # Panics
     ///
@@ -984,6 +1240,7 @@ impl RequestInfo {
             extension: R::EXTENSION,
             expects_fds: R::REPLY_EXPECTS_FDS,
             discard_reply: false,
+            checked: None,
             sequence: None,
         }
     }
@@ -1135,11 +1392,48 @@ pub enum PollOr<T, D> {
 }
 
 /// Decode the reply bytes and fds into a reply, returning a `BadObjectRead` if it is invalid.
+///
+/// Before decoding, the reply's length field is checked against the number of bytes that were
+/// actually received. After decoding, the number of bytes the decoder consumed is checked against
+/// that length, so that a decoder reading past the end of the reply is caught here instead of
+/// desynchronizing the stream.
 #[inline]
 pub(crate) fn decode_reply<R: Request>(reply: &[u8], fds: Box<[Fd]>) -> crate::Result<R::Reply> {
-    let mut r = R::Reply::from_bytes(reply)
-        .ok_or(BreadError::BadObjectRead(None))?
-        .0;
+    let name = reply_name::<R>();
+
+    let expected = match reply_length(reply) {
+        Some(expected) if expected == reply.len() => expected,
+        Some(expected) => {
+            log::error!(
+                "{} declares a length of {} bytes, but {} bytes were received",
+                name,
+                expected,
+                reply.len()
+            );
+            return Err(BreadError::BadObjectRead(Some(name)));
+        }
+        None => return Err(BreadError::BadObjectRead(Some(name))),
+    };
+
+    let (mut r, consumed) =
+        R::Reply::from_bytes(reply).ok_or(BreadError::BadObjectRead(Some(name)))?;
+
+    if consumed > expected {
+        log::error!(
+            "Decoding {} consumed {} bytes, but the reply is only {} bytes long",
+            name,
+            consumed,
+            expected
+        );
+        return Err(BreadError::BadObjectRead(Some(name)));
+    } else if expected > 32 && consumed + 3 < expected {
+        log::warn!(
+            "Decoding {} only consumed {} of {} bytes",
+            name,
+            consumed,
+            expected
+        );
+    }
 
     if let Some(fdslot) = r.file_descriptors() {
         *fdslot = fds.into_vec();
@@ -1148,6 +1442,25 @@ pub(crate) fn decode_reply<R: Request>(reply: &[u8], fds: Box<[Fd]>) -> crate::R
     Ok(r)
 }
 
+/// The total length in bytes declared by a reply's length field, or `None` if the reply is too
+/// short to be a reply at all.
+#[inline]
+fn reply_length(reply: &[u8]) -> Option<usize> {
+    if reply.len() < 32 {
+        return None;
+    }
+
+    input::additional_bytes(&reply[..8]).map(|ab| 32 + ab)
+}
+
+/// The name of a request's reply type, without the module path.
+#[inline]
+fn reply_name<R: Request>() -> &'static str {
+    let name = core::any::type_name::<R::Reply>();
+    let name = name.split('<').next().unwrap_or(name);
+    name.rsplit("::").next().unwrap_or(name)
+}
+
 /// Convenience function that wraps `generate_xid` and returns an error instead of `None` if it is out of XIDs.
 #[inline]
 pub(crate) fn generate_xid<D: DisplayBase + ?Sized>(display: &mut D) -> crate::Result<XID> {
@@ -1155,3 +1468,41 @@ pub(crate) fn generate_xid<D: DisplayBase + ?Sized>(display: &mut D) -> crate::R
         .generate_xid()
         .ok_or(crate::BreadError::StaticMsg("Ran out of XIDs"))
 }
+
+#[test]
+fn decode_reply_checks_the_declared_length() {
+    use crate::auto::xproto::{GetAtomNameRequest, GetInputFocusRequest};
+    use alloc::vec;
+
+    fn reply(length: u32, total: usize) -> Vec<u8> {
+        let mut bytes = vec![0; total];
+        bytes[0] = 1;
+        bytes[4..8].copy_from_slice(&length.to_ne_bytes());
+        bytes
+    }
+
+    // a reply with no additional data, of exactly the declared length
+    let focus = reply(0, 32);
+    assert!(decode_reply::<GetInputFocusRequest>(&focus, Box::new([])).is_ok());
+
+    // a reply with additional data, of exactly the declared length
+    let mut name = reply(1, 36);
+    name[8..10].copy_from_slice(&2_u16.to_ne_bytes());
+    name[32..34].copy_from_slice(b"WM");
+    let name = decode_reply::<GetAtomNameRequest>(&name, Box::new([])).unwrap();
+    assert_eq!(name.name, "WM");
+
+    // a reply that is too short to even hold the header
+    let truncated = reply(0, 20);
+    assert!(matches!(
+        decode_reply::<GetInputFocusRequest>(&truncated, Box::new([])),
+        Err(BreadError::BadObjectRead(Some("GetInputFocusReply")))
+    ));
+
+    // a reply that declares more data than was received
+    let overlong = reply(2, 32);
+    assert!(matches!(
+        decode_reply::<GetInputFocusRequest>(&overlong, Box::new([])),
+        Err(BreadError::BadObjectRead(Some("GetInputFocusReply")))
+    ));
+}
@@ -0,0 +1,202 @@
+// MIT/Apache2 License
+
+use super::{DisplayBase, EXT_KEY_SIZE};
+use alloc::{borrow::Cow, format, string::String};
+
+/// The names of the requests in the core protocol, indexed by their major opcode. Opcode zero is unused.
+const CORE_REQUEST_NAMES: [&str; 120] = [
+    "",
+    "CreateWindow",
+    "ChangeWindowAttributes",
+    "GetWindowAttributes",
+    "DestroyWindow",
+    "DestroySubwindows",
+    "ChangeSaveSet",
+    "ReparentWindow",
+    "MapWindow",
+    "MapSubwindows",
+    "UnmapWindow",
+    "UnmapSubwindows",
+    "ConfigureWindow",
+    "CirculateWindow",
+    "GetGeometry",
+    "QueryTree",
+    "InternAtom",
+    "GetAtomName",
+    "ChangeProperty",
+    "DeleteProperty",
+    "GetProperty",
+    "ListProperties",
+    "SetSelectionOwner",
+    "GetSelectionOwner",
+    "ConvertSelection",
+    "SendEvent",
+    "GrabPointer",
+    "UngrabPointer",
+    "GrabButton",
+    "UngrabButton",
+    "ChangeActivePointerGrab",
+    "GrabKeyboard",
+    "UngrabKeyboard",
+    "GrabKey",
+    "UngrabKey",
+    "AllowEvents",
+    "GrabServer",
+    "UngrabServer",
+    "QueryPointer",
+    "GetMotionEvents",
+    "TranslateCoordinates",
+    "WarpPointer",
+    "SetInputFocus",
+    "GetInputFocus",
+    "QueryKeymap",
+    "OpenFont",
+    "CloseFont",
+    "QueryFont",
+    "QueryTextExtents",
+    "ListFonts",
+    "ListFontsWithInfo",
+    "SetFontPath",
+    "GetFontPath",
+    "CreatePixmap",
+    "FreePixmap",
+    "CreateGC",
+    "ChangeGC",
+    "CopyGC",
+    "SetDashes",
+    "SetClipRectangles",
+    "FreeGC",
+    "ClearArea",
+    "CopyArea",
+    "CopyPlane",
+    "PolyPoint",
+    "PolyLine",
+    "PolySegment",
+    "PolyRectangle",
+    "PolyArc",
+    "FillPoly",
+    "PolyFillRectangle",
+    "PolyFillArc",
+    "PutImage",
+    "GetImage",
+    "PolyText8",
+    "PolyText16",
+    "ImageText8",
+    "ImageText16",
+    "CreateColormap",
+    "FreeColormap",
+    "CopyColormapAndFree",
+    "InstallColormap",
+    "UninstallColormap",
+    "ListInstalledColormaps",
+    "AllocColor",
+    "AllocNamedColor",
+    "AllocColorCells",
+    "AllocColorPlanes",
+    "FreeColors",
+    "StoreColors",
+    "StoreNamedColor",
+    "QueryColors",
+    "LookupColor",
+    "CreateCursor",
+    "CreateGlyphCursor",
+    "FreeCursor",
+    "RecolorCursor",
+    "QueryBestSize",
+    "QueryExtension",
+    "ListExtensions",
+    "ChangeKeyboardMapping",
+    "GetKeyboardMapping",
+    "ChangeKeyboardControl",
+    "GetKeyboardControl",
+    "Bell",
+    "ChangePointerControl",
+    "GetPointerControl",
+    "SetScreenSaver",
+    "GetScreenSaver",
+    "ChangeHosts",
+    "ListHosts",
+    "SetAccessControl",
+    "SetCloseDownMode",
+    "KillClient",
+    "RotateProperties",
+    "ForceScreenSaver",
+    "SetPointerMapping",
+    "GetPointerMapping",
+    "SetModifierMapping",
+    "GetModifierMapping",
+];
+
+const NO_OPERATION: u8 = 127;
+
+/// Major opcodes at or above this value belong to extensions.
+const FIRST_EXTENSION_OPCODE: u8 = 128;
+
+/// Get a readable name for a request, given its major and minor opcodes. Core requests are named after
+/// their entry in the protocol (e.g. `ConfigureWindow`). Extension requests are only named down to the
+/// extension: they are printed as the extension's name plus the numeric minor opcode (e.g. `RANDR:21`),
+/// provided that the extension is in the display's extension cache. Anything else is printed as raw
+/// numbers.
+#[inline]
+pub(crate) fn opcode_name<D: DisplayBase + ?Sized>(
+    display: &mut D,
+    major: u8,
+    minor: u16,
+) -> Cow<'static, str> {
+    if major < FIRST_EXTENSION_OPCODE {
+        return match major {
+            NO_OPERATION => Cow::Borrowed("NoOperation"),
+            major => match CORE_REQUEST_NAMES.get(usize::from(major)) {
+                Some(name) if !name.is_empty() => Cow::Borrowed(*name),
+                _ => Cow::Owned(format!("{}", major)),
+            },
+        };
+    }
+
+    match display.extension_name(major) {
+        Some(key) => Cow::Owned(format!("{}:{}", key_to_string(&key), minor)),
+        None => Cow::Owned(format!("{}:{}", major, minor)),
+    }
+}
+
+/// Get a readable name for a request that is about to be sent, given its own opcode and the major opcode of
+/// its extension, if it belongs to one. See `opcode_name` for the format.
+#[inline]
+pub(crate) fn request_name<D: DisplayBase + ?Sized>(
+    display: &mut D,
+    request_opcode: u8,
+    ext_opcode: Option<u8>,
+) -> Cow<'static, str> {
+    match ext_opcode {
+        None => opcode_name(display, request_opcode, 0),
+        Some(major) => opcode_name(display, major, u16::from(request_opcode)),
+    }
+}
+
+/// Convert an extension cache key back into the extension's name.
+#[inline]
+fn key_to_string(key: &[u8; EXT_KEY_SIZE]) -> String {
+    let len = key.iter().position(|&b| b == 0).unwrap_or(EXT_KEY_SIZE);
+    String::from_utf8_lossy(&key[..len]).into_owned()
+}
+
+#[test]
+fn core_request_names_match_opcodes() {
+    use crate::{
+        auto::xproto::{ConfigureWindowRequest, GetModifierMappingRequest, GetPropertyRequest},
+        Request,
+    };
+
+    assert_eq!(
+        CORE_REQUEST_NAMES[usize::from(ConfigureWindowRequest::OPCODE)],
+        "ConfigureWindow"
+    );
+    assert_eq!(
+        CORE_REQUEST_NAMES[usize::from(GetPropertyRequest::OPCODE)],
+        "GetProperty"
+    );
+    assert_eq!(
+        CORE_REQUEST_NAMES[usize::from(GetModifierMappingRequest::OPCODE)],
+        "GetModifierMapping"
+    );
+}
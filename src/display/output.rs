@@ -1,13 +1,42 @@
 // MIT/Apache2 License
 
 use super::{
-    decode_reply, input, Connection, Display, DisplayBase, PendingReply, PendingRequestFlags,
-    RequestInfo, RequestWorkaround, EXT_KEY_SIZE,
+    decode_reply, input, opcode::request_name, Connection, Display, DisplayBase, PendingReply,
+    PendingRequestFlags, RequestInfo, RequestWorkaround, EXT_KEY_SIZE,
 };
-use crate::{auto::xproto::QueryExtensionRequest, log_debug, log_trace};
-use alloc::borrow::Cow;
+use crate::{auto::xproto::QueryExtensionRequest, log_debug, log_trace, Extension, Fd};
+use alloc::{borrow::Cow, vec::Vec};
 use core::mem;
 
+/// Wraps a connection and counts the bytes sent across it, so that `Display::take_bytes_written` can report
+/// them. This includes any `QueryExtension` requests that are sent while sending another request.
+#[derive(Debug)]
+pub(crate) struct CountWritten<C> {
+    pub(crate) inner: C,
+    pub(crate) written: usize,
+}
+
+impl<C> CountWritten<C> {
+    #[inline]
+    pub(crate) fn new(inner: C) -> Self {
+        Self { inner, written: 0 }
+    }
+}
+
+impl<C: Connection> Connection for CountWritten<C> {
+    #[inline]
+    fn send_packet(&mut self, bytes: &[u8], fds: &mut Vec<Fd>) -> crate::Result {
+        self.inner.send_packet(bytes, fds)?;
+        self.written += bytes.len();
+        Ok(())
+    }
+
+    #[inline]
+    fn read_packet(&mut self, bytes: &mut [u8], fds: &mut Vec<Fd>) -> crate::Result {
+        self.inner.read_packet(bytes, fds)
+    }
+}
+
 #[inline]
 pub(crate) fn preprocess_request<D: DisplayBase + ?Sized>(
     display: &mut D,
@@ -27,10 +56,11 @@ pub(crate) fn finish_request<D: DisplayBase + ?Sized>(display: &mut D, mut pr: R
     log_trace!("Entering finish_request() with request info: {:?}", &pr);
 
     // data has already been sent over the bandwaves, make sure we acknowledge it
+    let checked = pr.checked.unwrap_or_else(|| display.checked());
     let mut flags = PendingRequestFlags {
         expects_fds: pr.expects_fds,
         discard_reply: pr.discard_reply,
-        checked: pr.zero_sized_reply && display.checked(),
+        checked: pr.zero_sized_reply && checked,
         ..Default::default()
     };
 
@@ -53,7 +83,7 @@ pub(crate) fn finish_request<D: DisplayBase + ?Sized>(display: &mut D, mut pr: R
     let seq = pr.sequence.take().expect("Failed to set sequence number");
     log_debug!("Got sequence number {}", seq);
 
-    if !pr.zero_sized_reply || display.checked() {
+    if !pr.zero_sized_reply || checked {
         log_trace!(
             "Request is neither zero-sized nor is the display not checked, so we expect a reply"
         );
@@ -96,9 +126,8 @@ pub(crate) fn send_request<D: Display + ?Sized, C: Connection + ?Sized>(
             if let Some(opcode) = display.get_extension_opcode(&key) {
                 Some(opcode)
             } else {
-                let opcode = get_ext_opcode(display, connection, extension)?;
-                display.set_extension_opcode(key, opcode);
-                Some(opcode)
+                // get_ext_opcode caches the extension in the display
+                Some(get_ext_opcode(display, connection, extension)?)
             }
         }
     };
@@ -107,6 +136,10 @@ pub(crate) fn send_request<D: Display + ?Sized, C: Connection + ?Sized>(
     let mut req = preprocess_request(display, request_info);
 
     let request_opcode = req.opcode;
+    log::trace!(
+        "Sending {} request",
+        request_name(display, request_opcode, ext_opcode)
+    );
     modify_for_opcode(&mut req.data, request_opcode, ext_opcode);
     log_trace!("We are sending the following request: {:?}", &req);
 
@@ -126,6 +159,11 @@ pub(crate) fn get_ext_opcode<D: Display + ?Sized, C: Connection + ?Sized>(
     extension: &'static str,
 ) -> crate::Result<u8> {
     log_trace!("Entering get_ext_opcode with extension: {}", extension);
+    let key = str_to_key(extension);
+    if display.extension_absent(&key) {
+        return Err(crate::BreadError::ExtensionNotPresent(extension.into()));
+    }
+
     log_debug!(
         "Could not find extension opcode in display's database; sending request to server..."
     );
@@ -139,7 +177,11 @@ pub(crate) fn get_ext_opcode<D: Display + ?Sized, C: Connection + ?Sized>(
     let tok = send_request(
         display,
         conn,
-        RequestInfo::from_request(qer, display.bigreq_enabled(), display.max_request_len()),
+        RequestInfo::from_request(
+            qer,
+            display.bigreq_enabled(),
+            display.effective_max_request_bytes(),
+        ),
     )?;
     log_trace!("Resolving QER...");
     let repl = loop {
@@ -152,19 +194,25 @@ pub(crate) fn get_ext_opcode<D: Display + ?Sized, C: Connection + ?Sized>(
     };
 
     if !repl.present {
+        display.set_extension_absent(key);
         return Err(crate::BreadError::ExtensionNotPresent(extension.into()));
     }
 
     log_debug!("Found opcode for extension: {}", &repl.major_opcode);
-    let key = str_to_key(extension);
-    display.set_extension_opcode(key, repl.major_opcode);
+    display.set_extension(
+        key,
+        Extension {
+            major_opcode: repl.major_opcode,
+            first_event: repl.first_event,
+            first_error: repl.first_error,
+        },
+    );
     log::info!(
         "Loaded extension \"{}\" with first event {} and first error {}",
         extension,
         repl.first_event,
         repl.first_error
     );
-    // TODO: first_event, first_error
     Ok(repl.major_opcode)
 }
 
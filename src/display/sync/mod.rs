@@ -9,15 +9,17 @@ use super::{
 };
 use crate::{
     event::Event,
+    extension::Extension,
     xid::{AtomicXidGenerator, XID},
 };
 use alloc::{collections::VecDeque, sync::Arc};
 use concurrent_queue::ConcurrentQueue;
 use core::{
+    mem,
     num::NonZeroU32,
-    sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+    sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering},
 };
-use dashmap::DashMap;
+use dashmap::{DashMap, DashSet};
 
 #[cfg(feature = "async")]
 use super::{
@@ -25,10 +27,7 @@ use super::{
     AsyncConnection, AsyncDisplay, PollOr, RequestWorkaround,
 };
 #[cfg(feature = "async")]
-use core::{
-    mem,
-    task::{Context, Poll},
-};
+use core::task::{Context, Poll};
 #[cfg(feature = "async")]
 use futures_lite::ready;
 #[cfg(feature = "async")]
@@ -60,6 +59,14 @@ use spinning_top::Spinlock;
 /// let conn: SyncDisplay<_> = conn.into();
 /// ```
 ///
+/// ## Caches
+///
+/// Information the display caches about the server, like the opcodes and first event and error codes of
+/// extensions and the `WM_PROTOCOLS` atom, is stored per connection rather than per handle. Every
+/// `&SyncDisplay` sees the same caches, so an extension queried through one handle is never queried again
+/// through another. The extension cache is a concurrent map, and the atom cache is an atomic integer; neither
+/// requires taking the IO lock.
+///
 /// ## Preferred Usage
 ///
 /// Usage of `SyncDisplay` from a mutable reference is not recommended as, due to the synchronous primitives
@@ -98,19 +105,26 @@ pub struct SyncDisplay<Conn> {
     // map of special event queues
     special_event_queues: DashMap<XID, VecDeque<Event>>,
 
-    // map of extensions to extension opcodes
+    // map of extensions to extension information
     // TODO: this is insert only, there's probably a more optimized version out there
-    extensions: DashMap<[u8; EXT_KEY_SIZE], u8>,
+    extensions: DashMap<[u8; EXT_KEY_SIZE], Extension>,
+
+    // names of extensions that the server does not support
+    absent_extensions: DashSet<[u8; EXT_KEY_SIZE]>,
 
     // request number
     request_number: AtomicU64,
 
     // interned atoms
     wm_protocols_atom: AtomicU32,
+    net_wm_ping_atom: AtomicU32,
 
     // do we care about zero sized replies?
     checked: AtomicBool,
 
+    // the number of bytes written since the last call to take_bytes_written
+    bytes_written: AtomicUsize,
+
     // we don't actually spin on these spinlocks, they're just used for mutable access that we can panic if
     // we get mutual access to it
     #[cfg(feature = "async")]
@@ -134,8 +148,11 @@ impl<Conn> From<BasicDisplay<Conn>> for SyncDisplay<Conn> {
             special_event_queues,
             request_number,
             wm_protocols_atom,
+            net_wm_ping_atom,
             checked,
             extensions,
+            absent_extensions,
+            bytes_written,
             ..
         } = bd;
 
@@ -151,12 +168,18 @@ impl<Conn> From<BasicDisplay<Conn>> for SyncDisplay<Conn> {
             pending_items: Arc::new(pending_items.into_iter().collect()),
             special_event_queues: special_event_queues.into_iter().collect(),
             extensions: extensions.into_iter().collect(),
+            absent_extensions: absent_extensions.into_iter().collect(),
             request_number: AtomicU64::new(request_number),
             wm_protocols_atom: AtomicU32::new(match wm_protocols_atom {
                 None => 0,
                 Some(wpa) => wpa.get(),
             }),
+            net_wm_ping_atom: AtomicU32::new(match net_wm_ping_atom {
+                None => 0,
+                Some(nwpa) => nwpa.get(),
+            }),
             checked: AtomicBool::new(checked),
+            bytes_written: AtomicUsize::new(bytes_written),
             #[cfg(feature = "async")]
             wait_buffer: Spinlock::new(None),
             #[cfg(feature = "async")]
@@ -257,6 +280,7 @@ impl<Conn> DisplayBase for SyncDisplay<Conn> {
         *self.checked.get_mut() = checked;
     }
 
+    // the concurrent event queue can't be rewritten in place, so motion events are never compressed
     #[inline]
     fn bigreq_enabled(&self) -> bool {
         self.bigreq_enabled
@@ -268,13 +292,31 @@ impl<Conn> DisplayBase for SyncDisplay<Conn> {
     }
 
     #[inline]
-    fn get_extension_opcode(&mut self, key: &[u8; EXT_KEY_SIZE]) -> Option<u8> {
+    fn get_extension(&mut self, key: &[u8; EXT_KEY_SIZE]) -> Option<Extension> {
         self.extensions.get(key).as_deref().copied()
     }
 
     #[inline]
-    fn set_extension_opcode(&mut self, key: [u8; EXT_KEY_SIZE], opcode: u8) {
-        self.extensions.insert(key, opcode);
+    fn set_extension(&mut self, key: [u8; EXT_KEY_SIZE], extension: Extension) {
+        self.extensions.insert(key, extension);
+    }
+
+    #[inline]
+    fn extension_absent(&mut self, key: &[u8; EXT_KEY_SIZE]) -> bool {
+        self.absent_extensions.contains(key)
+    }
+
+    #[inline]
+    fn set_extension_absent(&mut self, key: [u8; EXT_KEY_SIZE]) {
+        self.absent_extensions.insert(key);
+    }
+
+    #[inline]
+    fn extension_name(&mut self, major_opcode: u8) -> Option<[u8; EXT_KEY_SIZE]> {
+        self.extensions
+            .iter()
+            .find(|ext| ext.major_opcode == major_opcode)
+            .map(|ext| *ext.key())
     }
 
     #[inline]
@@ -286,6 +328,16 @@ impl<Conn> DisplayBase for SyncDisplay<Conn> {
     fn set_wm_protocols_atom(&mut self, a: NonZeroU32) {
         *self.wm_protocols_atom.get_mut() = a.get();
     }
+
+    #[inline]
+    fn net_wm_ping_atom(&self) -> Option<NonZeroU32> {
+        NonZeroU32::new(self.net_wm_ping_atom.load(Ordering::Relaxed))
+    }
+
+    #[inline]
+    fn set_net_wm_ping_atom(&mut self, a: Option<NonZeroU32>) {
+        *self.net_wm_ping_atom.get_mut() = a.map_or(0, NonZeroU32::get);
+    }
 }
 
 impl<Conn: Connection> Display for SyncDisplay<Conn> {
@@ -304,14 +356,20 @@ impl<Conn: Connection> Display for SyncDisplay<Conn> {
     #[inline]
     fn send_request_raw(&mut self, req: RequestInfo) -> crate::Result<u16> {
         self.io_lock.lock();
-        let mut connection = self.connection.take().expect("Poisoned!");
+        let mut connection = output::CountWritten::new(self.connection.take().expect("Poisoned!"));
 
         let result = output::send_request(self, &mut connection, req);
 
-        self.connection = Some(connection);
+        self.connection = Some(connection.inner);
+        *self.bytes_written.get_mut() += connection.written;
         self.io_lock.unlock();
         result
     }
+
+    #[inline]
+    fn take_bytes_written(&mut self) -> usize {
+        mem::take(self.bytes_written.get_mut())
+    }
 }
 
 #[cfg(feature = "async")]
@@ -487,6 +545,7 @@ impl<'a, Conn> DisplayBase for &'a SyncDisplay<Conn> {
         self.checked.store(checked, Ordering::SeqCst);
     }
 
+    // the concurrent event queue can't be rewritten in place, so motion events are never compressed
     #[inline]
     fn bigreq_enabled(&self) -> bool {
         self.bigreq_enabled
@@ -498,13 +557,31 @@ impl<'a, Conn> DisplayBase for &'a SyncDisplay<Conn> {
     }
 
     #[inline]
-    fn get_extension_opcode(&mut self, key: &[u8; EXT_KEY_SIZE]) -> Option<u8> {
+    fn get_extension(&mut self, key: &[u8; EXT_KEY_SIZE]) -> Option<Extension> {
         self.extensions.get(key).as_deref().copied()
     }
 
     #[inline]
-    fn set_extension_opcode(&mut self, key: [u8; EXT_KEY_SIZE], opcode: u8) {
-        self.extensions.insert(key, opcode);
+    fn set_extension(&mut self, key: [u8; EXT_KEY_SIZE], extension: Extension) {
+        self.extensions.insert(key, extension);
+    }
+
+    #[inline]
+    fn extension_absent(&mut self, key: &[u8; EXT_KEY_SIZE]) -> bool {
+        self.absent_extensions.contains(key)
+    }
+
+    #[inline]
+    fn set_extension_absent(&mut self, key: [u8; EXT_KEY_SIZE]) {
+        self.absent_extensions.insert(key);
+    }
+
+    #[inline]
+    fn extension_name(&mut self, major_opcode: u8) -> Option<[u8; EXT_KEY_SIZE]> {
+        self.extensions
+            .iter()
+            .find(|ext| ext.major_opcode == major_opcode)
+            .map(|ext| *ext.key())
     }
 
     #[inline]
@@ -516,6 +593,17 @@ impl<'a, Conn> DisplayBase for &'a SyncDisplay<Conn> {
     fn set_wm_protocols_atom(&mut self, a: NonZeroU32) {
         self.wm_protocols_atom.store(a.get(), Ordering::SeqCst);
     }
+
+    #[inline]
+    fn net_wm_ping_atom(&self) -> Option<NonZeroU32> {
+        NonZeroU32::new(self.net_wm_ping_atom.load(Ordering::SeqCst))
+    }
+
+    #[inline]
+    fn set_net_wm_ping_atom(&mut self, a: Option<NonZeroU32>) {
+        self.net_wm_ping_atom
+            .store(a.map_or(0, NonZeroU32::get), Ordering::SeqCst);
+    }
 }
 
 impl<'a, Conn> Display for &'a SyncDisplay<Conn>
@@ -534,11 +622,17 @@ where
     #[inline]
     fn send_request_raw(&mut self, req: RequestInfo) -> crate::Result<u16> {
         self.io_lock.lock();
-        let mut conn = self.connection.as_ref().expect("Poisoned");
+        let mut conn = output::CountWritten::new(self.connection.as_ref().expect("Poisoned"));
         let result = output::send_request(self, &mut conn, req);
+        self.bytes_written.fetch_add(conn.written, Ordering::SeqCst);
         self.io_lock.unlock();
         result
     }
+
+    #[inline]
+    fn take_bytes_written(&mut self) -> usize {
+        self.bytes_written.swap(0, Ordering::SeqCst)
+    }
 }
 
 #[cfg(feature = "async")]
@@ -11,6 +11,8 @@ mod dri3;
 mod glx;
 #[cfg(feature = "present")]
 mod present;
+#[cfg(feature = "record")]
+mod record;
 #[cfg(feature = "sync")]
 mod sync;
 #[cfg(feature = "fixes")]
@@ -22,6 +24,8 @@ pub use dri3::*;
 pub use glx::*;
 #[cfg(feature = "present")]
 pub use present::*;
+#[cfg(feature = "record")]
+pub use record::*;
 #[cfg(feature = "sync")]
 pub use sync::*;
 #[cfg(feature = "fixes")]
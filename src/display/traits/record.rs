@@ -0,0 +1,433 @@
+// MIT/Apache2 License
+
+use crate::{
+    auto::record::{
+        ClientSpec, Context, CreateContextRequest, DisableContextRequest, EnableContextReply,
+        EnableContextRequest, FreeContextRequest, HType, QueryVersionRequest, Range, Range8,
+    },
+    display::{
+        decode_reply, generate_xid,
+        input::{additional_bytes, expect_reply},
+        prelude::*,
+        Display, DisplayBase, PendingReply, PendingRequestFlags,
+    },
+    event::Event,
+    extension::ExtensionVersion,
+    BreadError,
+};
+use alloc::vec::Vec;
+
+#[cfg(feature = "async")]
+use crate::{
+    auto::record::QueryVersionReply,
+    display::{
+        futures::{ExchangeRequestFuture, ExchangeXidFuture, MapFuture, SendRequestFuture},
+        AsyncDisplay, RequestCookie,
+    },
+    util::BoxedFnOnce,
+};
+#[cfg(feature = "async")]
+use alloc::boxed::Box;
+
+/// The size of the timestamp that precedes each recorded element if the element header asks for it.
+const TIMESTAMP_SIZE: usize = 4;
+
+/// What kind of data a reply to `EnableContext` contains.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum RecordCategory {
+    /// Protocol data sent by the server, such as events, replies and errors.
+    FromServer,
+    /// Requests sent by a client.
+    FromClient,
+    /// A client connected to the server.
+    ClientStarted,
+    /// A client disconnected from the server.
+    ClientDied,
+    /// The first reply sent after the context is enabled. It contains no data.
+    StartOfData,
+    /// The last reply, sent after the context is disabled. It contains no data.
+    EndOfData,
+}
+
+impl EnableContextReply<'_> {
+    /// Get the category of the recorded data, or `None` if the server sent an unknown category.
+    #[inline]
+    #[must_use]
+    pub fn record_category(&self) -> Option<RecordCategory> {
+        Some(match self.category {
+            0 => RecordCategory::FromServer,
+            1 => RecordCategory::FromClient,
+            2 => RecordCategory::ClientStarted,
+            3 => RecordCategory::ClientDied,
+            4 => RecordCategory::StartOfData,
+            5 => RecordCategory::EndOfData,
+            _ => return None,
+        })
+    }
+
+    /// Decode the events contained within this reply. Replies and errors that were recorded alongside them
+    /// are skipped. If this reply does not contain data sent by the server, this returns an empty list.
+    ///
+    /// # Errors
+    ///
+    /// Returns `BreadError::BadObjectRead` if the data is truncated, or if it is in a different byte order
+    /// than the one used by this client.
+    #[inline]
+    pub fn recorded_events(&self) -> crate::Result<Vec<Event>> {
+        if self.record_category() != Some(RecordCategory::FromServer) {
+            return Ok(Vec::new());
+        }
+        if self.client_swapped {
+            log::error!("Recorded data is byte-swapped, which is not supported");
+            return Err(BreadError::BadObjectRead(Some(
+                "byte-swapped recorded data",
+            )));
+        }
+
+        let header = HType {
+            inner: i32::from(self.element_header),
+        };
+        let prefix = if header.from_server_time() {
+            TIMESTAMP_SIZE
+        } else {
+            0
+        };
+
+        let mut events = Vec::new();
+        let mut data = &*self.data;
+        while !data.is_empty() {
+            let element = data
+                .get(prefix..prefix + 32)
+                .ok_or(BreadError::BadObjectRead(Some("recorded element")))?;
+            let len = 32 + additional_bytes(element).unwrap_or(0);
+            let element = data
+                .get(prefix..prefix + len)
+                .ok_or(BreadError::BadObjectRead(Some("recorded element")))?;
+
+            // replies and errors are left out; everything else is an event
+            if element[0] > 1 {
+                events.push(Event::from_bytes(element.iter().copied().collect())?);
+            }
+
+            data = &data[prefix + len..];
+        }
+
+        Ok(events)
+    }
+}
+
+impl Range {
+    /// Create a range that records the device events (key presses, pointer motion, etc.) with opcodes from
+    /// `first` to `last`, inclusive.
+    #[inline]
+    #[must_use]
+    pub fn device_events(first: u8, last: u8) -> Self {
+        Self {
+            device_events: Range8 { first, last },
+            ..Default::default()
+        }
+    }
+}
+
+/// A context that has been enabled, and is sending the data it records. Use `next_reply` to receive it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RecordStream {
+    context: Context,
+    sequence: u16,
+    finished: bool,
+}
+
+impl RecordStream {
+    /// The context that is being recorded.
+    #[inline]
+    #[must_use]
+    pub fn context(&self) -> Context {
+        self.context
+    }
+
+    /// Whether or not the server has sent the last reply for this context.
+    #[inline]
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Wait for the next reply containing recorded data. Returns `None` once the context has been disabled and
+    /// the server has sent every reply.
+    #[inline]
+    pub fn next_reply<Dpy: Display + ?Sized>(
+        &mut self,
+        dpy: &mut Dpy,
+    ) -> crate::Result<Option<EnableContextReply<'static>>> {
+        if self.finished {
+            return Ok(None);
+        }
+
+        let reply = dpy.resolve_request_raw(self.sequence)?;
+        self.process_reply(dpy, reply).map(Some)
+    }
+
+    /// Wait for the next reply containing recorded data, async redox.
+    #[cfg(feature = "async")]
+    #[inline]
+    pub async fn next_reply_async<Dpy: AsyncDisplay + ?Sized>(
+        &mut self,
+        dpy: &mut Dpy,
+    ) -> crate::Result<Option<EnableContextReply<'static>>> {
+        if self.finished {
+            return Ok(None);
+        }
+
+        let reply = dpy.resolve_request_raw_async(self.sequence).await?;
+        self.process_reply(dpy, reply).map(Some)
+    }
+
+    /// Decode a reply to `EnableContext`.
+    #[inline]
+    fn process_reply<Dpy: DisplayBase + ?Sized>(
+        &mut self,
+        dpy: &mut Dpy,
+        reply: PendingReply,
+    ) -> crate::Result<EnableContextReply<'static>> {
+        let PendingReply { data, fds } = reply;
+        let reply = decode_reply::<EnableContextRequest>(&data, fds)?;
+
+        // every reply to EnableContext shares its sequence number, so keep expecting replies until the
+        // server tells us that there are none left
+        if reply.record_category() == Some(RecordCategory::EndOfData) {
+            self.finished = true;
+        } else {
+            expect_reply(dpy, self.sequence, PendingRequestFlags::default());
+        }
+
+        Ok(reply)
+    }
+}
+
+/// Requests for the RECORD extension, which lets a client record the protocol data that other clients send
+/// and receive. See [`AsyncDisplayRecordExt`] for the async equivalents.
+pub trait DisplayRecordExt: Display {
+    /// Query the version of the RECORD extension supported by the server.
+    #[inline]
+    fn record_query_version(&mut self, major: u16, minor: u16) -> crate::Result<ExtensionVersion> {
+        let reply = self.exchange_request(QueryVersionRequest {
+            major_version: major,
+            minor_version: minor,
+            ..Default::default()
+        })?;
+        Ok(ExtensionVersion {
+            major: reply.major_version.into(),
+            minor: reply.minor_version.into(),
+        })
+    }
+
+    /// Create a context that records the protocol data in `ranges` for the given clients. To record all input
+    /// on the server, use `Cs::AllClients` as the client and `Range::device_events` as the range.
+    #[inline]
+    fn record_create_context(
+        &mut self,
+        clients: Vec<ClientSpec>,
+        ranges: Vec<Range>,
+    ) -> crate::Result<Context> {
+        let xid = Context::const_from_xid(generate_xid(self)?);
+        self.exchange_request(CreateContextRequest {
+            context: xid,
+            client_specs: clients.into(),
+            ranges: ranges.into(),
+            ..Default::default()
+        })?;
+        Ok(xid)
+    }
+
+    /// Enable a context, causing the server to start sending the data it records.
+    ///
+    /// The server will not process any other requests sent over this connection until the context is
+    /// disabled, so the context should be enabled on a separate connection from the one used to create,
+    /// disable and free it.
+    #[inline]
+    fn record_enable_context(&mut self, context: Context) -> crate::Result<RecordStream> {
+        let tok = self.send_request(EnableContextRequest {
+            context,
+            ..Default::default()
+        })?;
+        Ok(RecordStream {
+            context,
+            sequence: tok.sequence(),
+            finished: false,
+        })
+    }
+
+    /// Disable a context, causing the server to stop sending recorded data over the connection that enabled
+    /// it.
+    #[inline]
+    fn record_disable_context(&mut self, context: Context) -> crate::Result {
+        self.exchange_request(DisableContextRequest {
+            context,
+            ..Default::default()
+        })
+    }
+
+    /// Free a context. If it is enabled, it is disabled first.
+    #[inline]
+    fn record_free_context(&mut self, context: Context) -> crate::Result {
+        self.exchange_request(FreeContextRequest {
+            context,
+            ..Default::default()
+        })
+    }
+}
+
+impl<D: Display + ?Sized> DisplayRecordExt for D {}
+
+/// Requests for the RECORD extension, async redox. See [`DisplayRecordExt`] for more information.
+#[cfg(feature = "async")]
+pub trait AsyncDisplayRecordExt: AsyncDisplay {
+    /// Query the version of the RECORD extension supported by the server, async redox.
+    #[allow(clippy::type_complexity)]
+    #[inline]
+    fn record_query_version_async(
+        &mut self,
+        major: u16,
+        minor: u16,
+    ) -> MapFuture<
+        ExchangeRequestFuture<'_, Self, QueryVersionRequest>,
+        fn(crate::Result<QueryVersionReply>) -> crate::Result<ExtensionVersion>,
+    > {
+        MapFuture::run(
+            self.exchange_request_async(QueryVersionRequest {
+                major_version: major,
+                minor_version: minor,
+                ..Default::default()
+            }),
+            |repl| {
+                repl.map(|repl| ExtensionVersion {
+                    major: repl.major_version.into(),
+                    minor: repl.minor_version.into(),
+                })
+            },
+        )
+    }
+
+    /// Create a recording context, async redox.
+    #[allow(clippy::type_complexity)]
+    #[inline]
+    fn record_create_context_async(
+        &mut self,
+        clients: Vec<ClientSpec>,
+        ranges: Vec<Range>,
+    ) -> ExchangeXidFuture<
+        '_,
+        Self,
+        CreateContextRequest<'static, 'static>,
+        Context,
+        BoxedFnOnce<Context, CreateContextRequest<'static, 'static>>,
+    > {
+        let mut ccr = CreateContextRequest {
+            context: Context::const_from_xid(0),
+            client_specs: clients.into(),
+            ranges: ranges.into(),
+            ..Default::default()
+        };
+        self.exchange_xid_async(Box::new(move |context| {
+            ccr.context = context;
+            ccr
+        }))
+    }
+
+    /// Enable a recording context, async redox. See `record_enable_context` for more information.
+    #[allow(clippy::type_complexity)]
+    #[inline]
+    fn record_enable_context_async(
+        &mut self,
+        context: Context,
+    ) -> MapFuture<
+        SendRequestFuture<'_, Self, EnableContextRequest>,
+        BoxedFnOnce<
+            crate::Result<RequestCookie<EnableContextRequest>>,
+            crate::Result<RecordStream>,
+        >,
+    > {
+        MapFuture::run(
+            self.send_request_async(EnableContextRequest {
+                context,
+                ..Default::default()
+            }),
+            Box::new(move |tok| {
+                tok.map(|tok| RecordStream {
+                    context,
+                    sequence: tok.sequence(),
+                    finished: false,
+                })
+            }),
+        )
+    }
+
+    /// Disable a recording context, async redox.
+    #[inline]
+    fn record_disable_context_async(
+        &mut self,
+        context: Context,
+    ) -> ExchangeRequestFuture<'_, Self, DisableContextRequest> {
+        self.exchange_request_async(DisableContextRequest {
+            context,
+            ..Default::default()
+        })
+    }
+
+    /// Free a recording context, async redox.
+    #[inline]
+    fn record_free_context_async(
+        &mut self,
+        context: Context,
+    ) -> ExchangeRequestFuture<'_, Self, FreeContextRequest> {
+        self.exchange_request_async(FreeContextRequest {
+            context,
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(feature = "async")]
+impl<D: AsyncDisplay + ?Sized> AsyncDisplayRecordExt for D {}
+
+#[test]
+fn recorded_events_are_decoded() {
+    use crate::auto::{
+        xproto::{ButtonPressEvent, KeyPressEvent},
+        Event as _,
+    };
+
+    fn element(opcode: u8, time: u32, extra: u32) -> Vec<u8> {
+        let mut bytes = alloc::vec![0; TIMESTAMP_SIZE + 32 + extra as usize * 4];
+        bytes[..TIMESTAMP_SIZE].copy_from_slice(&time.to_ne_bytes());
+        bytes[TIMESTAMP_SIZE] = opcode;
+        bytes[TIMESTAMP_SIZE + 4..TIMESTAMP_SIZE + 8].copy_from_slice(&extra.to_ne_bytes());
+        bytes
+    }
+
+    // a key press, a reply with four extra bytes, and a button press, each preceded by a server timestamp
+    let data = [
+        element(KeyPressEvent::OPCODE, 1, 0),
+        element(1, 2, 1),
+        element(ButtonPressEvent::OPCODE, 3, 0),
+    ]
+    .concat();
+    let reply = EnableContextReply {
+        category: 0,
+        element_header: HType::FROM_SERVER_TIME.inner as u8,
+        data: data.into(),
+        ..Default::default()
+    };
+
+    let events = reply.recorded_events().unwrap();
+    assert_eq!(events.len(), 2);
+    assert!(matches!(events[0], Event::KeyPress(_)));
+    assert!(matches!(events[1], Event::ButtonPress(_)));
+
+    // truncated data is rejected instead of read past
+    let reply = EnableContextReply {
+        data: alloc::vec![KeyPressEvent::OPCODE; 16].into(),
+        ..reply
+    };
+    assert!(reply.recorded_events().is_err());
+}
@@ -0,0 +1,255 @@
+// MIT/Apache2 License
+
+//! Provides `CachedWindow`, a window that remembers its geometry and event mask.
+
+use super::Geometry;
+use crate::{
+    auto::xproto::{EventMask, Window},
+    display::Display,
+    Event,
+};
+
+#[cfg(feature = "async")]
+use crate::display::AsyncDisplay;
+
+/// A wrapper around a `Window` that caches its geometry and event mask, in order to avoid repeated
+/// `GetGeometry` round trips in redraw-heavy programs.
+///
+/// The cache is kept up to date by feeding events to `note_event`. A `ConfigureNotify` for the
+/// window updates the cached geometry, while events that leave the geometry in an unknown state
+/// (e.g. a synthetic `ConfigureNotify`, whose coordinates are relative to the root rather than the
+/// parent) clear it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CachedWindow {
+    window: Window,
+    geometry: Option<Geometry>,
+    event_mask: Option<EventMask>,
+}
+
+impl From<Window> for CachedWindow {
+    #[inline]
+    fn from(window: Window) -> Self {
+        Self::new(window)
+    }
+}
+
+impl CachedWindow {
+    /// Wrap a window. Nothing is cached to begin with.
+    #[inline]
+    #[must_use]
+    pub const fn new(window: Window) -> Self {
+        Self {
+            window,
+            geometry: None,
+            event_mask: None,
+        }
+    }
+
+    /// The underlying window.
+    #[inline]
+    #[must_use]
+    pub const fn window(&self) -> Window {
+        self.window
+    }
+
+    /// The cached geometry of this window, if there is any.
+    #[inline]
+    #[must_use]
+    pub const fn cached_geometry(&self) -> Option<Geometry> {
+        self.geometry
+    }
+
+    /// The cached width and height of this window, if there are any.
+    #[inline]
+    #[must_use]
+    pub fn cached_size(&self) -> Option<(u16, u16)> {
+        self.geometry.map(|g| (g.width, g.height))
+    }
+
+    /// The cached event mask of this window, if there is one. It is cached by `set_event_mask`, or by
+    /// `event_mask` when it has to ask the server.
+    #[inline]
+    #[must_use]
+    pub fn cached_event_mask(&self) -> Option<EventMask> {
+        self.event_mask
+    }
+
+    /// Forget everything cached about this window.
+    #[inline]
+    pub fn invalidate(&mut self) {
+        self.geometry = None;
+        self.event_mask = None;
+    }
+
+    /// Update the cache using an event. Events that do not concern this window are ignored.
+    #[inline]
+    pub fn note_event(&mut self, event: &Event) {
+        match event {
+            Event::ConfigureNotify(cne) if cne.window == self.window => {
+                if cne.event_type & 0x80 != 0 {
+                    self.geometry = None;
+                } else if let Some(ref mut geometry) = self.geometry {
+                    geometry.x = cne.x;
+                    geometry.y = cne.y;
+                    geometry.width = cne.width;
+                    geometry.height = cne.height;
+                    geometry.border_width = cne.border_width;
+                }
+            }
+            Event::ResizeRequest(rre) if rre.window == self.window => {
+                self.geometry = None;
+            }
+            Event::ReparentNotify(rne) if rne.window == self.window => {
+                self.geometry = None;
+            }
+            Event::DestroyNotify(dne) if dne.window == self.window => {
+                self.invalidate();
+            }
+            _ => {}
+        }
+    }
+
+    /// Get the geometry of this window, only querying the server if it is not already cached.
+    #[inline]
+    pub fn geometry<Dpy: Display + ?Sized>(&mut self, dpy: &mut Dpy) -> crate::Result<Geometry> {
+        if let Some(geometry) = self.geometry {
+            return Ok(geometry);
+        }
+
+        let geometry = self.window.geometry_immediate(dpy)?;
+        self.geometry = Some(geometry);
+        Ok(geometry)
+    }
+
+    /// Get the geometry of this window, only querying the server if it is not already cached, async
+    /// redox.
+    #[cfg(feature = "async")]
+    #[inline]
+    pub async fn geometry_async<Dpy: AsyncDisplay + ?Sized>(
+        &mut self,
+        dpy: &mut Dpy,
+    ) -> crate::Result<Geometry> {
+        if let Some(geometry) = self.geometry {
+            return Ok(geometry);
+        }
+
+        let geometry = self.window.geometry_immediate_async(dpy).await?;
+        self.geometry = Some(geometry);
+        Ok(geometry)
+    }
+
+    /// Get the event mask this client has selected on the window, only querying the server if it is not
+    /// already cached.
+    #[inline]
+    pub fn event_mask<Dpy: Display + ?Sized>(&mut self, dpy: &mut Dpy) -> crate::Result<EventMask> {
+        if let Some(em) = self.event_mask {
+            return Ok(em);
+        }
+
+        let em = self
+            .window
+            .window_attributes_immediate(dpy)?
+            .your_event_mask;
+        self.event_mask = Some(em);
+        Ok(em)
+    }
+
+    /// Get the event mask this client has selected on the window, only querying the server if it is not
+    /// already cached, async redox.
+    #[cfg(feature = "async")]
+    #[inline]
+    pub async fn event_mask_async<Dpy: AsyncDisplay + ?Sized>(
+        &mut self,
+        dpy: &mut Dpy,
+    ) -> crate::Result<EventMask> {
+        if let Some(em) = self.event_mask {
+            return Ok(em);
+        }
+
+        let em = self
+            .window
+            .window_attributes_immediate_async(dpy)
+            .await?
+            .your_event_mask;
+        self.event_mask = Some(em);
+        Ok(em)
+    }
+
+    /// Set the event mask of this window, and remember it.
+    #[inline]
+    pub fn set_event_mask<Dpy: Display + ?Sized>(
+        &mut self,
+        dpy: &mut Dpy,
+        em: EventMask,
+    ) -> crate::Result {
+        self.window.set_event_mask(dpy, em)?;
+        self.event_mask = Some(em);
+        Ok(())
+    }
+
+    /// Set the event mask of this window, and remember it, async redox.
+    #[cfg(feature = "async")]
+    #[inline]
+    pub async fn set_event_mask_async<Dpy: AsyncDisplay + ?Sized>(
+        &mut self,
+        dpy: &mut Dpy,
+        em: EventMask,
+    ) -> crate::Result {
+        self.window.set_event_mask_async(dpy, em).await?;
+        self.event_mask = Some(em);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+fn cached_test_window() -> CachedWindow {
+    let mut cw = CachedWindow::new(Window::const_from_xid(1));
+    cw.geometry = Some(Geometry {
+        width: 100,
+        height: 100,
+        ..Default::default()
+    });
+    cw
+}
+
+#[test]
+fn configure_notify_updates_cache() {
+    use crate::auto::xproto::ConfigureNotifyEvent;
+
+    let mut cw = cached_test_window();
+    cw.note_event(&Event::ConfigureNotify(ConfigureNotifyEvent {
+        window: Window::const_from_xid(2),
+        width: 50,
+        ..Default::default()
+    }));
+    assert_eq!(cw.cached_size(), Some((100, 100)));
+
+    cw.note_event(&Event::ConfigureNotify(ConfigureNotifyEvent {
+        window: Window::const_from_xid(1),
+        width: 200,
+        height: 300,
+        ..Default::default()
+    }));
+    assert_eq!(cw.cached_size(), Some((200, 300)));
+
+    cw.note_event(&Event::ConfigureNotify(ConfigureNotifyEvent {
+        event_type: 0x80 | 22,
+        window: Window::const_from_xid(1),
+        ..Default::default()
+    }));
+    assert_eq!(cw.cached_size(), None);
+}
+
+#[test]
+fn resize_request_invalidates_cache() {
+    use crate::auto::xproto::ResizeRequestEvent;
+
+    let mut cw = cached_test_window();
+    cw.note_event(&Event::ResizeRequest(ResizeRequestEvent {
+        window: Window::const_from_xid(1),
+        width: 20,
+        height: 20,
+        ..Default::default()
+    }));
+    assert_eq!(cw.cached_size(), None);
+}
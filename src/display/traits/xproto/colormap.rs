@@ -3,10 +3,14 @@
 //! Provides functionality and structures used to interface with the colormap.
 
 use crate::{
-    auto::xproto::{AllocColorReply, AllocColorRequest, Colormap},
+    auto::xproto::{
+        AllocColorReply, AllocColorRequest, AllocNamedColorReply, AllocNamedColorRequest, Colormap,
+        FreeColormapRequest, FreeColorsRequest,
+    },
     display::prelude::*,
     Display, RequestCookie,
 };
+use alloc::borrow::Cow;
 
 #[cfg(feature = "async")]
 use crate::{
@@ -29,6 +33,106 @@ pub const fn rgb(r: u8, g: u8, b: u8) -> u32 {
     b + (g << 8) + (r << 16)
 }
 
+/// Parse a color specification on the client side, the same way Xlib's `XParseColor` does. The
+/// following forms are recognized, case insensitively:
+///
+/// * `#rgb`, `#rrggbb`, `#rrrgggbbb` and `#rrrrggggbbbb`, where each component is given by the same
+///   number of hex digits. These digits are the most significant bits of the component, so `#f00` is
+///   the same as `#f00000000000`.
+/// * `rgb:r/g/b`, where each component has one to four hex digits. Components are scaled, so
+///   `rgb:f/0/0` is the same as `rgb:ffff/0/0`.
+/// * `rgbi:r/g/b`, where each component is a floating point intensity between `0.0` and `1.0`.
+///
+/// The 16-bit red, green and blue components are returned. Anything else, e.g. a color name that
+/// should be looked up by the server, returns `None`.
+#[inline]
+#[must_use]
+pub fn parse_color_spec(spec: &str) -> Option<(u16, u16, u16)> {
+    if let Some(hex) = strip_prefix_ignore_case(spec, "#") {
+        parse_sharp_spec(hex)
+    } else if let Some(components) = strip_prefix_ignore_case(spec, "rgb:") {
+        parse_components(components, parse_scaled_hex)
+    } else if let Some(components) = strip_prefix_ignore_case(spec, "rgbi:") {
+        parse_components(components, parse_intensity)
+    } else {
+        None
+    }
+}
+
+#[inline]
+fn strip_prefix_ignore_case<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    match s.get(..prefix.len()) {
+        Some(p) if p.eq_ignore_ascii_case(prefix) => Some(&s[prefix.len()..]),
+        _ => None,
+    }
+}
+
+/// Parse the part of a `#` spec after the `#`.
+#[inline]
+fn parse_sharp_spec(hex: &str) -> Option<(u16, u16, u16)> {
+    // checking this first also makes sure we never slice in the middle of a character
+    if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let width = match hex.len() {
+        3 | 6 | 9 | 12 => hex.len() / 3,
+        _ => return None,
+    };
+
+    // the digits we are given are the most significant ones
+    let component = |i: usize| {
+        let digits = &hex[i * width..(i + 1) * width];
+        u16::from_str_radix(digits, 16)
+            .ok()
+            .map(|c| c << (16 - (width * 4)))
+    };
+
+    Some((component(0)?, component(1)?, component(2)?))
+}
+
+/// Parse the three slash-separated components of an `rgb:` or `rgbi:` spec.
+#[inline]
+fn parse_components(
+    components: &str,
+    parse: impl Fn(&str) -> Option<u16>,
+) -> Option<(u16, u16, u16)> {
+    let mut components = components.split('/');
+    let r = parse(components.next()?)?;
+    let g = parse(components.next()?)?;
+    let b = parse(components.next()?)?;
+
+    if components.next().is_some() {
+        None
+    } else {
+        Some((r, g, b))
+    }
+}
+
+/// Parse one to four hex digits, scaling the result to 16 bits.
+#[inline]
+fn parse_scaled_hex(digits: &str) -> Option<u16> {
+    if digits.is_empty() || digits.len() > 4 || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let value = u32::from_str_radix(digits, 16).ok()?;
+    let max = (1u32 << (digits.len() * 4)) - 1;
+    Some((value * 0xFFFF / max) as u16)
+}
+
+/// Parse an intensity between 0.0 and 1.0, scaling the result to 16 bits.
+#[inline]
+#[allow(clippy::cast_sign_loss)]
+fn parse_intensity(intensity: &str) -> Option<u16> {
+    let intensity: f32 = intensity.parse().ok()?;
+    if (0.0..=1.0).contains(&intensity) {
+        Some((intensity * 65535.0 + 0.5) as u16)
+    } else {
+        None
+    }
+}
+
 /// The result of a color allocation call.
 #[derive(Debug, Clone, Copy)]
 pub enum ColorAllocation {
@@ -70,6 +174,28 @@ impl ColorAllocation {
     }
 }
 
+impl ColorAllocation {
+    /// Convert an alloc named color reply to the result. The color is considered changed if the
+    /// color the hardware uses is not the exact color from the database.
+    #[inline]
+    #[must_use]
+    pub fn from_alloc_named_color_reply(ancr: AllocNamedColorReply) -> Self {
+        if ancr.exact_red == ancr.visual_red
+            && ancr.exact_green == ancr.visual_green
+            && ancr.exact_blue == ancr.visual_blue
+        {
+            Self::NoChange(ancr.pixel)
+        } else {
+            Self::Changed {
+                red: ancr.visual_red,
+                green: ancr.visual_green,
+                blue: ancr.visual_blue,
+                pixel: ancr.pixel,
+            }
+        }
+    }
+}
+
 impl From<ColorAllocation> for u32 {
     #[inline]
     fn from(ca: ColorAllocation) -> u32 {
@@ -151,4 +277,214 @@ impl Colormap {
             Box::new(move |acr| ColorAllocation::from_alloc_color_reply(acr, r, g, b)),
         )
     }
+
+    /// Alloc named color request.
+    #[inline]
+    fn alloc_named_color_request(self, name: &str) -> AllocNamedColorRequest<'_> {
+        AllocNamedColorRequest {
+            cmap: self,
+            name: Cow::Borrowed(name),
+            ..Default::default()
+        }
+    }
+
+    /// Allocate a color by looking its name up in the server's color database.
+    #[inline]
+    pub fn alloc_named_color<'a, Dpy: Display + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+        name: &'a str,
+    ) -> crate::Result<RequestCookie<AllocNamedColorRequest<'a>>> {
+        dpy.send_request(self.alloc_named_color_request(name))
+    }
+
+    /// Allocate a color by looking its name up in the server's color database, async redox.
+    #[cfg(feature = "async")]
+    #[inline]
+    pub fn alloc_named_color_async<'a, Dpy: AsyncDisplay + ?Sized>(
+        self,
+        dpy: &'a mut Dpy,
+        name: &'a str,
+    ) -> SendRequestFuture<'a, Dpy, AllocNamedColorRequest<'a>> {
+        dpy.send_request_async(self.alloc_named_color_request(name))
+    }
+
+    /// Immediately allocate a color from a color specification. Specs that `parse_color_spec`
+    /// understands, like `#ff8800` or `rgb:ff/88/00`, are parsed on the client side and allocated
+    /// with `AllocColor`. Anything else is treated as a color name and allocated with
+    /// `AllocNamedColor`.
+    #[inline]
+    pub fn alloc_color_spec_immediate<Dpy: Display + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+        spec: &str,
+    ) -> crate::Result<ColorAllocation> {
+        if let Some((r, g, b)) = parse_color_spec(spec) {
+            self.alloc_color_immediate(dpy, r, g, b)
+        } else {
+            let tok = self.alloc_named_color(dpy, spec)?;
+            Ok(ColorAllocation::from_alloc_named_color_reply(
+                dpy.resolve_request(tok)?,
+            ))
+        }
+    }
+
+    /// Immediately allocate a color from a color specification, async redox.
+    #[cfg(feature = "async")]
+    #[inline]
+    pub async fn alloc_color_spec_immediate_async<Dpy: AsyncDisplay + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+        spec: &str,
+    ) -> crate::Result<ColorAllocation> {
+        match parse_color_spec(spec) {
+            Some((r, g, b)) => Ok(ColorAllocation::from_alloc_color_reply(
+                dpy.exchange_request_async(self.alloc_color_request(r, g, b))
+                    .await?,
+                r,
+                g,
+                b,
+            )),
+            None => Ok(ColorAllocation::from_alloc_named_color_reply(
+                dpy.exchange_request_async(self.alloc_named_color_request(spec))
+                    .await?,
+            )),
+        }
+    }
+
+    /// Free this colormap.
+    #[inline]
+    pub fn free<Dpy: Display + ?Sized>(self, dpy: &mut Dpy) -> crate::Result {
+        dpy.exchange_request(FreeColormapRequest {
+            cmap: self,
+            ..Default::default()
+        })
+    }
+
+    /// Free this colormap, async redox.
+    #[cfg(feature = "async")]
+    #[inline]
+    pub async fn free_async<Dpy: AsyncDisplay + ?Sized>(self, dpy: &mut Dpy) -> crate::Result {
+        dpy.exchange_request_async(FreeColormapRequest {
+            cmap: self,
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// Free color cells that were previously allocated in this colormap. If there are too many
+    /// pixels to fit into a single request, they are split across several.
+    #[inline]
+    pub fn free_colors<Dpy: Display + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+        pixels: &[u32],
+        plane_mask: u32,
+    ) -> crate::Result {
+        pixels
+            .chunks(free_colors_chunk_len(dpy.effective_max_request_bytes()))
+            .try_for_each(|pixels| {
+                dpy.exchange_request(self.free_colors_request(pixels, plane_mask))
+            })
+    }
+
+    /// Free color cells that were previously allocated in this colormap, async redox.
+    #[cfg(feature = "async")]
+    #[inline]
+    pub async fn free_colors_async<Dpy: AsyncDisplay + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+        pixels: &[u32],
+        plane_mask: u32,
+    ) -> crate::Result {
+        for pixels in pixels.chunks(free_colors_chunk_len(dpy.effective_max_request_bytes())) {
+            dpy.exchange_request_async(self.free_colors_request(pixels, plane_mask))
+                .await?;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn free_colors_request(self, pixels: &[u32], plane_mask: u32) -> FreeColorsRequest<'_> {
+        FreeColorsRequest {
+            cmap: self,
+            plane_mask,
+            pixels: Cow::Borrowed(pixels),
+            ..Default::default()
+        }
+    }
+}
+
+/// The number of pixels that fit into a single `FreeColors` request, given the maximum request
+/// length in bytes.
+#[inline]
+fn free_colors_chunk_len(max_request_len: usize) -> usize {
+    // the header, colormap and plane mask take up 12 bytes
+    (max_request_len.saturating_sub(12) / 4).max(1)
+}
+
+#[test]
+fn parse_sharp_color_specs() {
+    assert_eq!(parse_color_spec("#f80"), Some((0xF000, 0x8000, 0x0000)));
+    assert_eq!(parse_color_spec("#ff8800"), Some((0xFF00, 0x8800, 0x0000)));
+    assert_eq!(parse_color_spec("#FF8800"), Some((0xFF00, 0x8800, 0x0000)));
+    assert_eq!(
+        parse_color_spec("#fff888000"),
+        Some((0xFFF0, 0x8880, 0x0000))
+    );
+    assert_eq!(
+        parse_color_spec("#ffff88880000"),
+        Some((0xFFFF, 0x8888, 0x0000))
+    );
+    assert_eq!(parse_color_spec("#ff880"), None);
+    assert_eq!(parse_color_spec("#ff880g"), None);
+    assert_eq!(parse_color_spec("#+f+f+f"), None);
+    // multi-byte characters don't have their byte length split across components
+    assert_eq!(parse_color_spec("#a\u{e9}"), None);
+    assert_eq!(parse_color_spec("#\u{e9}\u{e9}\u{e9}"), None);
+}
+
+#[test]
+fn parse_rgb_color_specs() {
+    assert_eq!(
+        parse_color_spec("rgb:f/8/0"),
+        Some((0xFFFF, 0x8888, 0x0000))
+    );
+    assert_eq!(
+        parse_color_spec("rgb:ff/88/00"),
+        Some((0xFFFF, 0x8888, 0x0000))
+    );
+    assert_eq!(
+        parse_color_spec("RGB:fff/8/0"),
+        Some((0xFFFF, 0x8888, 0x0000))
+    );
+    assert_eq!(
+        parse_color_spec("rgb:ffff/8000/0"),
+        Some((0xFFFF, 0x8000, 0x0000))
+    );
+    assert_eq!(parse_color_spec("rgb:ff/88"), None);
+    assert_eq!(parse_color_spec("rgb:ff/88/00/00"), None);
+    assert_eq!(parse_color_spec("rgb:fffff/0/0"), None);
+    assert_eq!(parse_color_spec("rgb:ff//00"), None);
+}
+
+#[test]
+fn parse_rgbi_color_specs() {
+    assert_eq!(
+        parse_color_spec("rgbi:1/0.5/0"),
+        Some((0xFFFF, 0x8000, 0x0000))
+    );
+    assert_eq!(
+        parse_color_spec("rgbi:1.0/1.0/1.0"),
+        Some((0xFFFF, 0xFFFF, 0xFFFF))
+    );
+    assert_eq!(parse_color_spec("rgbi:1.5/0/0"), None);
+    assert_eq!(parse_color_spec("rgbi:-0.5/0/0"), None);
+}
+
+#[test]
+fn color_names_are_not_parsed() {
+    assert_eq!(parse_color_spec("red"), None);
+    assert_eq!(parse_color_spec("light goldenrod"), None);
+    assert_eq!(parse_color_spec(""), None);
 }
@@ -10,6 +10,7 @@ use crate::{
     },
     display::{generate_xid, prelude::*},
     image::{put::put_image_req, Image},
+    xid::XidType,
     Display, Gcontext, RequestCookie,
 };
 use alloc::{boxed::Box, vec::Vec};
@@ -161,6 +162,237 @@ pub(crate) fn get_image_req(
     }
 }
 
+/// Get a plane mask that covers every plane of a drawable with the given depth.
+#[inline]
+fn plane_mask_for_depth(depth: u8) -> usize {
+    if depth >= 32 {
+        0xFFFF_FFFF
+    } else {
+        (1_usize << depth) - 1
+    }
+}
+
+/// Tell whether a span starting at `start` with length `len` fits within `-border..max + border`. A window's
+/// border can be read along with its contents, so coordinates may go into the border on either side; pixmaps
+/// have no border.
+#[inline]
+fn region_fits(start: isize, len: usize, max: u16, border: u16) -> bool {
+    match (start as i64 + i64::from(border)).try_into() {
+        Ok::<usize, _>(start) => len + start <= usize::from(max) + 2 * usize::from(border),
+        Err(_) => false,
+    }
+}
+
+/// An object that can be drawn on, such as a window or a pixmap.
+///
+/// Requests like `CopyArea` and `GetImage` work on any drawable. This trait lets generic code call these
+/// requests on windows and pixmaps alike, and allows pixels to be copied between any two drawables
+/// regardless of their type.
+pub trait DrawableType: XidType + Copy + Into<Drawable> {
+    /// Get this object as a generic drawable.
+    #[inline]
+    fn as_drawable(self) -> Drawable {
+        self.into()
+    }
+
+    /// Get the geometry of this drawable.
+    #[inline]
+    fn drawable_geometry<Dpy: Display + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+    ) -> crate::Result<RequestCookie<GetGeometryRequest>> {
+        dpy.get_drawable_geometry(self)
+    }
+
+    /// Immediately get the geometry of this drawable.
+    #[inline]
+    fn drawable_geometry_immediate<Dpy: Display + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+    ) -> crate::Result<Geometry> {
+        dpy.get_drawable_geometry_immediate(self)
+    }
+
+    /// Copy an area of this drawable into another drawable, which may be of a different type.
+    #[inline]
+    fn copy_area_to<Dpy: Display + ?Sized, Target: DrawableType>(
+        self,
+        dpy: &mut Dpy,
+        destination: Target,
+        gc: Gcontext,
+        src_x: i16,
+        src_y: i16,
+        width: u16,
+        height: u16,
+        dest_x: i16,
+        dest_y: i16,
+    ) -> crate::Result {
+        dpy.copy_area(
+            self,
+            destination,
+            gc,
+            src_x,
+            src_y,
+            width,
+            height,
+            dest_x,
+            dest_y,
+        )
+    }
+
+    /// Write an image to this drawable.
+    #[inline]
+    fn put_image<Dpy: Display + ?Sized, Data: Deref<Target = [u8]>>(
+        self,
+        dpy: &mut Dpy,
+        gc: Gcontext,
+        image: &Image<Data>,
+        src_x: isize,
+        src_y: isize,
+        dest_x: isize,
+        dest_y: isize,
+        width: usize,
+        height: usize,
+    ) -> crate::Result {
+        dpy.put_image(self, gc, image, src_x, src_y, dest_x, dest_y, width, height)
+    }
+
+    /// Immediately read an image from a region of this drawable.
+    #[inline]
+    fn get_image_immediate<Dpy: Display + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+        x: isize,
+        y: isize,
+        width: usize,
+        height: usize,
+        plane_mask: usize,
+        format: ImageFormat,
+    ) -> crate::Result<Image<Box<[u8]>>> {
+        dpy.get_image_immediate(self, x, y, width, height, plane_mask, format)
+    }
+
+    /// Read a region of this drawable into an image, figuring out the plane mask and format from the
+    /// drawable's depth. See `DisplayDrawableExt::capture_image` for more information.
+    #[inline]
+    fn capture_image<Dpy: Display + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+        x: isize,
+        y: isize,
+        width: usize,
+        height: usize,
+        geometry: Option<Geometry>,
+    ) -> crate::Result<Image<Box<[u8]>>> {
+        dpy.capture_image(self, x, y, width, height, geometry)
+    }
+
+    /// Read a single plane of a region of this drawable into a 1-bit image. See
+    /// `DisplayDrawableExt::get_image_plane` for more information.
+    #[inline]
+    fn get_image_plane<Dpy: Display + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+        x: isize,
+        y: isize,
+        width: usize,
+        height: usize,
+        plane: u8,
+    ) -> crate::Result<Image<Box<[u8]>>> {
+        dpy.get_image_plane(self, x, y, width, height, plane)
+    }
+
+    /// Get the geometry of this drawable, async redox.
+    #[cfg(feature = "async")]
+    #[inline]
+    fn drawable_geometry_async<Dpy: AsyncDisplay + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+    ) -> SendRequestFuture<'_, Dpy, GetGeometryRequest> {
+        dpy.get_drawable_geometry_async(self)
+    }
+
+    /// Immediately get the geometry of this drawable, async redox.
+    #[allow(clippy::type_complexity)]
+    #[cfg(feature = "async")]
+    #[inline]
+    fn drawable_geometry_immediate_async<Dpy: AsyncDisplay + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+    ) -> MapFuture<
+        ExchangeRequestFuture<'_, Dpy, GetGeometryRequest>,
+        fn(crate::Result<GetGeometryReply>) -> crate::Result<Geometry>,
+    > {
+        dpy.get_drawable_geometry_immediate_async(self)
+    }
+
+    /// Copy an area of this drawable into another drawable, async redox.
+    #[cfg(feature = "async")]
+    #[inline]
+    fn copy_area_to_async<Dpy: AsyncDisplay + ?Sized, Target: DrawableType>(
+        self,
+        dpy: &mut Dpy,
+        destination: Target,
+        gc: Gcontext,
+        src_x: i16,
+        src_y: i16,
+        width: u16,
+        height: u16,
+        dest_x: i16,
+        dest_y: i16,
+    ) -> ExchangeRequestFuture<'_, Dpy, CopyAreaRequest> {
+        dpy.copy_area_async(
+            self,
+            destination,
+            gc,
+            src_x,
+            src_y,
+            width,
+            height,
+            dest_x,
+            dest_y,
+        )
+    }
+
+    /// Write an image to this drawable, async redox.
+    #[cfg(feature = "async")]
+    #[inline]
+    fn put_image_async<'a, Dpy: AsyncDisplay + ?Sized, Data: Deref<Target = [u8]>>(
+        self,
+        dpy: &'a mut Dpy,
+        gc: Gcontext,
+        image: &Image<Data>,
+        src_x: isize,
+        src_y: isize,
+        dest_x: isize,
+        dest_y: isize,
+        width: usize,
+        height: usize,
+    ) -> PutImageFuture<'a, Dpy, Vec<PutImageRequest<'static>>> {
+        dpy.put_image_async(self, gc, image, src_x, src_y, dest_x, dest_y, width, height)
+    }
+
+    /// Immediately read an image from a region of this drawable, async redox.
+    #[cfg(feature = "async")]
+    #[inline]
+    fn get_image_immediate_async<Dpy: AsyncDisplay + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+        x: isize,
+        y: isize,
+        width: usize,
+        height: usize,
+        plane_mask: usize,
+        format: ImageFormat,
+    ) -> GetImageFuture<'_, Dpy> {
+        dpy.get_image_immediate_async(self, x, y, width, height, plane_mask, format)
+    }
+}
+
+impl DrawableType for Drawable {}
+impl DrawableType for Window {}
+impl DrawableType for Pixmap {}
+
 pub trait DisplayDrawableExt: Display {
     /// Get the geometry of a drawable object.
     #[inline]
@@ -368,6 +600,127 @@ pub trait DisplayDrawableExt: Display {
             self, width, height, plane_mask, format, repl,
         ))
     }
+
+    /// Read a region of a drawable into an image, without having to know the drawable's depth beforehand.
+    ///
+    /// `get_image` needs a plane mask and format that match the drawable, and guessing wrong (e.g. assuming
+    /// that every window has a depth of 24) results in a `BadMatch` error. This function first asks the
+    /// server for the drawable's geometry, and then reads every plane of the drawable in the `ZPixmap`
+    /// format. The resulting image has the drawable's depth and uses the server's pixmap format for that
+    /// depth, so its pixels can be read with `Image::pixel` directly.
+    ///
+    /// Coordinates are relative to the inside of the drawable. For a window, the region may extend into its
+    /// border, so `x` and `y` can be as low as `-border_width`.
+    ///
+    /// The extra round trip for the geometry can be skipped by passing in a `Geometry` that was fetched
+    /// earlier, e.g. when capturing the same window repeatedly. Note that it needs to be fetched again if
+    /// the drawable may have been resized.
+    ///
+    /// # Errors
+    ///
+    /// This returns `BreadError::StaticMsg` if the region does not fit within the drawable, or if the server
+    /// has no pixmap format for the drawable's depth. Otherwise, it returns any error that `GetGeometry` or
+    /// `GetImage` can, such as `BadMatch` if the target is a window that is not viewable.
+    #[inline]
+    fn capture_image<Target: Into<Drawable>>(
+        &mut self,
+        target: Target,
+        x: isize,
+        y: isize,
+        width: usize,
+        height: usize,
+        geometry: Option<Geometry>,
+    ) -> crate::Result<Image<Box<[u8]>>> {
+        let target = target.into();
+        let geometry = match geometry {
+            Some(geometry) => geometry,
+            None => self.get_drawable_geometry_immediate(target)?,
+        };
+
+        if !region_fits(x, width, geometry.width, geometry.border_width)
+            || !region_fits(y, height, geometry.height, geometry.border_width)
+        {
+            return Err(crate::BreadError::StaticMsg(
+                "Region to capture does not fit within the drawable",
+            ));
+        }
+
+        if !self
+            .setup()
+            .pixmap_formats
+            .iter()
+            .any(|format| format.depth == geometry.depth)
+        {
+            return Err(crate::BreadError::StaticMsg(
+                "Server has no pixmap format for the drawable's depth",
+            ));
+        }
+
+        self.get_image_immediate(
+            target,
+            x,
+            y,
+            width,
+            height,
+            plane_mask_for_depth(geometry.depth),
+            ImageFormat::ZPixmap,
+        )
+    }
+
+    /// Read a single plane of a region of a drawable into a 1-bit image, e.g. to extract a mask for the
+    /// SHAPE extension. `plane` is the index of the plane, where 0 is the least significant one.
+    ///
+    /// This reads the region in the `XyPixmap` format with only the given plane in the plane mask, so the
+    /// server only sends that plane. The data of the resulting image is laid out like a bitmap, and is also
+    /// available from `Image::plane(0)`.
+    ///
+    /// # Errors
+    ///
+    /// This returns `BreadError::StaticMsg` if the drawable does not have the given plane. Otherwise, it
+    /// returns any error that `GetImage` can.
+    #[inline]
+    fn get_image_plane<Target: Into<Drawable>>(
+        &mut self,
+        target: Target,
+        x: isize,
+        y: isize,
+        width: usize,
+        height: usize,
+        plane: u8,
+    ) -> crate::Result<Image<Box<[u8]>>> {
+        if plane >= 32 {
+            return Err(crate::BreadError::StaticMsg(
+                "Drawables have at most 32 planes",
+            ));
+        }
+
+        let plane_mask = 1 << plane;
+        let repl = self.exchange_request(get_image_req(
+            target.into(),
+            x,
+            y,
+            width,
+            height,
+            plane_mask,
+            ImageFormat::XyPixmap,
+        ))?;
+
+        // the server silently leaves out planes that the drawable doesn't have
+        if plane >= repl.depth {
+            return Err(crate::BreadError::StaticMsg(
+                "Plane is outside of the drawable's depth",
+            ));
+        }
+
+        Ok(Image::from_image_reply(
+            self,
+            width,
+            height,
+            plane_mask,
+            ImageFormat::XyPixmap,
+            repl,
+        ))
+    }
 }
 
 impl<D: Display + ?Sized> DisplayDrawableExt for D {}
@@ -591,3 +944,56 @@ pub trait AsyncDisplayDrawableExt: AsyncDisplay {
 
 #[cfg(feature = "async")]
 impl<D: AsyncDisplay + ?Sized> AsyncDisplayDrawableExt for D {}
+
+#[test]
+fn plane_mask_for_depth_covers_every_plane() {
+    assert_eq!(plane_mask_for_depth(1), 0x1);
+    assert_eq!(plane_mask_for_depth(24), 0x00FF_FFFF);
+    assert_eq!(plane_mask_for_depth(32), 0xFFFF_FFFF);
+
+    assert!(region_fits(0, 10, 10, 0));
+    assert!(!region_fits(1, 10, 10, 0));
+    assert!(!region_fits(-1, 1, 10, 0));
+
+    // a window's border can be captured as well
+    assert!(region_fits(-2, 14, 10, 2));
+    assert!(!region_fits(-3, 1, 10, 2));
+    assert!(!region_fits(-2, 15, 10, 2));
+}
+
+#[test]
+fn drawable_type_keeps_the_xid() {
+    assert_eq!(Window::const_from_xid(0x20).as_drawable().xid(), 0x20);
+    assert_eq!(Pixmap::const_from_xid(0x21).as_drawable().xid(), 0x21);
+    assert_eq!(Drawable::const_from_xid(0x22).as_drawable().xid(), 0x22);
+}
+
+#[test]
+fn copy_area_accepts_mixed_drawable_types() {
+    use crate::auto::AsByteSequence;
+
+    // the same bound as copy_area_to, which lets windows and pixmaps be copied into each other
+    fn request<S: DrawableType, D: DrawableType>(source: S, destination: D) -> CopyAreaRequest {
+        copy_area_request(
+            source.into(),
+            destination.into(),
+            Gcontext::const_from_xid(0x30),
+            0,
+            0,
+            4,
+            4,
+            0,
+            0,
+        )
+    }
+
+    let req = request(Window::const_from_xid(0x20), Pixmap::const_from_xid(0x21));
+    let mut bytes = alloc::vec![0; req.size()];
+    req.as_bytes(&mut bytes);
+    assert_eq!(&bytes[4..8], &0x20u32.to_ne_bytes());
+    assert_eq!(&bytes[8..12], &0x21u32.to_ne_bytes());
+
+    let req = request(Pixmap::const_from_xid(0x21), Window::const_from_xid(0x20));
+    assert_eq!(req.src_drawable.xid(), 0x21);
+    assert_eq!(req.dst_drawable.xid(), 0x20);
+}
@@ -2,14 +2,29 @@
 
 use crate::{
     auto::xproto::{
-        Arc, ChangeGcRequest, CoordMode, Drawable, FillPolyRequest, FreeGcRequest, Gcontext, Point,
+        Arc, ChangeGcRequest, Char2b, CoordMode, CopyGcRequest, Drawable, FillPolyRequest,
+        Fontable, FreeGcRequest, Gc, Gcontext, ImageText16Request, ImageText8Request, Point,
         PolyArcRequest, PolyFillArcRequest, PolyFillRectangleRequest, PolyRectangleRequest,
-        PolySegmentRequest, PolyShape, Rectangle, Segment,
+        PolySegmentRequest, PolyShape, QueryTextExtentsReply, QueryTextExtentsRequest, Rectangle,
+        Segment,
     },
+    auto::AsByteSequence,
     display::prelude::*,
-    Display, GcParameters,
+    BreadError, Display, GcParameters, Request,
 };
-use alloc::borrow::Cow;
+use alloc::{borrow::Cow, vec::Vec};
+use core::convert::TryFrom;
+
+/// The maximum number of characters a single `ImageText8` or `ImageText16` request can hold, since the
+/// length of the string is sent as a single byte.
+const IMAGE_TEXT_MAX_LEN: usize = 255;
+
+/// The size of the fixed part of a `PolyArc` or `PolyFillArc` request, and the size of each arc in it.
+const POLY_ARC_HEADER_SIZE: usize = 12;
+const ARC_SIZE: usize = 12;
+
+/// The number of 64ths of a degree in a full circle, the unit X uses for arc angles.
+const FULL_CIRCLE: i32 = 360 * 64;
 
 #[cfg(feature = "async")]
 use crate::display::{AsyncDisplay, EitherFuture, ExchangeRequestFuture};
@@ -18,6 +33,98 @@ use alloc::vec;
 #[cfg(feature = "async")]
 use futures_lite::future::{self, Ready};
 
+/// Get the number of arcs that fit into a single `PolyArc` or `PolyFillArc` request.
+#[inline]
+fn max_arcs_per_request<Dpy: DisplayBase + ?Sized>(dpy: &Dpy) -> usize {
+    (dpy.effective_max_request_bytes()
+        .saturating_sub(POLY_ARC_HEADER_SIZE)
+        / ARC_SIZE)
+        .max(1)
+}
+
+/// Convert an angle in degrees into the 64ths of a degree that X uses, rounding to the nearest unit. Angles
+/// outside of the range an `i16` can hold (about ±512 degrees) saturate to `i16::MIN` or `i16::MAX`, and NaN
+/// becomes zero.
+#[inline]
+fn degrees_to_arc_angle(degrees: f64) -> i16 {
+    let angle = degrees * 64.0;
+    if angle.is_nan() {
+        return 0;
+    }
+
+    // clamp first, since casting an out-of-range float is undefined on older compilers
+    (if angle < 0.0 {
+        angle - 0.5
+    } else {
+        angle + 0.5
+    })
+    .max(f64::from(i16::MIN))
+    .min(f64::from(i16::MAX)) as i16
+}
+
+impl Arc {
+    /// Create an arc from angles given in degrees. The arc is part of the ellipse inscribed in the rectangle
+    /// at `(x, y)` with the given size. It starts at `start` degrees, where zero is three o'clock and positive
+    /// angles go counterclockwise, and spans `extent` degrees from there (negative for clockwise).
+    ///
+    /// On the wire, `angle1` and `angle2` are in 64ths of a degree, so an arc created by hand that spans a
+    /// quarter circle has an `angle2` of `90 * 64`. Also note that `angle2` is the size of the arc relative
+    /// to `angle1`, not the angle the arc ends at.
+    ///
+    /// Since an `i16` can only hold about ±512 degrees in this unit, angles beyond that saturate to the
+    /// largest or smallest value that fits, and NaN is treated as zero. A full circle is well within range.
+    #[inline]
+    #[must_use]
+    pub fn from_degrees(x: i16, y: i16, width: u16, height: u16, start: f64, extent: f64) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+            angle1: degrees_to_arc_angle(start),
+            angle2: degrees_to_arc_angle(extent),
+        }
+    }
+}
+
+/// Split an ellipse into one pie slice per value, with each slice's size proportional to its value. This is
+/// the shape of a pie chart.
+///
+/// The slices start at twelve o'clock and go clockwise, in the order of `values`, and together they cover
+/// the whole ellipse without gaps. Values that are not positive produce empty slices, so that the returned
+/// arcs always line up with `values`; if none are positive, no arcs are returned. Each slice will usually be
+/// filled with a different color, using `Gcontext::fill_arc` with a GC whose arc mode is `PieSlice` (the
+/// default).
+#[must_use]
+pub fn pie_slices(x: i16, y: i16, width: u16, height: u16, values: &[f64]) -> Vec<Arc> {
+    let positive = |value: f64| if value > 0.0 { value } else { 0.0 };
+    let total: f64 = values.iter().copied().map(positive).sum();
+    if total <= 0.0 {
+        return Vec::new();
+    }
+
+    // compute the boundaries from the running total, so that rounding errors don't add up
+    let mut sum = 0.0;
+    let mut start = 0;
+    values
+        .iter()
+        .map(|&value| {
+            sum += positive(value);
+            let end = (f64::from(FULL_CIRCLE) * sum / total + 0.5) as i32;
+            let arc = Arc {
+                x,
+                y,
+                width,
+                height,
+                angle1: (90 * 64 - start) as i16,
+                angle2: (start - end) as i16,
+            };
+            start = end;
+            arc
+        })
+        .collect()
+}
+
 impl Gcontext {
     #[inline]
     fn change_request(self, params: GcParameters) -> ChangeGcRequest {
@@ -52,6 +159,43 @@ impl Gcontext {
         dpy.exchange_request_async(self.change_request(params))
     }
 
+    #[inline]
+    fn copy_request(self, dst: Gcontext, mask: Gc) -> CopyGcRequest {
+        CopyGcRequest {
+            src_gc: self,
+            dst_gc: dst,
+            value_mask: mask,
+            ..Default::default()
+        }
+    }
+
+    /// Copy the components selected by `mask` from this GC into `dst`. Both GCs must have been created
+    /// for drawables with the same root and depth.
+    ///
+    /// This is useful for keeping several GCs that differ only slightly: copy the shared state from a
+    /// template GC, then `change` the few fields that differ.
+    #[inline]
+    pub fn copy<Dpy: Display + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+        dst: Gcontext,
+        mask: Gc,
+    ) -> crate::Result<()> {
+        dpy.exchange_request(self.copy_request(dst, mask))
+    }
+
+    /// Copy the components selected by `mask` from this GC into `dst`, async redox.
+    #[cfg(feature = "async")]
+    #[inline]
+    pub fn copy_async<Dpy: AsyncDisplay + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+        dst: Gcontext,
+        mask: Gc,
+    ) -> ExchangeRequestFuture<'_, Dpy, CopyGcRequest> {
+        dpy.exchange_request_async(self.copy_request(dst, mask))
+    }
+
     /// Request to draw a line.
     #[inline]
     fn poly_segment_request(
@@ -247,7 +391,9 @@ impl Gcontext {
         }
     }
 
-    /// Draw one or more arcs to the screen.
+    /// Draw one or more arcs to the screen. See `Arc::from_degrees` for how angles are measured.
+    ///
+    /// If there are more arcs than fit into a single request, they are split across several requests.
     #[inline]
     pub fn draw_arcs<
         'a,
@@ -265,36 +411,51 @@ impl Gcontext {
             return Ok(());
         }
 
-        dpy.exchange_request(self.poly_arc_request(target.into(), arcs))
+        let target = target.into();
+        let max_arcs = max_arcs_per_request(dpy);
+        if arcs.len() <= max_arcs {
+            return dpy.exchange_request(self.poly_arc_request(target, arcs));
+        }
+
+        arcs.chunks(max_arcs).try_for_each(|chunk| {
+            dpy.exchange_request(self.poly_arc_request(target, Cow::Borrowed(chunk)))
+        })
     }
 
-    /// Draw one or more arcs to the screen, async redox.
+    /// Draw one or more arcs to the screen, async redox. Like `draw_arcs`, the arcs are split across several
+    /// requests if they don't fit into a single one.
     #[cfg(feature = "async")]
     #[inline]
-    pub fn draw_arcs_async<
+    pub async fn draw_arcs_async<
         'a,
-        'b,
         Dpy: AsyncDisplay + ?Sized,
         Target: Into<Drawable>,
-        Arcs: Into<Cow<'b, [Arc]>>,
+        Arcs: Into<Cow<'a, [Arc]>>,
     >(
         self,
-        dpy: &'a mut Dpy,
+        dpy: &mut Dpy,
         target: Target,
         arcs: Arcs,
-    ) -> EitherFuture<Ready<crate::Result>, ExchangeRequestFuture<'a, Dpy, PolyArcRequest<'b>>>
-    where
-        'b: 'a,
-    {
+    ) -> crate::Result {
         let arcs = arcs.into();
-        match arcs.is_empty() {
-            true => EitherFuture::Left {
-                future: future::ready(Ok(())),
-            },
-            false => EitherFuture::Right {
-                future: dpy.exchange_request_async(self.poly_arc_request(target.into(), arcs)),
-            },
+        if arcs.is_empty() {
+            return Ok(());
+        }
+
+        let target = target.into();
+        let max_arcs = max_arcs_per_request(dpy);
+        if arcs.len() <= max_arcs {
+            return dpy
+                .exchange_request_async(self.poly_arc_request(target, arcs))
+                .await;
         }
+
+        for chunk in arcs.chunks(max_arcs) {
+            dpy.exchange_request_async(self.poly_arc_request(target, Cow::Borrowed(chunk)))
+                .await?;
+        }
+
+        Ok(())
     }
 
     /// Draw an arc to the screen.
@@ -321,10 +482,7 @@ impl Gcontext {
     where
         'b: 'a,
     {
-        match self.draw_arcs_async(dpy, target, vec![arc]) {
-            EitherFuture::Right { future } => future,
-            EitherFuture::Left { .. } => unreachable!(),
-        }
+        dpy.exchange_request_async(self.poly_arc_request(target.into(), Cow::Owned(vec![arc])))
     }
 
     /// Request to fill a polygon.
@@ -514,7 +672,16 @@ impl Gcontext {
         }
     }
 
-    /// Fill a set of one or more arcs.
+    /// Fill a set of one or more arcs. Depending on the GC's arc mode, each arc is filled either as a pie
+    /// slice (the default), bounded by the arc and two lines to the center of the ellipse, or as a chord,
+    /// bounded by the arc and the line between its end points.
+    ///
+    /// Arc angles are measured in 64ths of a degree, starting at three o'clock and going counterclockwise,
+    /// and `angle2` is the size of the arc rather than the angle it ends at. For example, the top half of an
+    /// ellipse has an `angle1` of `0` and an `angle2` of `180 * 64`. `Arc::from_degrees` and `pie_slices`
+    /// can be used to avoid doing this conversion by hand.
+    ///
+    /// If there are more arcs than fit into a single request, they are split across several requests.
     #[inline]
     pub fn fill_arcs<
         'a,
@@ -532,37 +699,54 @@ impl Gcontext {
             return Ok(());
         }
 
-        dpy.exchange_request(self.poly_fill_arc_request(target.into(), arcs))
+        let target = target.into();
+        let max_arcs = max_arcs_per_request(dpy);
+        if arcs.len() <= max_arcs {
+            return dpy.exchange_request(self.poly_fill_arc_request(target, arcs));
+        }
+
+        arcs.chunks(max_arcs).try_for_each(|chunk| {
+            dpy.exchange_request(self.poly_fill_arc_request(target, Cow::Borrowed(chunk)))
+        })
     }
 
-    /// Fill a set of one or more arcs, async redox.
+    /// Fill a set of one or more arcs, async redox. Like `fill_arcs`, the arcs are split across several
+    /// requests if they don't fit into a single one.
     #[cfg(feature = "async")]
     #[inline]
-    pub fn fill_arcs_async<
+    pub async fn fill_arcs_async<
         'a,
-        'b,
         Dpy: AsyncDisplay + ?Sized,
         Target: Into<Drawable>,
-        Arcs: Into<Cow<'b, [Arc]>>,
+        Arcs: Into<Cow<'a, [Arc]>>,
     >(
         self,
-        dpy: &'a mut Dpy,
+        dpy: &mut Dpy,
         target: Target,
         arcs: Arcs,
-    ) -> EitherFuture<Ready<crate::Result>, ExchangeRequestFuture<'a, Dpy, PolyFillArcRequest<'b>>>
-    {
+    ) -> crate::Result {
         let arcs = arcs.into();
-        match arcs.is_empty() {
-            true => EitherFuture::Left {
-                future: future::ready(Ok(())),
-            },
-            false => EitherFuture::Right {
-                future: dpy.exchange_request_async(self.poly_fill_arc_request(target.into(), arcs)),
-            },
+        if arcs.is_empty() {
+            return Ok(());
+        }
+
+        let target = target.into();
+        let max_arcs = max_arcs_per_request(dpy);
+        if arcs.len() <= max_arcs {
+            return dpy
+                .exchange_request_async(self.poly_fill_arc_request(target, arcs))
+                .await;
+        }
+
+        for chunk in arcs.chunks(max_arcs) {
+            dpy.exchange_request_async(self.poly_fill_arc_request(target, Cow::Borrowed(chunk)))
+                .await?;
         }
+
+        Ok(())
     }
 
-    /// Fill an arc.
+    /// Fill an arc. See `fill_arcs` for how arcs are filled and how their angles are measured.
     #[inline]
     pub fn fill_arc<Dpy: Display + ?Sized, Target: Into<Drawable>>(
         self,
@@ -583,12 +767,180 @@ impl Gcontext {
         target: Target,
         arc: Arc,
     ) -> ExchangeRequestFuture<'_, Dpy, PolyFillArcRequest<'static>> {
-        match self.fill_arcs_async(dpy, target, vec![arc]) {
-            EitherFuture::Right { future } => future,
-            EitherFuture::Left { .. } => unreachable!(),
+        dpy.exchange_request_async(self.poly_fill_arc_request(target.into(), Cow::Owned(vec![arc])))
+    }
+
+    #[inline]
+    fn image_text8_request(
+        self,
+        drawable: Drawable,
+        x: i16,
+        y: i16,
+        string: &str,
+    ) -> ImageText8Request<'_> {
+        ImageText8Request {
+            drawable,
+            gc: self,
+            x,
+            y,
+            string: Cow::Borrowed(string),
+            ..Default::default()
         }
     }
 
+    #[inline]
+    fn image_text16_request(
+        self,
+        drawable: Drawable,
+        x: i16,
+        y: i16,
+        string: &[Char2b],
+    ) -> ImageText16Request<'_> {
+        ImageText16Request {
+            drawable,
+            gc: self,
+            x,
+            y,
+            string: Cow::Borrowed(string),
+            ..Default::default()
+        }
+    }
+
+    #[inline]
+    fn query_text_extents_request(self, string: &[Char2b]) -> TextExtentsRequest<'_> {
+        TextExtentsRequest {
+            font: self.into(),
+            string,
+        }
+    }
+
+    /// Draw a string of 8-bit characters, filling the bounding box of each character with the GC's
+    /// background color before drawing the character in the foreground color. This is what terminals
+    /// and labels with a solid background usually want. The string is sent byte for byte, so it should
+    /// only contain characters the GC's font can represent in a single byte.
+    ///
+    /// A single request can only hold 255 characters. Longer strings are split across several requests,
+    /// which costs an extra round trip per split in order to measure the text that has already been drawn.
+    #[inline]
+    pub fn image_text8<Dpy: Display + ?Sized, Target: Into<Drawable>>(
+        self,
+        dpy: &mut Dpy,
+        target: Target,
+        x: i16,
+        y: i16,
+        text: &str,
+    ) -> crate::Result {
+        let target = target.into();
+        let mut x = x;
+        let mut rest = text;
+
+        while !rest.is_empty() {
+            let (chunk, tail) = split_image_text8(rest);
+            dpy.exchange_request(self.image_text8_request(target, x, y, chunk))?;
+            if !tail.is_empty() {
+                let chunk = widen_image_text8(chunk);
+                let width = dpy
+                    .exchange_request(self.query_text_extents_request(&chunk))?
+                    .overall_width;
+                x = advance_text_x(x, width)?;
+            }
+            rest = tail;
+        }
+
+        Ok(())
+    }
+
+    /// Draw a string of 8-bit characters with a solid background, async redox.
+    #[cfg(feature = "async")]
+    #[inline]
+    pub async fn image_text8_async<Dpy: AsyncDisplay + ?Sized, Target: Into<Drawable>>(
+        self,
+        dpy: &mut Dpy,
+        target: Target,
+        x: i16,
+        y: i16,
+        text: &str,
+    ) -> crate::Result {
+        let target = target.into();
+        let mut x = x;
+        let mut rest = text;
+
+        while !rest.is_empty() {
+            let (chunk, tail) = split_image_text8(rest);
+            dpy.exchange_request_async(self.image_text8_request(target, x, y, chunk))
+                .await?;
+            if !tail.is_empty() {
+                let chunk = widen_image_text8(chunk);
+                let width = dpy
+                    .exchange_request_async(self.query_text_extents_request(&chunk))
+                    .await?
+                    .overall_width;
+                x = advance_text_x(x, width)?;
+            }
+            rest = tail;
+        }
+
+        Ok(())
+    }
+
+    /// Draw a string of 16-bit characters, filling the bounding box of each character with the GC's
+    /// background color. See `image_text8` for more information.
+    #[inline]
+    pub fn image_text16<Dpy: Display + ?Sized, Target: Into<Drawable>>(
+        self,
+        dpy: &mut Dpy,
+        target: Target,
+        x: i16,
+        y: i16,
+        text: &[Char2b],
+    ) -> crate::Result {
+        let target = target.into();
+        let mut x = x;
+        let mut chunks = text.chunks(IMAGE_TEXT_MAX_LEN).peekable();
+
+        while let Some(chunk) = chunks.next() {
+            dpy.exchange_request(self.image_text16_request(target, x, y, chunk))?;
+            if chunks.peek().is_some() {
+                let width = dpy
+                    .exchange_request(self.query_text_extents_request(chunk))?
+                    .overall_width;
+                x = advance_text_x(x, width)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Draw a string of 16-bit characters with a solid background, async redox.
+    #[cfg(feature = "async")]
+    #[inline]
+    pub async fn image_text16_async<Dpy: AsyncDisplay + ?Sized, Target: Into<Drawable>>(
+        self,
+        dpy: &mut Dpy,
+        target: Target,
+        x: i16,
+        y: i16,
+        text: &[Char2b],
+    ) -> crate::Result {
+        let target = target.into();
+        let mut x = x;
+        let mut chunks = text.chunks(IMAGE_TEXT_MAX_LEN).peekable();
+
+        while let Some(chunk) = chunks.next() {
+            dpy.exchange_request_async(self.image_text16_request(target, x, y, chunk))
+                .await?;
+            if chunks.peek().is_some() {
+                let width = dpy
+                    .exchange_request_async(self.query_text_extents_request(chunk))
+                    .await?
+                    .overall_width;
+                x = advance_text_x(x, width)?;
+            }
+        }
+
+        Ok(())
+    }
+
     /// Free the memory this GC allocates. Note that this will cause future requests involving this GC
     /// to fail.
     #[inline]
@@ -612,3 +964,143 @@ impl Gcontext {
         })
     }
 }
+
+/// A `QueryTextExtents` request. The generated `QueryTextExtentsRequest` has no field for the `odd_length`
+/// byte, so the request length ends up written over the font, and the padding after a string with an odd
+/// number of characters would be measured as an extra character.
+#[derive(Debug, Clone)]
+struct TextExtentsRequest<'a> {
+    font: Fontable,
+    string: &'a [Char2b],
+}
+
+impl AsByteSequence for TextExtentsRequest<'_> {
+    #[inline]
+    fn size(&self) -> usize {
+        8 + ((self.string.len() * 2 + 3) & !3)
+    }
+
+    #[inline]
+    fn as_bytes(&self, bytes: &mut [u8]) -> usize {
+        bytes[0] = Self::OPCODE;
+        // the server drops the last character if this is set, which is the padding
+        bytes[1] = (self.string.len() % 2) as u8;
+        // bytes 2 and 3 are filled in with the request length
+        self.font.as_bytes(&mut bytes[4..]);
+        for (i, ch) in self.string.iter().enumerate() {
+            bytes[8 + i * 2] = ch.byte1;
+            bytes[9 + i * 2] = ch.byte2;
+        }
+        self.size()
+    }
+
+    #[inline]
+    fn from_bytes(_bytes: &[u8]) -> Option<(Self, usize)> {
+        // the string is borrowed, so there is nothing for it to borrow from
+        None
+    }
+}
+
+impl Request for TextExtentsRequest<'_> {
+    type Reply = QueryTextExtentsReply;
+
+    const OPCODE: u8 = QueryTextExtentsRequest::OPCODE;
+    const EXTENSION: Option<&'static str> = None;
+    const REPLY_EXPECTS_FDS: bool = false;
+}
+
+/// Move the x coordinate of a string past `width` pixels of text, failing if it no longer fits into
+/// the 16 bits a coordinate is sent as.
+#[inline]
+fn advance_text_x(x: i16, width: i32) -> crate::Result<i16> {
+    i32::from(x)
+        .checked_add(width)
+        .and_then(|x| i16::try_from(x).ok())
+        .ok_or(BreadError::StaticMsg(
+            "Text extends past the largest possible x coordinate",
+        ))
+}
+
+/// Split off as many characters as fit into a single `ImageText8` request, without splitting a character in
+/// half.
+#[inline]
+fn split_image_text8(text: &str) -> (&str, &str) {
+    if text.len() <= IMAGE_TEXT_MAX_LEN {
+        return (text, "");
+    }
+
+    let mut index = IMAGE_TEXT_MAX_LEN;
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    text.split_at(index)
+}
+
+/// Convert a string of 8-bit characters into 16-bit characters, for use in `QueryTextExtents`.
+#[inline]
+fn widen_image_text8(text: &str) -> Vec<Char2b> {
+    text.bytes()
+        .map(|byte2| Char2b { byte1: 0, byte2 })
+        .collect()
+}
+
+#[test]
+fn pie_slices_cover_the_whole_circle() {
+    let arcs = pie_slices(0, 0, 100, 100, &[1.0, 2.0, 0.0, 1.0]);
+    assert_eq!(arcs.len(), 4);
+
+    // starts at twelve o'clock and goes clockwise
+    assert_eq!(arcs[0].angle1, 90 * 64);
+    assert_eq!(arcs[0].angle2, -90 * 64);
+    assert_eq!(arcs[1].angle1, 0);
+    assert_eq!(arcs[1].angle2, -180 * 64);
+    assert_eq!(arcs[2].angle2, 0);
+
+    let total: i32 = arcs.iter().map(|arc| i32::from(arc.angle2)).sum();
+    assert_eq!(total, -FULL_CIRCLE);
+
+    assert!(pie_slices(0, 0, 100, 100, &[0.0, -1.0]).is_empty());
+    assert_eq!(Arc::from_degrees(0, 0, 10, 10, 45.0, -22.5).angle2, -1440);
+}
+
+#[test]
+fn degrees_to_arc_angle_saturates() {
+    assert_eq!(degrees_to_arc_angle(360.0), 360 * 64);
+    assert_eq!(degrees_to_arc_angle(1000.0), i16::MAX);
+    assert_eq!(degrees_to_arc_angle(-1000.0), i16::MIN);
+    assert_eq!(degrees_to_arc_angle(f64::NAN), 0);
+}
+
+#[test]
+fn text_extents_request_encodes_odd_length() {
+    let string = [
+        Char2b {
+            byte1: 0,
+            byte2: b'a',
+        },
+        Char2b {
+            byte1: 1,
+            byte2: b'b',
+        },
+        Char2b {
+            byte1: 0,
+            byte2: b'c',
+        },
+    ];
+    let req = Gcontext::const_from_xid(0x0102_0304).query_text_extents_request(&string);
+    let mut bytes = alloc::vec![0xFF; req.size()];
+    assert_eq!(req.as_bytes(&mut bytes), 16);
+
+    assert_eq!(bytes[0], 48);
+    assert_eq!(bytes[1], 1);
+    assert_eq!(&bytes[4..8], &0x0102_0304u32.to_ne_bytes());
+    assert_eq!(&bytes[8..14], &[0, b'a', 1, b'b', 0, b'c']);
+
+    let req = Gcontext::const_from_xid(1).query_text_extents_request(&string[..2]);
+    let mut bytes = alloc::vec![0; req.size()];
+    assert_eq!(req.as_bytes(&mut bytes), 12);
+    assert_eq!(bytes[1], 0);
+
+    assert_eq!(advance_text_x(100, 50).unwrap(), 150);
+    assert!(advance_text_x(i16::MAX - 10, 50).is_err());
+}
@@ -0,0 +1,253 @@
+// MIT/Apache2 License
+
+//! Provides structures used to read the server's host access list.
+
+use crate::{
+    auto::{
+        xproto::{Family, ListHostsRequest},
+        AsByteSequence,
+    },
+    Request,
+};
+use alloc::vec::Vec;
+
+/// The family of a host in the access list.
+///
+/// Unlike `Family`, this can represent every family a server may report. For instance, the X.Org
+/// server lists local connections with the `LocalHost` family, which cannot be used in a
+/// `ChangeHosts` request.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum HostFamily {
+    Internet,
+    DecNet,
+    Chaos,
+    ServerInterpreted,
+    Internet6,
+    LocalHost,
+    Unknown(u8),
+}
+
+impl HostFamily {
+    /// Convert the family's byte representation into a `HostFamily`.
+    #[inline]
+    #[must_use]
+    pub fn from_raw(raw: u8) -> Self {
+        match raw {
+            0 => Self::Internet,
+            1 => Self::DecNet,
+            2 => Self::Chaos,
+            5 => Self::ServerInterpreted,
+            6 => Self::Internet6,
+            252 => Self::LocalHost,
+            raw => Self::Unknown(raw),
+        }
+    }
+
+    /// The byte representation of this family.
+    #[inline]
+    #[must_use]
+    pub fn raw(self) -> u8 {
+        match self {
+            Self::Internet => 0,
+            Self::DecNet => 1,
+            Self::Chaos => 2,
+            Self::ServerInterpreted => 5,
+            Self::Internet6 => 6,
+            Self::LocalHost => 252,
+            Self::Unknown(raw) => raw,
+        }
+    }
+
+    /// The family as it would be used in a `ChangeHosts` request, if it can be used in one.
+    #[inline]
+    #[must_use]
+    pub fn as_family(self) -> Option<Family> {
+        match self {
+            Self::Internet => Some(Family::Internet),
+            Self::DecNet => Some(Family::DeCnet),
+            Self::Chaos => Some(Family::Chaos),
+            Self::ServerInterpreted => Some(Family::ServerInterpreted),
+            Self::Internet6 => Some(Family::Internet6),
+            Self::LocalHost | Self::Unknown(_) => None,
+        }
+    }
+}
+
+impl From<Family> for HostFamily {
+    #[inline]
+    fn from(family: Family) -> Self {
+        Self::from_raw(family as u8)
+    }
+}
+
+/// A host in the server's access list.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HostEntry {
+    /// The family of the host's address.
+    pub family: HostFamily,
+    /// The address itself. For the `ServerInterpreted` family, this is a type and a value separated
+    /// by a zero byte, e.g. `localuser\0john`.
+    pub address: Vec<u8>,
+}
+
+impl AsByteSequence for HostEntry {
+    #[inline]
+    fn size(&self) -> usize {
+        4 + self.address.len() + pad4(self.address.len())
+    }
+
+    #[inline]
+    fn as_bytes(&self, bytes: &mut [u8]) -> usize {
+        let len = self.address.len();
+        bytes[0] = self.family.raw();
+        (len as u16).as_bytes(&mut bytes[2..]);
+        bytes[4..4 + len].copy_from_slice(&self.address);
+        4 + len + pad4(len)
+    }
+
+    #[inline]
+    fn from_bytes(bytes: &[u8]) -> Option<(Self, usize)> {
+        let family = HostFamily::from_raw(*bytes.first()?);
+        let (len, _) = u16::from_bytes(bytes.get(2..)?)?;
+        let len = len as usize;
+        let address = bytes.get(4..4 + len)?.to_vec();
+
+        // the padding after the final entry may be missing
+        let index = (4 + len + pad4(len)).min(bytes.len());
+        Some((Self { family, address }, index))
+    }
+}
+
+/// The server's host access list, as reported by `ListHosts`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct HostList {
+    /// Whether or not access control is enabled.
+    pub enabled: bool,
+    /// The hosts allowed to connect while access control is enabled.
+    pub hosts: Vec<HostEntry>,
+}
+
+impl AsByteSequence for HostList {
+    #[inline]
+    fn size(&self) -> usize {
+        32 + self.hosts.iter().map(HostEntry::size).sum::<usize>()
+    }
+
+    #[inline]
+    fn as_bytes(&self, bytes: &mut [u8]) -> usize {
+        bytes[0] = 1;
+        bytes[1] = self.enabled.into();
+        ((self.size() - 32) as u32 / 4).as_bytes(&mut bytes[4..]);
+        (self.hosts.len() as u16).as_bytes(&mut bytes[8..]);
+
+        self.hosts
+            .iter()
+            .fold(32, |index, host| index + host.as_bytes(&mut bytes[index..]))
+    }
+
+    #[inline]
+    fn from_bytes(bytes: &[u8]) -> Option<(Self, usize)> {
+        log::trace!("Deserializing HostList from byte buffer");
+        let enabled = *bytes.get(1)? != 0;
+        let (len, _) = u16::from_bytes(bytes.get(8..)?)?;
+
+        let mut index = 32;
+        let hosts = (0..len)
+            .map(|_| {
+                let (host, sz) = HostEntry::from_bytes(bytes.get(index..)?)?;
+                index += sz;
+                Some(host)
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        Some((Self { enabled, hosts }, index))
+    }
+}
+
+/// A `ListHosts` request whose reply is decoded into a `HostList`. The generated `ListHostsReply`
+/// fails to decode if the server lists a host with a family that `Family` does not cover.
+#[derive(Debug, Clone, Default)]
+pub struct ListHostEntriesRequest {
+    inner: ListHostsRequest,
+}
+
+impl AsByteSequence for ListHostEntriesRequest {
+    #[inline]
+    fn size(&self) -> usize {
+        self.inner.size()
+    }
+
+    #[inline]
+    fn as_bytes(&self, bytes: &mut [u8]) -> usize {
+        self.inner.as_bytes(bytes)
+    }
+
+    #[inline]
+    fn from_bytes(bytes: &[u8]) -> Option<(Self, usize)> {
+        let (inner, sz) = ListHostsRequest::from_bytes(bytes)?;
+        Some((Self { inner }, sz))
+    }
+}
+
+impl Request for ListHostEntriesRequest {
+    type Reply = HostList;
+
+    const OPCODE: u8 = ListHostsRequest::OPCODE;
+    const EXTENSION: Option<&'static str> = None;
+    const REPLY_EXPECTS_FDS: bool = false;
+}
+
+#[inline]
+fn pad4(len: usize) -> usize {
+    (4 - (len % 4)) % 4
+}
+
+#[test]
+fn host_list_round_trip() {
+    let list = HostList {
+        enabled: true,
+        hosts: alloc::vec![
+            HostEntry {
+                family: HostFamily::Internet,
+                address: alloc::vec![127, 0, 0, 1],
+            },
+            HostEntry {
+                family: HostFamily::ServerInterpreted,
+                address: b"localuser\0john".to_vec(),
+            },
+        ],
+    };
+
+    let mut bytes = alloc::vec![0; list.size()];
+    assert_eq!(list.as_bytes(&mut bytes), 32 + 8 + 20);
+    assert_eq!(HostList::from_bytes(&bytes), Some((list, 32 + 8 + 20)));
+}
+
+#[test]
+fn host_list_tolerates_unknown_families() {
+    let mut bytes = alloc::vec![0; 32 + 8 + 4];
+    bytes[1] = 0;
+    bytes[8..10].copy_from_slice(&2u16.to_ne_bytes());
+    // a local host entry, with an empty address
+    bytes[32] = 252;
+    // an entry with a family nobody has heard of
+    bytes[36] = 77;
+    bytes[38..40].copy_from_slice(&3u16.to_ne_bytes());
+    bytes[40..43].copy_from_slice(&[1, 2, 3]);
+
+    let (list, _) = HostList::from_bytes(&bytes).unwrap();
+    assert!(!list.enabled);
+    assert_eq!(
+        list.hosts,
+        [
+            HostEntry {
+                family: HostFamily::LocalHost,
+                address: Vec::new(),
+            },
+            HostEntry {
+                family: HostFamily::Unknown(77),
+                address: alloc::vec![1, 2, 3],
+            },
+        ]
+    );
+}
@@ -4,46 +4,71 @@
 
 use crate::{
     auto::xproto::{
-        AccessControl, ArcMode, Atom, AutoRepeatMode, BackingStore, BellRequest, CapStyle,
-        ChangeActivePointerGrabRequest, ChangeGcRequest, ChangeKeyboardControlRequest,
-        ChangePointerControlRequest, ChangeWindowAttributesRequest, CloseDown, Colormap,
-        ColormapAlloc, CreateColormapRequest, CreateCursorRequest, CreateGcRequest,
-        CreateWindowRequest, Cursor, Cw, Drawable, EventMask, FillRule, FillStyle, Font,
+        AccessControl, Allow, AllowEventsRequest, ArcMode, Atom, AutoRepeatMode, BackingStore,
+        BellRequest, ButtonIndex, ButtonPressEvent, CapStyle, ChangeActivePointerGrabRequest,
+        ChangeGcRequest, ChangeHostsRequest, ChangeKeyboardControlRequest,
+        ChangePointerControlRequest, ChangeWindowAttributesRequest, ClientMessageEvent, CloseDown,
+        Colormap, ColormapAlloc, CreateColormapRequest, CreateCursorRequest, CreateGcRequest,
+        CreateWindowRequest, Cursor, Cw, Drawable, EventMask, Family, FillRule, FillStyle, Font,
         ForceScreenSaverRequest, Gc, Gcontext, GetAtomNameRequest, GetKeyboardMappingReply,
         GetKeyboardMappingRequest, GetModifierMappingReply, GetModifierMappingRequest,
-        GrabServerRequest, Gravity, Gx, InternAtomRequest, JoinStyle, Kb, Keycode, Keysym, LedMode,
-        LineStyle, Pixmap, QueryExtensionRequest, ScreenSaver, SendEventRequest,
-        SetAccessControlRequest, SetCloseDownModeRequest, SubwindowMode, Timestamp,
-        UngrabServerRequest, Visualid, Window, WindowClass,
+        GetPointerControlReply, GetPointerControlRequest, GetPointerMappingReply,
+        GetPointerMappingRequest, GrabButtonRequest, GrabServerRequest, Gravity, Gx, HostMode,
+        InternAtomRequest, JoinStyle, Kb, Keycode, Keysym, LedMode, LineStyle, MappingStatus,
+        ModMask, Pixmap, QueryExtensionRequest, ScreenSaver, SendEventRequest,
+        SetAccessControlRequest, SetCloseDownModeRequest, SetModifierMappingRequest, SubwindowMode,
+        UngrabButtonRequest, UngrabKeyboardRequest, UngrabPointerRequest, UngrabServerRequest,
+        Visualid, Window, WindowClass, ATOM_ATOM,
     },
-    display::{generate_xid, Display, RequestCookie},
-    Event, Extension,
+    display::{generate_xid, output::str_to_key, Display, RequestCookie, RequestInfo},
+    Event, Extension, Time,
 };
-use alloc::{borrow::Cow, boxed::Box, string::String};
+use alloc::{borrow::Cow, boxed::Box, string::String, vec::Vec};
+use core::num::NonZeroU32;
 use cty::c_char;
 
+#[cfg(feature = "std")]
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
 #[cfg(feature = "async")]
 use crate::{
-    auto::xproto::{GetAtomNameReply, InternAtomReply, QueryExtensionReply},
+    auto::xproto::{GetAtomNameReply, InternAtomReply, SetModifierMappingReply},
     display::{
-        futures::{ExchangeRequestFuture, ExchangeXidFuture, MapFuture, SendRequestFuture},
+        futures::{
+            CreateWindowCheckedFuture, ExchangeRequestFuture, ExchangeXidFuture, FocusClickFuture,
+            MapFuture, NetSupportedFuture, QueryExtensionFuture, SendRequestFuture,
+        },
         AsyncDisplay,
     },
     util::BoxedFnOnce,
 };
 
+mod cached_window;
 mod colormap;
 mod cursor;
 mod drawable;
 mod gcontext;
+mod host;
 mod pixmap;
+mod resource;
+mod screen;
+mod selection;
+mod size_hints;
 mod window;
 
+pub use cached_window::*;
 pub use colormap::*;
 pub use cursor::*;
 pub use drawable::*;
 pub use gcontext::*;
+pub use host::*;
 pub use pixmap::*;
+pub use resource::*;
+pub use selection::*;
+pub use size_hints::*;
 pub use window::*;
 
 crate::create_paramaterizer! {
@@ -204,6 +229,129 @@ impl<'a> From<GetModifierMappingReply<'a>> for ModifierMapping {
     }
 }
 
+impl ModifierMapping {
+    /// The number of modifiers in a modifier mapping: `Shift`, `Lock`, `Control`, and `Mod1` through `Mod5`.
+    pub const MODIFIER_COUNT: usize = 8;
+
+    /// Get the keycodes mapped to a modifier, where `modifier` is the modifier's index in the mapping (zero
+    /// for `Shift`, through seven for `Mod5`). Unused entries are zero.
+    #[inline]
+    #[must_use]
+    pub fn modifier_keycodes(&self, modifier: usize) -> &[Keycode] {
+        let per = usize::from(self.keycodes_per_modifier);
+        self.keycodes
+            .get(modifier * per..(modifier + 1) * per)
+            .unwrap_or(&[])
+    }
+
+    /// Find out which modifiers are mapped to a different set of keycodes in `new` than in this mapping. The
+    /// order of keycodes within a modifier and the amount of padding do not matter.
+    #[inline]
+    #[must_use]
+    pub fn diff(&self, new: &ModifierMapping) -> ModifierMapDiff {
+        #[inline]
+        fn keycode_set(mapping: &ModifierMapping, modifier: usize) -> Vec<Keycode> {
+            let mut keycodes: Vec<Keycode> = mapping
+                .modifier_keycodes(modifier)
+                .iter()
+                .copied()
+                .filter(|&kc| kc != 0)
+                .collect();
+            keycodes.sort_unstable();
+            keycodes.dedup();
+            keycodes
+        }
+
+        let changed = (0..Self::MODIFIER_COUNT)
+            .filter(|&modifier| keycode_set(self, modifier) != keycode_set(new, modifier))
+            .fold(0, |mask, modifier| mask | (1 << modifier));
+
+        ModifierMapDiff {
+            changed: ModMask { inner: changed },
+        }
+    }
+}
+
+/// The difference between two `ModifierMapping`s, as returned by `ModifierMapping::diff`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ModifierMapDiff {
+    /// The modifiers whose keycodes have changed.
+    pub changed: ModMask,
+}
+
+impl ModifierMapDiff {
+    /// Whether or not no modifiers have changed.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.changed.inner == 0
+    }
+}
+
+/// A mapping from physical pointer buttons to logical buttons.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PointerMapping {
+    /// The logical button for each physical button, starting at button 1. A logical button of zero
+    /// means that the physical button is disabled.
+    pub map: Box<[u8]>,
+}
+
+impl<'a> From<GetPointerMappingReply<'a>> for PointerMapping {
+    #[inline]
+    fn from(gpmr: GetPointerMappingReply<'a>) -> Self {
+        Self {
+            map: gpmr.map.into_owned().into_boxed_slice(),
+        }
+    }
+}
+
+impl PointerMapping {
+    /// The number of physical buttons on the pointer.
+    #[inline]
+    #[must_use]
+    pub fn button_count(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Map a physical button, as reported in a `ButtonPress` or `ButtonRelease` event, to its logical
+    /// button. Buttons outside of the mapping are returned unchanged, since some devices report more
+    /// buttons than the server's mapping covers.
+    #[inline]
+    #[must_use]
+    pub fn logical_button(&self, button: u8) -> u8 {
+        match (button as usize)
+            .checked_sub(1)
+            .and_then(|i| self.map.get(i))
+        {
+            Some(logical) => *logical,
+            None => button,
+        }
+    }
+}
+
+/// The acceleration and threshold currently used for the pointer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct PointerControl {
+    /// The numerator of the acceleration. Once the pointer has moved more than `threshold` pixels at once,
+    /// it moves `accel_numerator / accel_denominator` times faster than normal.
+    pub accel_numerator: u16,
+    /// The denominator of the acceleration.
+    pub accel_denominator: u16,
+    /// The number of pixels the pointer has to move at once before it is accelerated.
+    pub threshold: u16,
+}
+
+impl From<GetPointerControlReply> for PointerControl {
+    #[inline]
+    fn from(gpcr: GetPointerControlReply) -> Self {
+        Self {
+            accel_numerator: gpcr.acceleration_numerator,
+            accel_denominator: gpcr.acceleration_denominator,
+            threshold: gpcr.threshold,
+        }
+    }
+}
+
 #[inline]
 fn create_window_request(
     wid: Window,
@@ -277,6 +425,19 @@ fn create_simple_window_request(
     cwr
 }
 
+/// Prepare a `CreateWindowRequest` to be sent in checked mode, whatever mode the display is in, so that
+/// its error can be picked up after synchronizing.
+#[inline]
+pub(crate) fn create_simple_window_checked_request<Dpy: DisplayBase + ?Sized>(
+    dpy: &Dpy,
+    cw: CreateWindowRequest,
+) -> RequestInfo {
+    let mut cw =
+        RequestInfo::from_request(cw, dpy.bigreq_enabled(), dpy.effective_max_request_bytes());
+    cw.checked = Some(true);
+    cw
+}
+
 /// Create a `CreateGcRequest`.
 #[inline]
 fn create_gc_request(cid: Gcontext, drawable: Drawable, props: GcParameters) -> CreateGcRequest {
@@ -291,6 +452,68 @@ fn create_gc_request(cid: Gcontext, drawable: Drawable, props: GcParameters) ->
     gcr
 }
 
+/// The parameters used by `create_gc_default`, given the black pixel of the default screen.
+#[inline]
+fn default_gc_parameters(black_pixel: u32) -> GcParameters {
+    GcParameters {
+        foreground: Some(black_pixel),
+        ..Default::default()
+    }
+}
+
+/// The parameters used by `create_gc_no_exposures`, given the black pixel of the default screen.
+#[inline]
+fn no_exposures_gc_parameters(black_pixel: u32) -> GcParameters {
+    GcParameters {
+        graphics_exposures: Some(0),
+        ..default_gc_parameters(black_pixel)
+    }
+}
+
+/// The passive grab used by `click_to_focus_grab`.
+#[inline]
+fn click_to_focus_grab_request(window: Window) -> GrabButtonRequest {
+    GrabButtonRequest {
+        owner_events: false,
+        grab_window: window,
+        event_mask: EventMask::BUTTON_PRESS,
+        pointer_mode: GrabMode::Sync,
+        keyboard_mode: GrabMode::Async,
+        button: ButtonIndex::Any,
+        modifiers: ModMask::ANY,
+        ..Default::default()
+    }
+}
+
+/// The requests sent by `handle_focus_click`, in the order they are sent. Each one uses the event's own
+/// timestamp.
+#[inline]
+fn focus_click_requests(
+    event: &ButtonPressEvent,
+) -> (
+    SetInputFocusRequest,
+    AllowEventsRequest,
+    UngrabButtonRequest,
+) {
+    let time = Time::from(event.time);
+    (
+        event
+            .event
+            .set_input_focus_request(InputFocus::PointerRoot, time),
+        AllowEventsRequest {
+            mode: Allow::ReplayPointer,
+            time: time.into(),
+            ..Default::default()
+        },
+        UngrabButtonRequest {
+            button: ButtonIndex::Any,
+            grab_window: event.event,
+            modifiers: ModMask::ANY,
+            ..Default::default()
+        },
+    )
+}
+
 /// Create an `InternAtomRequest` for our use.
 #[inline]
 fn intern_atom_request(name: Cow<'_, str>, exists: bool) -> InternAtomRequest<'_> {
@@ -301,6 +524,16 @@ fn intern_atom_request(name: Cow<'_, str>, exists: bool) -> InternAtomRequest<'_
     }
 }
 
+/// Map the atom the server returns for a nonexistent atom (zero) to `None`.
+#[inline]
+fn existing_atom(atom: Atom) -> Option<Atom> {
+    if atom.xid() == 0 {
+        None
+    } else {
+        Some(atom)
+    }
+}
+
 /// Change Keyboard Control Request
 #[inline]
 fn change_keyboard_control_request(props: KbParameters) -> ChangeKeyboardControlRequest {
@@ -314,12 +547,12 @@ fn change_keyboard_control_request(props: KbParameters) -> ChangeKeyboardControl
 fn change_active_pointer_grab_request(
     event_mask: EventMask,
     cursor: Cursor,
-    time: Option<Timestamp>,
+    time: Time,
 ) -> ChangeActivePointerGrabRequest {
     ChangeActivePointerGrabRequest {
         cursor,
         event_mask,
-        time: time.unwrap_or(0),
+        time: time.into(),
         ..Default::default()
     }
 }
@@ -373,15 +606,53 @@ fn create_cursor_request(
     }
 }
 
+/// The destination of a `SendEvent` request.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SendEventDest {
+    /// The window that the pointer is currently in.
+    PointerWindow,
+    /// The window that currently has the input focus. If the pointer is in a child of the focus
+    /// window, the event is sent to that child instead.
+    InputFocus,
+    /// A specific window.
+    Window(Window),
+}
+
+impl SendEventDest {
+    /// Get the value that will be sent over the wire for this destination.
+    #[inline]
+    #[must_use]
+    pub fn as_window(self) -> Window {
+        match self {
+            Self::PointerWindow => Window::const_from_xid(0),
+            Self::InputFocus => Window::const_from_xid(1),
+            Self::Window(w) => w,
+        }
+    }
+}
+
+impl From<Window> for SendEventDest {
+    #[inline]
+    fn from(w: Window) -> Self {
+        Self::Window(w)
+    }
+}
+
 #[inline]
-fn send_event_request(target: Window, em: EventMask, event: Event) -> SendEventRequest {
+fn send_event_request(
+    propagate: bool,
+    target: SendEventDest,
+    em: EventMask,
+    event: Event,
+) -> SendEventRequest {
     let mut bytes: [u8; 32] = [0; 32];
     event.as_bytes(&mut bytes);
 
     bytes[0] = event.opcode();
 
     SendEventRequest {
-        destination: target,
+        propagate,
+        destination: target.as_window(),
         event_mask: em,
         event: bytemuck::cast::<_, [c_char; 32]>(bytes),
         ..Default::default()
@@ -405,6 +676,75 @@ fn create_colormap_request(
     }
 }
 
+/// Set modifier mapping request.
+#[inline]
+fn set_modifier_mapping_request(mapping: &ModifierMapping) -> SetModifierMappingRequest<'_> {
+    SetModifierMappingRequest {
+        keycodes_per_modifier: mapping.keycodes_per_modifier,
+        keycodes: Cow::Borrowed(&mapping.keycodes),
+        ..Default::default()
+    }
+}
+
+/// Change hosts request.
+#[inline]
+fn change_host_request(mode: HostMode, family: Family, address: &[u8]) -> ChangeHostsRequest<'_> {
+    ChangeHostsRequest {
+        mode,
+        family,
+        address: Cow::Borrowed(address),
+        ..Default::default()
+    }
+}
+
+/// If automatic ping responses are enabled and `event` is a `_NET_WM_PING` message, get the request that
+/// sends it back to the root window as the EWMH specifies.
+#[inline]
+pub(crate) fn net_wm_pong_request<D: DisplayBase + ?Sized>(
+    dpy: &D,
+    event: &Event,
+) -> Option<SendEventRequest> {
+    let ping = dpy.net_wm_ping_atom()?.get();
+
+    let cme = match (event, dpy.wm_protocols_atom()) {
+        (Event::ClientMessage(cme), Some(wpa))
+            if wpa.get() == cme.ty.xid() && cme.format == 32 && cme.data.longs()[0] == ping =>
+        {
+            cme
+        }
+        _ => return None,
+    };
+
+    log::debug!("Responding to _NET_WM_PING");
+    let root = dpy.default_root();
+    let pong = ClientMessageEvent {
+        window: root,
+        ..cme.clone()
+    };
+    Some(send_event_request(
+        false,
+        SendEventDest::Window(root),
+        EventMask::SUBSTRUCTURE_NOTIFY | EventMask::SUBSTRUCTURE_REDIRECT,
+        Event::ClientMessage(pong),
+    ))
+}
+
+/// If automatic ping responses are enabled and `event` is a `_NET_WM_PING` message, send it back to the root
+/// window as the EWMH specifies and return `true`.
+#[inline]
+pub(crate) fn answer_net_wm_ping<D: Display + ?Sized>(
+    dpy: &mut D,
+    event: &Event,
+) -> crate::Result<bool> {
+    match net_wm_pong_request(dpy, event) {
+        Some(pong) => {
+            dpy.exchange_request(pong)?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
 pub trait DisplayXprotoExt: Display {
     /// Query for extension information.
     #[inline]
@@ -420,16 +760,77 @@ pub trait DisplayXprotoExt: Display {
 
     /// Query for extension information, but resolve immediately. The `ExtensionNotPresent` error is
     /// returned when the extension is not found.
+    ///
+    /// The display's extension cache is checked first, and the result is stored there, so each
+    /// extension is only queried once per connection. This includes extensions that are not present.
     #[inline]
     fn query_extension_immediate<'a, Name: Into<Cow<'a, str>>>(
         &mut self,
         name: Name,
     ) -> crate::Result<Extension> {
+        let name = name.into();
+        let key = str_to_key(&name);
+        if let Some(ext) = self.get_extension(&key) {
+            return Ok(ext);
+        }
+        if self.extension_absent(&key) {
+            return Err(crate::BreadError::ExtensionNotPresent(
+                name.into_owned().into(),
+            ));
+        }
+
         let qer = self.exchange_request(QueryExtensionRequest {
-            name: name.into(),
+            name: Cow::Borrowed(&name),
             ..Default::default()
         })?;
-        Extension::from_reply(qer)
+        match Extension::from_reply(qer, &name) {
+            Ok(ext) => {
+                self.set_extension(key, ext);
+                Ok(ext)
+            }
+            Err(e) => {
+                self.set_extension_absent(key);
+                Err(e)
+            }
+        }
+    }
+
+    /// Run `f` only if the server supports the given extension, returning `None` if it doesn't. This is
+    /// useful for features that should degrade gracefully when an extension is missing, e.g.:
+    ///
+    /// ```rust,no_run
+    /// # use breadx::prelude::*;
+    /// # fn f(conn: &mut breadx::DisplayConnection) -> breadx::Result {
+    /// let outputs = conn.with_extension("RANDR", |conn| {
+    ///     // query outputs using RandR
+    /// #   Ok(())
+    /// })?;
+    /// if outputs.is_none() {
+    ///     // fall back to the size of the root window
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Whether or not the extension is present is cached in the display, so only the first call for a given
+    /// extension has to ask the server.
+    ///
+    /// # Errors
+    ///
+    /// If the extension is present but `f` fails, its error is returned as-is, so that a missing extension
+    /// can be told apart from one that failed. Errors that occur while querying for the extension are
+    /// returned as well.
+    #[inline]
+    fn with_extension<'a, T, Name: Into<Cow<'a, str>>, F: FnOnce(&mut Self) -> crate::Result<T>>(
+        &mut self,
+        name: Name,
+        f: F,
+    ) -> crate::Result<Option<T>> {
+        match self.query_extension_immediate(name) {
+            Ok(_) => f(self).map(Some),
+            Err(crate::BreadError::ExtensionNotPresent(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
     }
 
     /// Create a new window.
@@ -495,6 +896,54 @@ pub trait DisplayXprotoExt: Display {
         Ok(wid)
     }
 
+    /// Create a new simple window, making sure that any error caused by the request (e.g. a bogus
+    /// parent window or pixel value) is returned from this function instead of showing up later.
+    ///
+    /// The request is always tracked and followed by a synchronization, whatever mode the display is
+    /// in, which costs an extra round trip. The display's checked mode is left alone. If
+    /// `validate_parent` is true, the parent's attributes are queried beforehand, which costs
+    /// another. Performance-sensitive code should use `create_simple_window` instead.
+    #[inline]
+    fn create_simple_window_checked(
+        &mut self,
+        parent: Window,
+        x: i16,
+        y: i16,
+        width: u16,
+        height: u16,
+        border_width: u16,
+        border: u32,
+        background: u32,
+        validate_parent: bool,
+    ) -> crate::Result<Window> {
+        if validate_parent {
+            parent.window_attributes_immediate(self)?;
+        }
+
+        let wid = Window::const_from_xid(generate_xid(self)?);
+        let cw = create_simple_window_checked_request(
+            self,
+            create_simple_window_request(
+                wid,
+                parent,
+                x,
+                y,
+                width,
+                height,
+                border_width,
+                border,
+                background,
+            ),
+        );
+
+        // track this one request, regardless of the display's mode, and wait for its error
+        let seq = self.send_request_raw(cw)?;
+        self.synchronize()?;
+        self.take_pending_request(seq);
+        self.check_for_pending_error(seq)?;
+        Ok(wid)
+    }
+
     /// Create a new graphics context for the specified target.
     #[inline]
     fn create_gc<Target: Into<Drawable>>(
@@ -508,7 +957,36 @@ pub trait DisplayXprotoExt: Display {
         Ok(gid)
     }
 
-    /// Intern a string and get a corresponding atom for that string.
+    /// Create a new graphics context for the specified target, with the default screen's black pixel as its
+    /// foreground and every other component left at the server's default.
+    #[inline]
+    fn create_gc_default<Target: Into<Drawable>>(
+        &mut self,
+        target: Target,
+    ) -> crate::Result<Gcontext> {
+        let props = default_gc_parameters(self.default_screen().black_pixel);
+        self.create_gc(target, props)
+    }
+
+    /// Create a new graphics context for the specified target, like `create_gc_default`, but with graphics
+    /// exposures turned off.
+    ///
+    /// By default, every `CopyArea` or `CopyPlane` that uses a GC generates either `GraphicsExposure` events
+    /// (for parts of the source that were obscured and couldn't be copied) or a single `NoExposure` event.
+    /// Code that only copies from pixmaps, such as double buffering, never needs these and would otherwise
+    /// have to filter them out of the event queue. If the source may be a partially covered window, use
+    /// `create_gc_default` instead and redraw the areas that the `GraphicsExposure` events report.
+    #[inline]
+    fn create_gc_no_exposures<Target: Into<Drawable>>(
+        &mut self,
+        target: Target,
+    ) -> crate::Result<Gcontext> {
+        let props = no_exposures_gc_parameters(self.default_screen().black_pixel);
+        self.create_gc(target, props)
+    }
+
+    /// Intern a string and get a corresponding atom for that string. The name can be borrowed (e.g.
+    /// a `&str` literal), so there is no need to allocate a `String` for it.
     #[inline]
     fn intern_atom<'a, Name: Into<Cow<'a, str>>>(
         &mut self,
@@ -520,6 +998,9 @@ pub trait DisplayXprotoExt: Display {
     }
 
     /// Intern an atom, but try to resolve the request immediately.
+    ///
+    /// Note that if `only_if_exists` is true and the atom does not exist, the server replies with atom zero
+    /// (`None` in the protocol). Use `intern_atom_if_exists` in order to get an `Option` instead.
     #[inline]
     fn intern_atom_immediate<'a, Name: Into<Cow<'a, str>>>(
         &mut self,
@@ -530,6 +1011,18 @@ pub trait DisplayXprotoExt: Display {
         Ok(self.resolve_request(r)?.atom)
     }
 
+    /// Get the atom for a string, without creating it if it does not exist yet. The server's sentinel atom
+    /// zero is mapped to `None`, so this is useful for probing whether an optional atom (e.g. a hint that only
+    /// some window managers use) is known to the server.
+    #[inline]
+    fn intern_atom_if_exists<'a, Name: Into<Cow<'a, str>>>(
+        &mut self,
+        name: Name,
+    ) -> crate::Result<Option<Atom>> {
+        let atom = self.intern_atom_immediate(name, true)?;
+        Ok(existing_atom(atom))
+    }
+
     /// Change the keyboard's control properties.
     #[inline]
     fn change_keyboard_control(&mut self, props: KbParameters) -> crate::Result<()> {
@@ -555,17 +1048,84 @@ pub trait DisplayXprotoExt: Display {
         })
     }
 
+    /// Get the hosts that are allowed to connect to the display while access control is enabled, as
+    /// well as whether access control is enabled.
+    #[inline]
+    fn list_hosts(&mut self) -> crate::Result<(bool, Vec<HostEntry>)> {
+        let HostList { enabled, hosts } =
+            self.exchange_request(ListHostEntriesRequest::default())?;
+        Ok((enabled, hosts))
+    }
+
+    /// Add a host to or remove a host from the access list.
+    #[inline]
+    fn change_host(&mut self, mode: HostMode, family: Family, address: &[u8]) -> crate::Result {
+        self.exchange_request(change_host_request(mode, family, address))
+    }
+
     /// Change the active pointer grab.
     #[inline]
     fn change_active_pointer_grab(
         &mut self,
         event_mask: EventMask,
         cursor: Cursor,
-        time: Option<Timestamp>,
+        time: Time,
     ) -> crate::Result {
         self.exchange_request(change_active_pointer_grab_request(event_mask, cursor, time))
     }
 
+    /// Release an active pointer grab held by this client.
+    #[inline]
+    fn ungrab_pointer(&mut self, time: Time) -> crate::Result {
+        self.exchange_request(UngrabPointerRequest {
+            time: time.into(),
+            ..Default::default()
+        })
+    }
+
+    /// Release an active keyboard grab held by this client.
+    #[inline]
+    fn ungrab_keyboard(&mut self, time: Time) -> crate::Result {
+        self.exchange_request(UngrabKeyboardRequest {
+            time: time.into(),
+            ..Default::default()
+        })
+    }
+
+    /// Release events queued by the server as the result of a frozen grab.
+    #[inline]
+    fn allow_events(&mut self, mode: Allow, time: Time) -> crate::Result {
+        self.exchange_request(AllowEventsRequest {
+            mode,
+            time: time.into(),
+            ..Default::default()
+        })
+    }
+
+    /// Set up a click-to-focus grab on `window`. This establishes a synchronous passive grab on every pointer
+    /// button with any modifiers, so that clicking on the window freezes the pointer and reports a
+    /// `ButtonPress` to this client. That event should be passed to `handle_focus_click`, which focuses the
+    /// window and lets the click through to the client.
+    #[inline]
+    fn click_to_focus_grab(&mut self, window: Window) -> crate::Result {
+        self.exchange_request(click_to_focus_grab_request(window))
+    }
+
+    /// Handle a `ButtonPress` caused by a grab set up by `click_to_focus_grab`. This gives the input focus
+    /// to the clicked window, replays the click so that the client receives it as if no grab had been
+    /// present, and removes the passive grab so that later clicks on the focused window are not
+    /// intercepted. Call `click_to_focus_grab` on the window again once it loses the focus.
+    ///
+    /// The event's own timestamp is used for every request, so a stale click cannot steal the focus
+    /// back from a newer one.
+    #[inline]
+    fn handle_focus_click(&mut self, event: &ButtonPressEvent) -> crate::Result {
+        let (focus, allow, ungrab) = focus_click_requests(event);
+        self.exchange_request(focus)?;
+        self.exchange_request(allow)?;
+        self.exchange_request(ungrab)
+    }
+
     /// Set the close down mode.
     #[inline]
     fn set_close_down_mode(&mut self, mode: CloseDown) -> crate::Result {
@@ -575,7 +1135,9 @@ pub trait DisplayXprotoExt: Display {
         })
     }
 
-    /// Change variables for the pointer.
+    /// Change variables for the pointer. The acceleration fraction is only applied if
+    /// `do_acceleration` is true, and the threshold is only applied if `do_threshold` is true. A
+    /// value of `-1` restores the server's default for that field.
     #[inline]
     fn change_pointer_control(
         &mut self,
@@ -594,6 +1156,19 @@ pub trait DisplayXprotoExt: Display {
         ))
     }
 
+    /// Get the variables for the pointer.
+    #[inline]
+    fn get_pointer_control(&mut self) -> crate::Result<RequestCookie<GetPointerControlRequest>> {
+        self.send_request(GetPointerControlRequest::default())
+    }
+
+    /// Get the variables for the pointer, resolving immediately.
+    #[inline]
+    fn get_pointer_control_immediate(&mut self) -> crate::Result<PointerControl> {
+        self.exchange_request(GetPointerControlRequest::default())
+            .map(PointerControl::from)
+    }
+
     /// Create a new cursor.
     #[inline]
     fn create_cursor(
@@ -625,10 +1200,41 @@ pub trait DisplayXprotoExt: Display {
         })
     }
 
-    /// Send an event to the X server.
+    /// Reset the screensaver's idle timer, as if the user had pressed a key. If the screensaver is active,
+    /// it is deactivated.
+    ///
+    /// This is meant for programs like slideshows and media players that need to keep the screen from
+    /// blanking while they are in use, without changing the user's screensaver settings. Call it at an
+    /// interval shorter than the screensaver timeout (see `GetScreenSaver`); once a minute is fine for
+    /// typical timeouts. Unlike disabling the screensaver, there is nothing to restore afterwards: once the
+    /// program stops calling this, the screen blanks normally.
+    ///
+    /// Note that this only affects the X server's own idle timer. Under `XWayland`, idling is managed by the
+    /// Wayland compositor, which may ignore this request.
     #[inline]
-    fn send_event(&mut self, target: Window, mask: EventMask, event: Event) -> crate::Result {
-        self.exchange_request(send_event_request(target, mask, event))
+    fn reset_screen_saver(&mut self) -> crate::Result {
+        self.force_screensaver(ScreenSaver::Reset)
+    }
+
+    /// Send an event to a window. The event is sent to every client that selected any of the events
+    /// in `mask` on the destination window, or to the window's creator if `mask` is empty.
+    ///
+    /// If `propagate` is true and no client selected the events on the destination, the event is
+    /// propagated up the window hierarchy the same way a device event would be.
+    #[inline]
+    fn send_event<Dest: Into<SendEventDest>>(
+        &mut self,
+        propagate: bool,
+        destination: Dest,
+        mask: EventMask,
+        event: Event,
+    ) -> crate::Result {
+        self.exchange_request(send_event_request(
+            propagate,
+            destination.into(),
+            mask,
+            event,
+        ))
     }
 
     /// Create a new colormap.
@@ -679,6 +1285,69 @@ pub trait DisplayXprotoExt: Display {
         Ok(repl.into())
     }
 
+    /// Set the modifier mapping for this display.
+    ///
+    /// The server replies with `MappingStatus::Busy` if any of the new modifier keys are currently held
+    /// down, in which case nothing is changed. See `set_modifier_mapping_blocking` for a version that
+    /// retries.
+    #[inline]
+    fn set_modifier_mapping(&mut self, mapping: &ModifierMapping) -> crate::Result<MappingStatus> {
+        self.exchange_request(set_modifier_mapping_request(mapping))
+            .map(|repl| repl.status)
+    }
+
+    /// Set the modifier mapping for this display, retrying while the server reports that it is busy
+    /// until `timeout` has passed.
+    ///
+    /// `Busy` means that a modifier key is held down, so this sleeps for a short while between
+    /// attempts in order to give the user time to release it. The final status is returned: `Success`
+    /// if the mapping was changed, `Failure` if the server refused it outright (e.g. because it does not
+    /// allow one of the keycodes as a modifier), or `Busy` if the timeout expired.
+    #[cfg(feature = "std")]
+    #[inline]
+    fn set_modifier_mapping_blocking(
+        &mut self,
+        mapping: &ModifierMapping,
+        timeout: Duration,
+    ) -> crate::Result<MappingStatus> {
+        const MAX_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+        let start = Instant::now();
+        let mut delay = Duration::from_millis(5);
+
+        loop {
+            let status = self.set_modifier_mapping(mapping)?;
+            let elapsed = start.elapsed();
+            if status != MappingStatus::Busy || elapsed >= timeout {
+                return Ok(status);
+            }
+
+            log::debug!("Modifier mapping is busy, retrying in {:?}", delay);
+            thread::sleep(delay.min(timeout.checked_sub(elapsed).unwrap_or_default()));
+            delay = (delay * 2).min(MAX_RETRY_DELAY);
+        }
+    }
+
+    /// Get the pointer mapping for this display.
+    #[inline]
+    fn get_pointer_mapping(&mut self) -> crate::Result<RequestCookie<GetPointerMappingRequest>> {
+        self.send_request(GetPointerMappingRequest::default())
+    }
+
+    /// Immediately get the pointer mapping for this display.
+    #[inline]
+    fn get_pointer_mapping_immediate(&mut self) -> crate::Result<PointerMapping> {
+        let tok = self.get_pointer_mapping()?;
+        let repl = self.resolve_request(tok)?;
+        Ok(repl.into())
+    }
+
+    /// Get the number of physical buttons on the pointer. This queries the pointer mapping.
+    #[inline]
+    fn pointer_button_count(&mut self) -> crate::Result<usize> {
+        Ok(self.get_pointer_mapping_immediate()?.button_count())
+    }
+
     /// Grab the server.
     #[inline]
     fn grab_server(&mut self) -> crate::Result {
@@ -709,6 +1378,142 @@ pub trait DisplayXprotoExt: Display {
         })
         .map(|ganr| ganr.name.into_owned())
     }
+
+    /// Give up ownership of a selection, by setting its owner to `None`. The selection is left without an
+    /// owner, so other clients stop asking this client for its contents. As with taking ownership, this is
+    /// ignored by the server if `time` is older than the last time the selection changed hands.
+    ///
+    /// Clipboard clients should do this before they exit or destroy the owner window. `Window::own_selection`
+    /// returns a handle that does it automatically.
+    #[inline]
+    fn disown_selection(&mut self, selection: Atom, time: Time) -> crate::Result {
+        Window::const_from_xid(0).set_selection_owner(self, selection, time)
+    }
+
+    /// Search the window tree for a window that matches a predicate, starting at `root` (which is tested
+    /// as well), and return the first match. This is the core of tools like `xdotool search`.
+    ///
+    /// The tree is walked depth-first using `QueryTree`, and sibling windows are visited from the bottom of
+    /// the stacking order to the top. The predicate is given the display, so it can inspect the window by
+    /// sending requests (e.g. reading its `WM_CLASS` property). Since other clients may destroy windows while
+    /// the search is running, a window is skipped along with its children if either the predicate or
+    /// `QueryTree` fails on it with a `BadWindow` error. Any other error returned by the predicate stops the
+    /// search.
+    ///
+    /// This sends one request per window in the tree, plus whatever the predicate sends, so it can be slow on
+    /// a busy desktop. Consider using `GrabServer` around it if the tree must not change during the search.
+    #[inline]
+    fn find_window<F: FnMut(Window, &mut Self) -> crate::Result<bool>>(
+        &mut self,
+        root: Window,
+        mut predicate: F,
+    ) -> crate::Result<Option<Window>> {
+        const BAD_WINDOW: u8 = 3;
+
+        #[inline]
+        fn is_bad_window(err: &crate::BreadError) -> bool {
+            matches!(err, crate::BreadError::XProtocol { error_code, .. } if error_code.0 == BAD_WINDOW)
+        }
+
+        let mut stack = Vec::with_capacity(16);
+        stack.push(root);
+
+        while let Some(window) = stack.pop() {
+            match predicate(window, self) {
+                Ok(true) => return Ok(Some(window)),
+                Ok(false) => (),
+                Err(ref e) if is_bad_window(e) => {
+                    log::debug!("Window {:?} was destroyed during the search", window);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            }
+
+            match self.exchange_request(QueryTreeRequest {
+                window,
+                ..Default::default()
+            }) {
+                // the stack is last-in first-out, so push the children in reverse
+                Ok(qtr) => stack.extend(qtr.children.iter().rev().copied()),
+                Err(ref e) if is_bad_window(e) => {
+                    log::debug!("Window {:?} was destroyed during the search", window);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Get the list of EWMH hints supported by the window manager, as advertised by the `_NET_SUPPORTED`
+    /// property on the root window. If the window manager does not support EWMH, the list is empty. If the
+    /// property is not a list of atoms, `BreadError::BadObjectRead` is returned.
+    #[inline]
+    fn net_supported(&mut self) -> crate::Result<Vec<Atom>> {
+        let net_supported = self.intern_atom_immediate("_NET_SUPPORTED", true)?;
+        if net_supported.xid() == 0 {
+            return Ok(Vec::new());
+        }
+
+        let root = self.default_root();
+        Ok(root
+            .get_property_u32(self, net_supported, ATOM_ATOM)?
+            .unwrap_or_default()
+            .into_iter()
+            .map(Atom::const_from_xid)
+            .collect())
+    }
+
+    /// Enable or disable automatically responding to `_NET_WM_PING` messages. Window managers and compositors
+    /// send these to check whether a program is still responsive, and mark programs that never answer as "not
+    /// responding".
+    ///
+    /// While this is enabled, `wait_for_event` sends pings back to the root window instead of returning them.
+    /// Other client messages are returned as usual. Note that the window manager only sends pings to windows
+    /// that list `_NET_WM_PING` in their `WM_PROTOCOLS` property; `Window::set_wm_protocols` adds it to the
+    /// list automatically while this is enabled, so this should be called before setting the protocols.
+    /// `wait_for_event_async` responds to pings in the same way.
+    ///
+    /// # Errors
+    ///
+    /// Returns `BreadError::StaticMsg` if the display does not store the `_NET_WM_PING` atom, which is the
+    /// case for displays that use the default `DisplayBase::set_net_wm_ping_atom`.
+    #[inline]
+    fn enable_auto_ping_response(&mut self, enable: bool) -> crate::Result {
+        if !enable {
+            self.set_net_wm_ping_atom(None);
+            return Ok(());
+        }
+
+        if self.wm_protocols_atom().is_none() {
+            let wpa = self.intern_atom_immediate("WM_PROTOCOLS", false)?;
+            self.set_wm_protocols_atom(NonZeroU32::new(wpa.xid()).ok_or(
+                crate::BreadError::StaticMsg("Unable to intern WM_PROTOCOLS atom"),
+            )?);
+        }
+
+        if self.net_wm_ping_atom().is_none() {
+            let nwpa = self.intern_atom_immediate("_NET_WM_PING", false)?;
+            self.set_net_wm_ping_atom(Some(NonZeroU32::new(nwpa.xid()).ok_or(
+                crate::BreadError::StaticMsg("Unable to intern _NET_WM_PING atom"),
+            )?));
+            if self.net_wm_ping_atom().is_none() {
+                return Err(crate::BreadError::StaticMsg(
+                    "Display does not support automatic ping responses",
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Tell whether or not the window manager supports an EWMH hint, by looking for it in
+    /// `_NET_SUPPORTED`. Note that this requests the entire list every time it is called; use
+    /// `net_supported` to check several hints at once.
+    #[inline]
+    fn wm_supports(&mut self, hint: Atom) -> crate::Result<bool> {
+        Ok(self.net_supported()?.contains(&hint))
+    }
 }
 
 impl<D: Display + ?Sized> DisplayXprotoExt for D {}
@@ -729,21 +1534,15 @@ pub trait AsyncDisplayXprotoExt: AsyncDisplay {
 
     /// Query for extension information, but resolve immediately redox . The `Error::ExtensionNotPresent`
     /// error is returned when the extension is not found.
+    ///
+    /// Like `query_extension_immediate`, the display's extension cache is checked first, and the result is
+    /// stored there, including extensions that are not present.
     #[inline]
     fn query_extension_immediate_async<'a, 'b, Name: Into<Cow<'b, str>>>(
         &'a mut self,
         name: Name,
-    ) -> MapFuture<
-        ExchangeRequestFuture<'a, Self, QueryExtensionRequest<'b>>,
-        fn(crate::Result<QueryExtensionReply>) -> crate::Result<Extension>,
-    > {
-        MapFuture::run(
-            self.exchange_request_async(QueryExtensionRequest {
-                name: name.into(),
-                ..Default::default()
-            }),
-            |repl| repl.and_then(Extension::from_reply),
-        )
+    ) -> QueryExtensionFuture<'a, 'b, Self> {
+        QueryExtensionFuture::run(self, name.into())
     }
 
     /// Create a new window redox.
@@ -824,6 +1623,35 @@ pub trait AsyncDisplayXprotoExt: AsyncDisplay {
         }))
     }
 
+    /// Create a new simple window, making sure that any error caused by the request is returned from the
+    /// future, async redox. See `create_simple_window_checked` for more information.
+    #[inline]
+    fn create_simple_window_checked_async(
+        &mut self,
+        parent: Window,
+        x: i16,
+        y: i16,
+        width: u16,
+        height: u16,
+        border_width: u16,
+        border: u32,
+        background: u32,
+        validate_parent: bool,
+    ) -> CreateWindowCheckedFuture<'_, Self> {
+        let cw = create_simple_window_request(
+            Window::const_from_xid(0),
+            parent,
+            x,
+            y,
+            width,
+            height,
+            border_width,
+            border,
+            background,
+        );
+        CreateWindowCheckedFuture::run(self, cw, validate_parent)
+    }
+
     /// Create a new graphics context redox.
     #[inline]
     fn create_gc_async<Target: Into<Drawable>>(
@@ -844,6 +1672,39 @@ pub trait AsyncDisplayXprotoExt: AsyncDisplay {
         }))
     }
 
+    /// Create a new graphics context with a black foreground, async redox.
+    #[inline]
+    fn create_gc_default_async<Target: Into<Drawable>>(
+        &mut self,
+        target: Target,
+    ) -> ExchangeXidFuture<
+        '_,
+        Self,
+        CreateGcRequest,
+        Gcontext,
+        BoxedFnOnce<Gcontext, CreateGcRequest>,
+    > {
+        let props = default_gc_parameters(self.default_screen().black_pixel);
+        self.create_gc_async(target, props)
+    }
+
+    /// Create a new graphics context for the specified target, like `create_gc_default`, but with graphics
+    /// exposures turned off, async redox.
+    #[inline]
+    fn create_gc_no_exposures_async<Target: Into<Drawable>>(
+        &mut self,
+        target: Target,
+    ) -> ExchangeXidFuture<
+        '_,
+        Self,
+        CreateGcRequest,
+        Gcontext,
+        BoxedFnOnce<Gcontext, CreateGcRequest>,
+    > {
+        let props = no_exposures_gc_parameters(self.default_screen().black_pixel);
+        self.create_gc_async(target, props)
+    }
+
     /// Intern a string and get a corresponding atom for that string redox.
     #[inline]
     fn intern_atom_async<'a, 'b, Name: Into<Cow<'b, str>>>(
@@ -870,6 +1731,23 @@ pub trait AsyncDisplayXprotoExt: AsyncDisplay {
         )
     }
 
+    /// Get the atom for a string without creating it, async redox. See `intern_atom_if_exists` for more
+    /// information.
+    #[allow(clippy::type_complexity)]
+    #[inline]
+    fn intern_atom_if_exists_async<'a, 'b, Name: Into<Cow<'b, str>>>(
+        &'a mut self,
+        name: Name,
+    ) -> MapFuture<
+        ExchangeRequestFuture<'a, Self, InternAtomRequest<'b>>,
+        fn(crate::Result<InternAtomReply>) -> crate::Result<Option<Atom>>,
+    > {
+        MapFuture::run(
+            self.exchange_request_async(intern_atom_request(name.into(), true)),
+            |repl| repl.map(|repl| existing_atom(repl.atom)),
+        )
+    }
+
     /// Change the keyboard's control properties redox.
     #[inline]
     fn change_keyboard_control_async(
@@ -899,16 +1777,95 @@ pub trait AsyncDisplayXprotoExt: AsyncDisplay {
         })
     }
 
+    /// Get the host access list, async redox.
+    #[inline]
+    fn list_hosts_async(
+        &mut self,
+    ) -> MapFuture<
+        ExchangeRequestFuture<'_, Self, ListHostEntriesRequest>,
+        fn(crate::Result<HostList>) -> crate::Result<(bool, Vec<HostEntry>)>,
+    > {
+        MapFuture::run(
+            self.exchange_request_async(ListHostEntriesRequest::default()),
+            |repl| repl.map(|HostList { enabled, hosts }| (enabled, hosts)),
+        )
+    }
+
+    /// Add a host to or remove a host from the access list, async redox.
+    #[inline]
+    fn change_host_async<'a>(
+        &'a mut self,
+        mode: HostMode,
+        family: Family,
+        address: &'a [u8],
+    ) -> ExchangeRequestFuture<'a, Self, ChangeHostsRequest<'a>> {
+        self.exchange_request_async(change_host_request(mode, family, address))
+    }
+
     #[inline]
     fn change_active_pointer_grab_async(
         &mut self,
         event_mask: EventMask,
         cursor: Cursor,
-        time: Option<Timestamp>,
+        time: Time,
     ) -> ExchangeRequestFuture<'_, Self, ChangeActivePointerGrabRequest> {
         self.exchange_request_async(change_active_pointer_grab_request(event_mask, cursor, time))
     }
 
+    /// Release an active pointer grab held by this client, async redox.
+    #[inline]
+    fn ungrab_pointer_async(
+        &mut self,
+        time: Time,
+    ) -> ExchangeRequestFuture<'_, Self, UngrabPointerRequest> {
+        self.exchange_request_async(UngrabPointerRequest {
+            time: time.into(),
+            ..Default::default()
+        })
+    }
+
+    /// Release an active keyboard grab held by this client, async redox.
+    #[inline]
+    fn ungrab_keyboard_async(
+        &mut self,
+        time: Time,
+    ) -> ExchangeRequestFuture<'_, Self, UngrabKeyboardRequest> {
+        self.exchange_request_async(UngrabKeyboardRequest {
+            time: time.into(),
+            ..Default::default()
+        })
+    }
+
+    /// Release events queued by the server as the result of a frozen grab, async redox.
+    #[inline]
+    fn allow_events_async(
+        &mut self,
+        mode: Allow,
+        time: Time,
+    ) -> ExchangeRequestFuture<'_, Self, AllowEventsRequest> {
+        self.exchange_request_async(AllowEventsRequest {
+            mode,
+            time: time.into(),
+            ..Default::default()
+        })
+    }
+
+    /// Set up a click-to-focus grab on `window`, async redox. See `click_to_focus_grab` for more information.
+    #[inline]
+    fn click_to_focus_grab_async(
+        &mut self,
+        window: Window,
+    ) -> ExchangeRequestFuture<'_, Self, GrabButtonRequest> {
+        self.exchange_request_async(click_to_focus_grab_request(window))
+    }
+
+    /// Handle a `ButtonPress` caused by a grab set up by `click_to_focus_grab`, async redox. See
+    /// `handle_focus_click` for more information.
+    #[inline]
+    fn handle_focus_click_async(&mut self, event: &ButtonPressEvent) -> FocusClickFuture<'_, Self> {
+        FocusClickFuture::run(self, focus_click_requests(event))
+    }
+
     #[inline]
     fn set_close_down_mode_async(
         &mut self,
@@ -938,6 +1895,28 @@ pub trait AsyncDisplayXprotoExt: AsyncDisplay {
         ))
     }
 
+    /// Get the variables for the pointer, async redox.
+    #[inline]
+    fn get_pointer_control_async(
+        &mut self,
+    ) -> SendRequestFuture<'_, Self, GetPointerControlRequest> {
+        self.send_request_async(GetPointerControlRequest::default())
+    }
+
+    /// Get the variables for the pointer, resolving immediately, async redox.
+    #[inline]
+    fn get_pointer_control_immediate_async(
+        &mut self,
+    ) -> MapFuture<
+        ExchangeRequestFuture<'_, Self, GetPointerControlRequest>,
+        fn(crate::Result<GetPointerControlReply>) -> crate::Result<PointerControl>,
+    > {
+        MapFuture::run(
+            self.exchange_request_async(GetPointerControlRequest::default()),
+            |repl| repl.map(PointerControl::from),
+        )
+    }
+
     /// Create a new cursor redox.
     #[inline]
     fn create_cursor_async(
@@ -989,15 +1968,44 @@ pub trait AsyncDisplayXprotoExt: AsyncDisplay {
         })
     }
 
+    /// Give up ownership of a selection, async redox. See `disown_selection` for more information.
+    #[inline]
+    fn disown_selection_async(
+        &mut self,
+        selection: Atom,
+        time: Time,
+    ) -> ExchangeRequestFuture<'_, Self, SetSelectionOwnerRequest> {
+        self.exchange_request_async(SetSelectionOwnerRequest {
+            owner: Window::const_from_xid(0),
+            selection,
+            time: time.into(),
+            ..Default::default()
+        })
+    }
+
+    /// Reset the screensaver's idle timer, async redox. See `reset_screen_saver` for more information.
+    #[inline]
+    fn reset_screen_saver_async(
+        &mut self,
+    ) -> ExchangeRequestFuture<'_, Self, ForceScreenSaverRequest> {
+        self.force_screensaver_async(ScreenSaver::Reset)
+    }
+
     /// Send an event to the X server redox.
     #[inline]
-    fn send_event_async(
+    fn send_event_async<Dest: Into<SendEventDest>>(
         &mut self,
-        target: Window,
+        propagate: bool,
+        destination: Dest,
         mask: EventMask,
         event: Event,
     ) -> ExchangeRequestFuture<'_, Self, SendEventRequest> {
-        self.exchange_request_async(send_event_request(target, mask, event))
+        self.exchange_request_async(send_event_request(
+            propagate,
+            destination.into(),
+            mask,
+            event,
+        ))
     }
 
     /// Create a new colormap redox.
@@ -1073,6 +2081,57 @@ pub trait AsyncDisplayXprotoExt: AsyncDisplay {
         )
     }
 
+    /// Set the modifier mapping for this display, async redox.
+    #[inline]
+    fn set_modifier_mapping_async<'a>(
+        &'a mut self,
+        mapping: &'a ModifierMapping,
+    ) -> MapFuture<
+        ExchangeRequestFuture<'a, Self, SetModifierMappingRequest<'a>>,
+        fn(crate::Result<SetModifierMappingReply>) -> crate::Result<MappingStatus>,
+    > {
+        MapFuture::run(
+            self.exchange_request_async(set_modifier_mapping_request(mapping)),
+            |repl| repl.map(|repl| repl.status),
+        )
+    }
+
+    /// Get the pointer mapping for this display, async redox.
+    #[inline]
+    fn get_pointer_mapping_async(
+        &mut self,
+    ) -> SendRequestFuture<'_, Self, GetPointerMappingRequest> {
+        self.send_request_async(GetPointerMappingRequest::default())
+    }
+
+    /// Immediately get the pointer mapping for this display, async redox.
+    #[inline]
+    fn get_pointer_mapping_immediate_async(
+        &mut self,
+    ) -> MapFuture<
+        ExchangeRequestFuture<'_, Self, GetPointerMappingRequest>,
+        fn(crate::Result<GetPointerMappingReply>) -> crate::Result<PointerMapping>,
+    > {
+        MapFuture::run(
+            self.exchange_request_async(GetPointerMappingRequest::default()),
+            |repl| repl.map(PointerMapping::from),
+        )
+    }
+
+    /// Get the number of physical buttons on the pointer, async redox.
+    #[inline]
+    fn pointer_button_count_async(
+        &mut self,
+    ) -> MapFuture<
+        ExchangeRequestFuture<'_, Self, GetPointerMappingRequest>,
+        fn(crate::Result<GetPointerMappingReply>) -> crate::Result<usize>,
+    > {
+        MapFuture::run(
+            self.exchange_request_async(GetPointerMappingRequest::default()),
+            |repl| repl.map(|repl| repl.map.len()),
+        )
+    }
+
     /// Grab the server, async redox.
     #[inline]
     fn grab_server_async(&mut self) -> ExchangeRequestFuture<'_, Self, GrabServerRequest> {
@@ -1111,7 +2170,118 @@ pub trait AsyncDisplayXprotoExt: AsyncDisplay {
             |ganr| ganr.name.into_owned(),
         )
     }
+
+    /// Get the list of EWMH hints supported by the window manager, async redox. See `net_supported` for more
+    /// information.
+    #[inline]
+    fn net_supported_async(&mut self) -> NetSupportedFuture<'_, Self> {
+        NetSupportedFuture::run(self)
+    }
+
+    /// Tell whether or not the window manager supports an EWMH hint, async redox. See `wm_supports` for more
+    /// information.
+    #[allow(clippy::type_complexity)]
+    #[inline]
+    fn wm_supports_async(
+        &mut self,
+        hint: Atom,
+    ) -> MapFuture<
+        NetSupportedFuture<'_, Self>,
+        BoxedFnOnce<crate::Result<Vec<Atom>>, crate::Result<bool>>,
+    > {
+        MapFuture::run(
+            self.net_supported_async(),
+            Box::new(move |supported| supported.map(|supported| supported.contains(&hint))),
+        )
+    }
 }
 
 #[cfg(feature = "async")]
 impl<D: AsyncDisplay + ?Sized> AsyncDisplayXprotoExt for D {}
+
+#[test]
+fn pointer_mapping_out_of_range() {
+    let pm = PointerMapping {
+        map: alloc::vec![3, 2, 1, 0].into_boxed_slice(),
+    };
+
+    assert_eq!(pm.button_count(), 4);
+    assert_eq!(pm.logical_button(1), 3);
+    assert_eq!(pm.logical_button(4), 0);
+    assert_eq!(pm.logical_button(0), 0);
+    assert_eq!(pm.logical_button(5), 5);
+    assert_eq!(pm.logical_button(255), 255);
+}
+
+#[test]
+fn modifier_mapping_diff() {
+    use alloc::vec;
+
+    let old = ModifierMapping {
+        keycodes_per_modifier: 2,
+        keycodes: vec![50, 62, 66, 0, 37, 105, 64, 108, 0, 0, 0, 0, 133, 134, 0, 0]
+            .into_boxed_slice(),
+    };
+    let reordered = ModifierMapping {
+        keycodes_per_modifier: 3,
+        keycodes: vec![
+            62, 50, 0, 66, 0, 0, 105, 37, 0, 64, 108, 0, 0, 0, 0, 0, 0, 0, 133, 134, 0, 0, 0, 0,
+        ]
+        .into_boxed_slice(),
+    };
+    assert!(old.diff(&reordered).is_empty());
+
+    let mut moved_alt = old.clone();
+    moved_alt.keycodes[7] = 0;
+    moved_alt.keycodes[13] = 0;
+    let diff = old.diff(&moved_alt);
+    assert!(diff.changed.One());
+    assert!(diff.changed.Four());
+    assert!(!diff.changed.shift());
+    assert_eq!(diff.changed.count_ones(), 2);
+}
+
+#[test]
+fn gc_without_exposures_sets_the_flag() {
+    let gcr = create_gc_request(
+        Gcontext::const_from_xid(1),
+        Drawable::const_from_xid(2),
+        no_exposures_gc_parameters(7),
+    );
+    assert!(gcr.value_mask.graphics_exposures());
+    assert_eq!(gcr.graphics_exposures, 0);
+    // the rest of the parameters match create_gc_default
+    assert!(gcr.value_mask.foreground());
+    assert_eq!(gcr.foreground, 7);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn find_window_skips_windows_destroyed_under_the_predicate() {
+    use crate::{
+        display::{connection::dummy::PreprogrammedConnection, BasicDisplay},
+        AuthInfo, BreadError, ErrorCode,
+    };
+    use core::iter;
+
+    let conn = PreprogrammedConnection::normal_setup(iter::empty());
+    let mut dpy = BasicDisplay::from_connection(conn, 0, AuthInfo::default()).unwrap();
+    let root = dpy.default_root();
+
+    // the root is skipped along with its subtree, so no QueryTree request is sent for it
+    let found = dpy
+        .find_window(root, |_, _| {
+            Err(BreadError::XProtocol {
+                error_code: ErrorCode(3),
+                minor_code: 0,
+                major_code: 20,
+                sequence: 0,
+            })
+        })
+        .unwrap();
+    assert_eq!(found, None);
+
+    // other errors still stop the search
+    let res = dpy.find_window(root, |_, _| Err(BreadError::ClosedConnection));
+    assert!(matches!(res, Err(BreadError::ClosedConnection)));
+}
@@ -0,0 +1,70 @@
+// MIT/Apache2 License
+
+use crate::{
+    auto::xproto::{
+        CloseFontRequest, Colormap, Cursor, DestroyWindowRequest, Font, FreeColormapRequest,
+        FreeCursorRequest, FreeGcRequest, FreePixmapRequest, Gcontext, Pixmap, Window,
+    },
+    display::{prelude::*, Display},
+    xid::XidType,
+    Request,
+};
+
+#[cfg(feature = "async")]
+use crate::display::{futures::ExchangeRequestFuture, AsyncDisplay};
+
+/// An object that is allocated on the server and has to be released by the client once it is no longer
+/// needed, such as a pixmap or a graphics context.
+///
+/// This allows generic code to free any kind of resource. Every implementor also has an inherent `free`
+/// method that does the same thing.
+pub trait Resource: XidType + Copy {
+    /// The request that releases this resource.
+    type FreeRequest: Request<Reply = ()>;
+
+    /// Create the request that releases this resource.
+    fn free_request(self) -> Self::FreeRequest;
+
+    /// Release this resource. Any later request that uses it will fail.
+    #[inline]
+    fn free<Dpy: Display + ?Sized>(self, dpy: &mut Dpy) -> crate::Result {
+        dpy.exchange_request(self.free_request())
+    }
+
+    /// Release this resource, async redox.
+    #[cfg(feature = "async")]
+    #[inline]
+    fn free_async<Dpy: AsyncDisplay + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+    ) -> ExchangeRequestFuture<'_, Dpy, Self::FreeRequest> {
+        dpy.exchange_request_async(self.free_request())
+    }
+}
+
+macro_rules! resource_impl {
+    ($($ty: ty => $req: ident { $field: ident }),*) => {
+        $(
+            impl Resource for $ty {
+                type FreeRequest = $req;
+
+                #[inline]
+                fn free_request(self) -> $req {
+                    $req {
+                        $field: self,
+                        ..Default::default()
+                    }
+                }
+            }
+        )*
+    };
+}
+
+resource_impl! {
+    Pixmap => FreePixmapRequest { pixmap },
+    Gcontext => FreeGcRequest { gc },
+    Cursor => FreeCursorRequest { cursor },
+    Colormap => FreeColormapRequest { cmap },
+    Font => CloseFontRequest { font },
+    Window => DestroyWindowRequest { window }
+}
@@ -0,0 +1,129 @@
+// MIT/Apache2 License
+
+use crate::auto::xproto::{Colormap, Depth, Screen, VisualClass, Visualid, Visualtype, Window};
+
+impl Screen<'_, '_> {
+    /// Get the root window of this screen.
+    #[inline]
+    #[must_use]
+    pub fn root(&self) -> Window {
+        self.root
+    }
+
+    /// Get the pixel used for the white color on this screen.
+    #[inline]
+    #[must_use]
+    pub fn white_pixel(&self) -> u32 {
+        self.white_pixel
+    }
+
+    /// Get the pixel used for the black color on this screen.
+    #[inline]
+    #[must_use]
+    pub fn black_pixel(&self) -> u32 {
+        self.black_pixel
+    }
+
+    /// Get the colormap that this screen uses by default.
+    #[inline]
+    #[must_use]
+    pub fn default_colormap(&self) -> Colormap {
+        self.default_colormap
+    }
+
+    /// Get the ID of the visual used by the root window of this screen.
+    #[inline]
+    #[must_use]
+    pub fn root_visual_id(&self) -> Visualid {
+        self.root_visual
+    }
+
+    /// Get the visual used by the root window of this screen. This only returns `None` if the server sent a
+    /// malformed setup.
+    #[inline]
+    #[must_use]
+    pub fn root_visual(&self) -> Option<&Visualtype> {
+        self.allowed_depths
+            .iter()
+            .flat_map(|d| d.visuals.iter())
+            .find(|v| v.visual_id == self.root_visual)
+    }
+
+    /// Iterate over the depths that this screen supports.
+    #[inline]
+    pub fn depths(&self) -> impl Iterator<Item = &Depth<'_>> + '_ {
+        self.allowed_depths.iter()
+    }
+
+    /// Iterate over every visual this screen supports, alongside the depth it is used with.
+    #[inline]
+    pub fn visuals(&self) -> impl Iterator<Item = (u8, &Visualtype)> + '_ {
+        self.depths()
+            .flat_map(|d| d.visuals().iter().map(move |v| (d.depth, v)))
+    }
+
+    /// Iterate over the visuals of the given class that are used with the given depth. For instance,
+    /// `visuals_matching(VisualClass::TrueColor, 32)` finds the visuals that can be used to create windows
+    /// with an alpha channel.
+    #[inline]
+    pub fn visuals_matching(
+        &self,
+        class: VisualClass,
+        depth: u8,
+    ) -> impl Iterator<Item = &Visualtype> + '_ {
+        self.depths()
+            .filter(move |d| d.depth == depth)
+            .flat_map(|d| d.visuals().iter())
+            .filter(move |v| v.class == class)
+    }
+}
+
+impl Depth<'_> {
+    /// Get the visuals that can be used with this depth.
+    #[inline]
+    #[must_use]
+    pub fn visuals(&self) -> &[Visualtype] {
+        &self.visuals
+    }
+}
+
+#[test]
+fn visuals_matching_filters_by_class_and_depth() {
+    use alloc::borrow::Cow;
+
+    fn visual(visual_id: Visualid, class: VisualClass) -> Visualtype {
+        Visualtype {
+            visual_id,
+            class,
+            ..Default::default()
+        }
+    }
+
+    let screen = Screen {
+        allowed_depths: Cow::Owned(alloc::vec![
+            Depth {
+                depth: 24,
+                visuals: Cow::Owned(alloc::vec![
+                    visual(1, VisualClass::TrueColor),
+                    visual(2, VisualClass::DirectColor),
+                ]),
+            },
+            Depth {
+                depth: 32,
+                visuals: Cow::Owned(alloc::vec![visual(3, VisualClass::TrueColor)]),
+            },
+        ]),
+        ..Default::default()
+    };
+
+    let ids = |depth| {
+        screen
+            .visuals_matching(VisualClass::TrueColor, depth)
+            .map(|v| v.visual_id)
+            .collect::<alloc::vec::Vec<_>>()
+    };
+    assert_eq!(ids(24), [1]);
+    assert_eq!(ids(32), [3]);
+    assert!(ids(8).is_empty());
+    assert_eq!(screen.visuals().count(), 3);
+}
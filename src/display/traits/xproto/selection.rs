@@ -0,0 +1,131 @@
+// MIT/Apache2 License
+
+use crate::{
+    auto::xproto::{Atom, GetSelectionOwnerRequest, Window},
+    display::{prelude::*, Display},
+    xid::XidType,
+    Time,
+};
+use core::ops::{Deref, DerefMut};
+
+/// Proof that a window owns a selection. When this is dropped, the window gives up ownership of the
+/// selection, unless another client has taken it over in the meantime.
+///
+/// This borrows the display, and dereferences to it so that the display can still be used while the
+/// selection is owned (e.g. to answer `SelectionRequest` events).
+#[derive(Debug)]
+pub struct SelectionOwnership<'a, Dpy: Display + ?Sized> {
+    dpy: &'a mut Dpy,
+    owner: Window,
+    selection: Atom,
+    time: Time,
+}
+
+impl<Dpy: Display + ?Sized> SelectionOwnership<'_, Dpy> {
+    /// The window that owns the selection.
+    #[inline]
+    #[must_use]
+    pub fn owner(&self) -> Window {
+        self.owner
+    }
+
+    /// The selection that is owned.
+    #[inline]
+    #[must_use]
+    pub fn selection(&self) -> Atom {
+        self.selection
+    }
+
+    /// Give up ownership of the selection now, returning any error that occurs. Otherwise, this is done
+    /// when the `SelectionOwnership` is dropped, and errors are only logged.
+    #[inline]
+    pub fn disown(mut self) -> crate::Result {
+        self.disown_internal()
+    }
+
+    #[inline]
+    fn disown_internal(&mut self) -> crate::Result {
+        // if someone else took the selection since, we shouldn't clobber it
+        let current = self.dpy.exchange_request(GetSelectionOwnerRequest {
+            selection: self.selection,
+            ..Default::default()
+        })?;
+
+        if current.owner == self.owner {
+            self.dpy.disown_selection(self.selection, self.time)?;
+        }
+
+        // make sure we don't try again when dropped
+        self.owner = Window::const_from_xid(0);
+        Ok(())
+    }
+}
+
+impl<Dpy: Display + ?Sized> Deref for SelectionOwnership<'_, Dpy> {
+    type Target = Dpy;
+
+    #[inline]
+    fn deref(&self) -> &Dpy {
+        self.dpy
+    }
+}
+
+impl<Dpy: Display + ?Sized> DerefMut for SelectionOwnership<'_, Dpy> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Dpy {
+        self.dpy
+    }
+}
+
+impl<Dpy: Display + ?Sized> Drop for SelectionOwnership<'_, Dpy> {
+    #[inline]
+    fn drop(&mut self) {
+        if self.owner.xid() == 0 {
+            return;
+        }
+
+        if let Err(e) = self.disown_internal() {
+            log::error!("Failed to disown selection {:?}: {}", self.selection, e);
+        }
+    }
+}
+
+impl Window {
+    /// Take ownership of a selection (e.g. `CLIPBOARD`), and return a handle that gives it up once it is
+    /// dropped.
+    ///
+    /// As the ICCCM requires, this checks that the window actually became the owner afterwards, since the
+    /// request is silently ignored if `time` is older than the selection's last change. `time` should be the
+    /// timestamp of the event that caused the program to take the selection, rather than `Time::Current`.
+    ///
+    /// # Errors
+    ///
+    /// This returns `BreadError::StaticMsg` if the window did not become the selection's owner, in addition to
+    /// any errors that occur while sending the requests.
+    #[inline]
+    pub fn own_selection<Dpy: Display + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+        selection: Atom,
+        time: Time,
+    ) -> crate::Result<SelectionOwnership<'_, Dpy>> {
+        self.set_selection_owner(dpy, selection, time)?;
+
+        let current = dpy.exchange_request(GetSelectionOwnerRequest {
+            selection,
+            ..Default::default()
+        })?;
+        if current.owner != self {
+            return Err(crate::BreadError::StaticMsg(
+                "Failed to take ownership of the selection",
+            ));
+        }
+
+        Ok(SelectionOwnership {
+            dpy,
+            owner: self,
+            selection,
+            time,
+        })
+    }
+}
@@ -0,0 +1,386 @@
+// MIT/Apache2 License
+
+//! This module contains the `WM_NORMAL_HINTS` property, which clients use to tell window managers which sizes
+//! their windows can have, as well as the logic for fitting a size to those hints.
+
+use super::{window::check_property_type, PropertyFormat};
+use crate::{
+    auto::{
+        xproto::{
+            GetPropertyReply, GetPropertyRequest, Gravity, Window, ATOM_WM_NORMAL_HINTS,
+            ATOM_WM_SIZE_HINTS,
+        },
+        AsByteSequence,
+    },
+    display::{prelude::*, Display},
+};
+use alloc::vec::Vec;
+use core::{cmp, convert::TryFrom};
+
+#[cfg(feature = "async")]
+use crate::display::AsyncDisplay;
+
+/// The number of 32-bit values in a `WM_SIZE_HINTS` property.
+const SIZE_HINTS_LEN: usize = 18;
+/// Clients written against older versions of the ICCCM leave out the base size and window gravity.
+const OLD_SIZE_HINTS_LEN: usize = 15;
+
+const P_MIN_SIZE: u32 = 1 << 4;
+const P_MAX_SIZE: u32 = 1 << 5;
+const P_RESIZE_INC: u32 = 1 << 6;
+const P_ASPECT: u32 = 1 << 7;
+const P_BASE_SIZE: u32 = 1 << 8;
+const P_WIN_GRAVITY: u32 = 1 << 9;
+
+/// The size constraints a client places on its window, stored in the `WM_NORMAL_HINTS` property. See section
+/// 4.1.2.3 of the ICCCM for more information.
+///
+/// Every field is optional; a field that is `None` does not constrain the window's size. Sizes are given
+/// as `(width, height)` pairs, and aspect ratios as `(numerator, denominator)` pairs, where the numerator is
+/// the width.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct WmSizeHints {
+    /// The smallest size the window can have.
+    pub min_size: Option<(u32, u32)>,
+    /// The largest size the window can have.
+    pub max_size: Option<(u32, u32)>,
+    /// The window is only resized in steps of this many pixels, starting from the base size. For example,
+    /// terminals use the size of a character cell here.
+    pub resize_increments: Option<(u32, u32)>,
+    /// The smallest and largest aspect ratio the window can have. These are always set together.
+    pub aspect: Option<((u32, u32), (u32, u32))>,
+    /// The size the resize increments are counted from.
+    pub base_size: Option<(u32, u32)>,
+    /// The point of the window that should stay in place when the window manager adds its frame.
+    pub win_gravity: Option<Gravity>,
+}
+
+impl WmSizeHints {
+    /// Parse the hints from the 32-bit values of a `WM_NORMAL_HINTS` property. Returns `None` if there are
+    /// too few values to be a size hints property.
+    #[must_use]
+    pub fn from_property_data(data: &[u32]) -> Option<Self> {
+        if data.len() < OLD_SIZE_HINTS_LEN {
+            return None;
+        }
+
+        let flags = data[0];
+        let pair = |flag: u32, index: usize| {
+            if flags & flag != 0 && index + 1 < data.len() {
+                Some((data[index], data[index + 1]))
+            } else {
+                None
+            }
+        };
+
+        // values 1 through 4 hold the obsolete position and size fields
+        Some(Self {
+            min_size: pair(P_MIN_SIZE, 5),
+            max_size: pair(P_MAX_SIZE, 7),
+            resize_increments: pair(P_RESIZE_INC, 9),
+            aspect: match (pair(P_ASPECT, 11), pair(P_ASPECT, 13)) {
+                (Some(min), Some(max)) => Some((min, max)),
+                _ => None,
+            },
+            base_size: pair(P_BASE_SIZE, 15),
+            win_gravity: data
+                .get(17)
+                .filter(|_| flags & P_WIN_GRAVITY != 0)
+                .and_then(|&gravity| u8::try_from(gravity).ok())
+                .and_then(|gravity| Gravity::from_bytes(&[gravity]))
+                .map(|(gravity, _)| gravity),
+        })
+    }
+
+    /// Convert the hints into the 32-bit values of a `WM_NORMAL_HINTS` property.
+    #[must_use]
+    pub fn to_property_data(&self) -> [u32; SIZE_HINTS_LEN] {
+        let mut data = [0; SIZE_HINTS_LEN];
+        let mut set_pair = |flag: u32, index: usize, pair: Option<(u32, u32)>| {
+            if let Some((first, second)) = pair {
+                data[0] |= flag;
+                data[index] = first;
+                data[index + 1] = second;
+            }
+        };
+
+        set_pair(P_MIN_SIZE, 5, self.min_size);
+        set_pair(P_MAX_SIZE, 7, self.max_size);
+        set_pair(P_RESIZE_INC, 9, self.resize_increments);
+        set_pair(P_ASPECT, 11, self.aspect.map(|(min, _)| min));
+        set_pair(P_ASPECT, 13, self.aspect.map(|(_, max)| max));
+        set_pair(P_BASE_SIZE, 15, self.base_size);
+        if let Some(gravity) = self.win_gravity {
+            data[0] |= P_WIN_GRAVITY;
+            data[17] = gravity as u32;
+        }
+
+        data
+    }
+
+    /// Find the valid size closest to `(width, height)`, following the algorithm the ICCCM describes for
+    /// window managers.
+    ///
+    /// The size is first clamped between the minimum and maximum sizes. Then it is rounded down to the
+    /// nearest resize increment counted from the base size, staying at or above the minimum size. Finally,
+    /// if the aspect ratio is out of range, the height (or, if that would go below the minimum, the width)
+    /// is adjusted by whole increments until it is in range. As the ICCCM specifies, the base size is
+    /// subtracted before checking the aspect ratio.
+    ///
+    /// If the minimum or base size is missing, the other one is used in its place, and the result is never
+    /// smaller than 1x1.
+    #[must_use]
+    pub fn constrain(&self, width: u32, height: u32) -> (u32, u32) {
+        let (base_w, base_h) = self.base_size.or(self.min_size).unwrap_or((0, 0));
+        let (min_w, min_h) = self.min_size.or(self.base_size).unwrap_or((1, 1));
+        let (min_w, min_h) = (cmp::max(min_w, 1), cmp::max(min_h, 1));
+        let (max_w, max_h) = self.max_size.unwrap_or((u32::MAX, u32::MAX));
+        let (max_w, max_h) = (cmp::max(max_w, min_w), cmp::max(max_h, min_h));
+        let (inc_w, inc_h) = self.resize_increments.unwrap_or((1, 1));
+        let (inc_w, inc_h) = (cmp::max(inc_w, 1), cmp::max(inc_h, 1));
+
+        let width = snap_to_increment(clamp(width, min_w, max_w), base_w, inc_w, min_w, max_w);
+        let height = snap_to_increment(clamp(height, min_h, max_h), base_h, inc_h, min_h, max_h);
+
+        let ((min_x, min_y), (max_x, max_y)) = match self.aspect {
+            Some(aspect) => aspect,
+            None => return (width, height),
+        };
+
+        // only the base size is subtracted here, the minimum size is not
+        let (aspect_base_w, aspect_base_h) = self.base_size.unwrap_or((0, 0));
+        let (mut width, mut height) = (u64::from(width), u64::from(height));
+        let (base_w, base_h) = (u64::from(aspect_base_w), u64::from(aspect_base_h));
+        let content = |size: u64, base: u64| size.saturating_sub(base);
+
+        // too narrow: width / height < min_x / min_y
+        if min_y != 0
+            && content(width, base_w) * u64::from(min_y)
+                < u64::from(min_x) * content(height, base_h)
+        {
+            let target_h = content(width, base_w) * u64::from(min_y) / u64::from(min_x);
+            let delta = round_up_to(content(height, base_h) - target_h, u64::from(inc_h));
+            if height >= u64::from(min_h) + delta {
+                height -= delta;
+            } else {
+                let target_w =
+                    div_round_up(content(height, base_h) * u64::from(min_x), u64::from(min_y));
+                let delta = round_up_to(target_w - content(width, base_w), u64::from(inc_w));
+                if width + delta <= u64::from(max_w) {
+                    width += delta;
+                }
+            }
+        }
+
+        // too wide: width / height > max_x / max_y
+        if max_y != 0
+            && content(width, base_w) * u64::from(max_y)
+                > u64::from(max_x) * content(height, base_h)
+        {
+            let target_w = content(height, base_h) * u64::from(max_x) / u64::from(max_y);
+            let delta = round_up_to(content(width, base_w) - target_w, u64::from(inc_w));
+            if width >= u64::from(min_w) + delta {
+                width -= delta;
+            } else if max_x != 0 {
+                let target_h =
+                    div_round_up(content(width, base_w) * u64::from(max_y), u64::from(max_x));
+                let delta = round_up_to(target_h - content(height, base_h), u64::from(inc_h));
+                if height + delta <= u64::from(max_h) {
+                    height += delta;
+                }
+            }
+        }
+
+        (width as u32, height as u32)
+    }
+}
+
+#[inline]
+fn clamp(value: u32, min: u32, max: u32) -> u32 {
+    cmp::min(cmp::max(value, min), max)
+}
+
+/// Round `size` down to `base` plus a multiple of `inc`, rounding up instead if that would go below `min`.
+#[inline]
+fn snap_to_increment(size: u32, base: u32, inc: u32, min: u32, max: u32) -> u32 {
+    if size < base {
+        return size;
+    }
+
+    let snapped = base + (size - base) / inc * inc;
+    if snapped >= min {
+        snapped
+    } else {
+        let up = u64::from(base) + round_up_to(u64::from(min - base), u64::from(inc));
+        if up <= u64::from(max) {
+            up as u32
+        } else {
+            snapped
+        }
+    }
+}
+
+#[inline]
+fn div_round_up(value: u64, divisor: u64) -> u64 {
+    (value + divisor - 1) / divisor
+}
+
+#[inline]
+fn round_up_to(value: u64, multiple: u64) -> u64 {
+    div_round_up(value, multiple) * multiple
+}
+
+impl Window {
+    #[inline]
+    fn wm_normal_hints_request(self) -> GetPropertyRequest {
+        GetPropertyRequest {
+            window: self,
+            property: ATOM_WM_NORMAL_HINTS,
+            ty: ATOM_WM_SIZE_HINTS,
+            long_offset: 0,
+            long_length: SIZE_HINTS_LEN as u32,
+            delete: false,
+            ..Default::default()
+        }
+    }
+
+    /// Get the `WM_NORMAL_HINTS` property of this window. Returns `None` if the property is not set or is too
+    /// short, and `BreadError::BadObjectRead` if it is not of type `WM_SIZE_HINTS`.
+    #[inline]
+    pub fn wm_normal_hints_immediate<Dpy: Display + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+    ) -> crate::Result<Option<WmSizeHints>> {
+        let gpr = dpy.exchange_request(self.wm_normal_hints_request())?;
+        parse_size_hints(&gpr)
+    }
+
+    /// Get the `WM_NORMAL_HINTS` property of this window, async redox.
+    #[cfg(feature = "async")]
+    #[inline]
+    pub async fn wm_normal_hints_immediate_async<Dpy: AsyncDisplay + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+    ) -> crate::Result<Option<WmSizeHints>> {
+        let gpr = dpy
+            .exchange_request_async(self.wm_normal_hints_request())
+            .await?;
+        parse_size_hints(&gpr)
+    }
+
+    /// Resize this window to the valid size closest to the requested one, according to `hints`. Returns the
+    /// size that was actually used. See `WmSizeHints::constrain` for how the size is chosen.
+    ///
+    /// This only changes the window's size, so its top-left corner stays in place. Window managers that
+    /// want to honor `win_gravity` should move the window as well, using `move_resize`.
+    #[inline]
+    pub fn resize_respecting_hints<Dpy: Display + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+        width: u16,
+        height: u16,
+        hints: &WmSizeHints,
+    ) -> crate::Result<(u16, u16)> {
+        let (width, height) = constrain_to_u16(hints, width, height);
+        self.resize(dpy, width.into(), height.into())?;
+        Ok((width, height))
+    }
+
+    /// Resize this window to the valid size closest to the requested one, async redox.
+    #[cfg(feature = "async")]
+    #[inline]
+    pub async fn resize_respecting_hints_async<Dpy: AsyncDisplay + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+        width: u16,
+        height: u16,
+        hints: &WmSizeHints,
+    ) -> crate::Result<(u16, u16)> {
+        let (width, height) = constrain_to_u16(hints, width, height);
+        self.resize_async(dpy, width.into(), height.into()).await?;
+        Ok((width, height))
+    }
+}
+
+/// Window sizes are 16-bit on the wire, so anything larger than that is cut down to the largest size.
+#[inline]
+fn constrain_to_u16(hints: &WmSizeHints, width: u16, height: u16) -> (u16, u16) {
+    let (width, height) = hints.constrain(width.into(), height.into());
+    (
+        u16::try_from(width).unwrap_or(u16::MAX),
+        u16::try_from(height).unwrap_or(u16::MAX),
+    )
+}
+
+/// Parse a `WM_NORMAL_HINTS` property, returning `None` if it is missing or too short.
+#[inline]
+fn parse_size_hints(gpr: &GetPropertyReply<'_>) -> crate::Result<Option<WmSizeHints>> {
+    if !check_property_type(gpr, ATOM_WM_SIZE_HINTS, Some(PropertyFormat::ThirtyTwo))? {
+        return Ok(None);
+    }
+
+    let data: Vec<u32> = gpr
+        .value
+        .chunks_exact(4)
+        .map(|chunk| u32::from_ne_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect();
+    Ok(WmSizeHints::from_property_data(&data))
+}
+
+#[test]
+fn size_hints_round_trip() {
+    let hints = WmSizeHints {
+        min_size: Some((10, 20)),
+        resize_increments: Some((7, 13)),
+        aspect: Some(((1, 2), (2, 1))),
+        win_gravity: Some(Gravity::SouthEast),
+        ..Default::default()
+    };
+    let data = hints.to_property_data();
+    assert_eq!(
+        data[0],
+        P_MIN_SIZE | P_RESIZE_INC | P_ASPECT | P_WIN_GRAVITY
+    );
+    assert_eq!(WmSizeHints::from_property_data(&data), Some(hints));
+    assert_eq!(
+        WmSizeHints::from_property_data(&data[..OLD_SIZE_HINTS_LEN]),
+        Some(WmSizeHints {
+            win_gravity: None,
+            ..hints
+        })
+    );
+}
+
+#[test]
+fn constrain_applies_increments_from_base_size() {
+    // a terminal with 2 pixels of padding and 8x16 character cells
+    let hints = WmSizeHints {
+        min_size: Some((10, 18)),
+        max_size: Some((500, 500)),
+        base_size: Some((2, 2)),
+        resize_increments: Some((8, 16)),
+        ..Default::default()
+    };
+    assert_eq!(hints.constrain(100, 100), (98, 98));
+    assert_eq!(hints.constrain(1, 1), (10, 18));
+    assert_eq!(hints.constrain(1000, 1000), (498, 498));
+}
+
+#[test]
+fn constrain_keeps_aspect_ratio_in_range() {
+    let square = WmSizeHints {
+        aspect: Some(((1, 1), (1, 1))),
+        ..Default::default()
+    };
+    assert_eq!(square.constrain(200, 100), (100, 100));
+    assert_eq!(square.constrain(100, 200), (100, 100));
+
+    // if shrinking the height would go below the minimum, the width grows instead
+    let wide = WmSizeHints {
+        min_size: Some((1, 100)),
+        aspect: Some(((2, 1), (4, 1))),
+        ..Default::default()
+    };
+    assert_eq!(wide.constrain(100, 100), (200, 100));
+    assert_eq!(wide.constrain(300, 100), (300, 100));
+}
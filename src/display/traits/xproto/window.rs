@@ -3,26 +3,35 @@
 #![allow(clippy::similar_names)]
 
 use super::Geometry as DrawableGeometry;
+use crate::{
+    auto::xproto::{
+        ButtonIndex, GrabButtonRequest, ModMask, UngrabButtonRequest, ATOM_ANY, ATOM_ATOM,
+        ATOM_NONE, ATOM_STRING,
+    },
+    Time,
+};
 pub use crate::{
     auto::{
         xproto::{
             Atom, BackingStore, ChangePropertyRequest, ChangeSaveSetRequest,
             ChangeWindowAttributesRequest, Circulate, CirculateWindowRequest, ClearAreaRequest,
-            Colormap, ConfigWindow, ConfigureWindowRequest, ConvertSelectionRequest, Cursor,
-            DeletePropertyRequest, DestroySubwindowsRequest, DestroyWindowRequest, EventMask,
-            Gcontext, GetGeometryRequest, GetPropertyRequest, GetWindowAttributesReply,
-            GetWindowAttributesRequest, Gravity, MapState, MapSubwindowsRequest, MapWindowRequest,
-            PropMode, QueryTreeReply, QueryTreeRequest, ReparentWindowRequest, SetMode, StackMode,
-            Timestamp, UnmapSubwindowsRequest, UnmapWindowRequest, Visualid, Window, WindowClass,
-            ATOM_WM_NAME,
+            Colormap, ConfigWindow, ConfigureRequestEvent, ConfigureWindowRequest,
+            ConvertSelectionRequest, Cursor, DeletePropertyRequest, DestroySubwindowsRequest,
+            DestroyWindowRequest, EventMask, Gcontext, GetGeometryRequest, GetPropertyReply,
+            GetPropertyRequest, GetWindowAttributesReply, GetWindowAttributesRequest,
+            GrabKeyboardRequest, GrabMode, GrabPointerRequest, GrabStatus, Gravity, InputFocus,
+            MapState, MapSubwindowsRequest, MapWindowRequest, PropMode, QueryTreeReply,
+            QueryTreeRequest, ReparentWindowRequest, SetInputFocusRequest, SetMode,
+            SetSelectionOwnerRequest, StackMode, Timestamp, UnmapSubwindowsRequest,
+            UnmapWindowRequest, Visualid, Window, WindowClass, ATOM_WM_NAME,
         },
         AsByteSequence,
     },
     display::{prelude::*, Connection, Display, DisplayExt, RequestCookie, WindowParameters},
     xid::XidType,
 };
-use alloc::{borrow::Cow, boxed::Box, string::ToString, vec::Vec};
-use core::{iter, mem};
+use alloc::{borrow::Cow, boxed::Box, string::String, vec::Vec};
+use core::{iter, mem, num::NonZeroU32};
 
 #[cfg(feature = "async")]
 use crate::display::{futures::SendRequestFuture, traits::AsyncDisplayDrawableExt, AsyncDisplay};
@@ -33,7 +42,7 @@ macro_rules! retrieve_atom {
         match $dpy.$dgetter() {
             Some(wpa) => Atom::const_from_xid(wpa.get()),
             None => {
-                let wpa = $dpy.intern_atom_immediate(($name).to_string(), false)?;
+                let wpa = $dpy.intern_atom_immediate($name, false)?;
                 if wpa.xid() == 0 {
                     log::error!("Unable to intern {} atom", $name);
                     return Ok(());
@@ -53,20 +62,74 @@ macro_rules! retrieve_atom_async {
             Some(wpa) => Atom::const_from_xid(wpa.get()),
             None => {
                 let wpa = $dpy
-                    .intern_atom_immediate_async(($name).to_string(), false)
+                    .intern_atom_immediate_async($name, false)
                     .await?;
                 if wpa.xid() == 0 {
                     log::error!("Unable to intern {} atom", $name);
                     return Ok(());
-                } else {
-                    $dpy.$dsetter(core::num::NonZeroU32::new(wpa.xid()).unwrap());
-                    wpa
                 }
+
+                $dpy.$dsetter(core::num::NonZeroU32::new(wpa.xid()).unwrap());
+                wpa
             }
         }
     }};
 }
 
+/// The combinations of lock modifiers that a passive grab has to be registered for, so that it still triggers
+/// while Caps Lock (`Lock`) or Num Lock (usually `Mod2`) is active.
+const LOCK_COMBINATIONS: [ModMask; 4] = [
+    ModMask { inner: 0 },
+    ModMask::LOCK,
+    ModMask::TWO,
+    ModMask {
+        inner: ModMask::LOCK.inner | ModMask::TWO.inner,
+    },
+];
+
+/// Expand a set of modifiers into every combination of it with the lock modifiers. `AnyModifier` already
+/// matches every combination, so it is left alone.
+#[inline]
+fn with_lock_combinations(modifiers: ModMask) -> impl Iterator<Item = ModMask> {
+    let combinations: &'static [ModMask] = if modifiers == ModMask::ANY {
+        &LOCK_COMBINATIONS[..1]
+    } else {
+        &LOCK_COMBINATIONS
+    };
+    combinations.iter().map(move |&locks| modifiers | locks)
+}
+
+/// Add `_NET_WM_PING` to a list of WM protocols if pings are being answered automatically, so that the
+/// window manager knows to send them.
+#[inline]
+fn with_net_wm_ping(net_wm_ping: Option<NonZeroU32>, protocols: &[Atom]) -> Cow<'_, [Atom]> {
+    match net_wm_ping {
+        Some(nwp) if !protocols.iter().any(|p| p.xid() == nwp.get()) => {
+            let mut protocols = protocols.to_vec();
+            protocols.push(Atom::const_from_xid(nwp.get()));
+            Cow::Owned(protocols)
+        }
+        _ => Cow::Borrowed(protocols),
+    }
+}
+
+/// Merge a list of WM protocols into the ones that are already set on a window. The existing protocols keep
+/// their order, the new ones are added after them, and duplicates are dropped.
+#[inline]
+fn merge_protocols(existing: &[u32], protocols: &[Atom]) -> Vec<Atom> {
+    let mut merged: Vec<Atom> = Vec::with_capacity(existing.len() + protocols.len());
+    existing
+        .iter()
+        .map(|&protocol| Atom::const_from_xid(protocol))
+        .chain(protocols.iter().copied())
+        .for_each(|protocol| {
+            if protocol != ATOM_NONE && !merged.contains(&protocol) {
+                merged.push(protocol);
+            }
+        });
+    merged
+}
+
 crate::create_paramaterizer! {
     pub struct ConfigureWindowParameters : (ConfigWindow, ConfigureWindowRequest) {
         x            (set_x,            x)            : i32,
@@ -79,6 +142,45 @@ crate::create_paramaterizer! {
     }
 }
 
+impl From<&ConfigureRequestEvent> for ConfigureWindowParameters {
+    /// Get the parameters a client asked for in a redirected `ConfigureWindow` request. Only the fields set in
+    /// the event's `value_mask` are filled in, so a window manager can honor exactly what the client requested
+    /// and fill in the rest with its own values before passing the result to `Window::configure`.
+    #[inline]
+    fn from(cre: &ConfigureRequestEvent) -> Self {
+        let mask = cre.value_mask;
+        Self {
+            x: if mask.x() { Some(cre.x.into()) } else { None },
+            y: if mask.y() { Some(cre.y.into()) } else { None },
+            width: if mask.width() {
+                Some(cre.width.into())
+            } else {
+                None
+            },
+            height: if mask.height() {
+                Some(cre.height.into())
+            } else {
+                None
+            },
+            border_width: if mask.border_width() {
+                Some(cre.border_width.into())
+            } else {
+                None
+            },
+            sibling: if mask.sibling() {
+                Some(cre.sibling)
+            } else {
+                None
+            },
+            stack_mode: if mask.stack_mode() {
+                Some(cre.stack_mode)
+            } else {
+                None
+            },
+        }
+    }
+}
+
 /// The return type of `Window::window_attributes_immediate`.
 #[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub struct WindowAttributes {
@@ -146,6 +248,89 @@ impl<'a> From<QueryTreeReply<'a>> for TreeInformation {
     }
 }
 
+/// Check that the property in a `GetProperty` reply has the expected type and format. Returns `Ok(false)` if
+/// the property does not exist.
+///
+/// If the types don't match, the server sends the actual type of the property and no data. A property can
+/// also have the right type but the wrong format if whichever client set it got it wrong. Either way, the
+/// reply can't be read as the type the caller expects, so this returns an error instead.
+#[inline]
+pub(crate) fn check_property_type(
+    gpr: &GetPropertyReply<'_>,
+    expected_type: Atom,
+    expected_format: Option<PropertyFormat>,
+) -> crate::Result<bool> {
+    if gpr.ty == ATOM_NONE {
+        return Ok(false);
+    }
+
+    let type_matches = expected_type == ATOM_ANY || gpr.ty == expected_type;
+    let format_matches = expected_format.map_or(true, |format| gpr.format == format as u8);
+    if type_matches && format_matches {
+        Ok(true)
+    } else {
+        log::warn!(
+            "Expected property of type {:?} and format {:?}, found type {:?} and format {}",
+            expected_type,
+            expected_format,
+            gpr.ty,
+            gpr.format
+        );
+        Err(crate::BreadError::BadObjectRead(Some(
+            "property type mismatch",
+        )))
+    }
+}
+
+#[inline]
+fn read_typed_property<T: AsByteSequence>(
+    gpr: &GetPropertyReply<'_>,
+    ty: PropertyType,
+) -> crate::Result<Option<T>> {
+    if !check_property_type(gpr, Atom::const_from_xid(ty as u32), None)? {
+        return Ok(None);
+    }
+
+    Ok(T::from_bytes(&gpr.value).map(|(x, _)| x))
+}
+
+#[inline]
+pub(crate) fn read_u32_property(
+    gpr: &GetPropertyReply<'_>,
+    ty: Atom,
+) -> crate::Result<Option<Vec<u32>>> {
+    if !check_property_type(gpr, ty, Some(PropertyFormat::ThirtyTwo))? {
+        return Ok(None);
+    }
+
+    Ok(Some(
+        gpr.value
+            .chunks_exact(4)
+            .map(|value| u32::from_ne_bytes([value[0], value[1], value[2], value[3]]))
+            .collect(),
+    ))
+}
+
+#[inline]
+fn read_string_property(gpr: &GetPropertyReply<'_>, ty: Atom) -> crate::Result<Option<String>> {
+    if !check_property_type(gpr, ty, Some(PropertyFormat::Eight))? {
+        return Ok(None);
+    }
+
+    Ok(Some(decode_string_property(gpr.ty, &gpr.value)))
+}
+
+/// Decode the data of a string property. `STRING` properties are encoded in ISO Latin-1, while other text
+/// properties (e.g. `UTF8_STRING`) are assumed to be UTF-8.
+#[inline]
+fn decode_string_property(ty: Atom, value: &[u8]) -> String {
+    if ty == ATOM_STRING {
+        value.iter().map(|&c| char::from(c)).collect()
+    } else {
+        String::from_utf8_lossy(value).into_owned()
+    }
+}
+
 impl Window {
     /// Map this window to the screen.
     #[inline]
@@ -207,7 +392,11 @@ impl Window {
         })
     }
 
-    /// Get a property of this window, resolving immediately.
+    /// Get a property of this window, resolving immediately. Returns `None` if the property does not exist.
+    ///
+    /// # Errors
+    ///
+    /// If the property exists but has a type other than `ty`, this returns `BreadError::BadObjectRead`.
     #[inline]
     pub fn get_property_immediate<Dpy: Display + ?Sized, T: AsByteSequence>(
         self,
@@ -225,7 +414,134 @@ impl Window {
             delete,
             ..Default::default()
         })
-        .map(|gpr| T::from_bytes(&gpr.value).map(|(x, _)| x))
+        .and_then(|gpr| read_typed_property(&gpr, ty))
+    }
+
+    #[inline]
+    pub(crate) fn get_entire_property_request(
+        self,
+        property: Atom,
+        ty: Atom,
+    ) -> GetPropertyRequest {
+        GetPropertyRequest {
+            window: self,
+            property,
+            ty,
+            long_offset: 0,
+            long_length: u32::MAX,
+            delete: false,
+            ..Default::default()
+        }
+    }
+
+    /// Read a property of this window that consists of 32-bit values, such as a list of atoms or windows.
+    /// Returns `None` if the property does not exist.
+    ///
+    /// # Errors
+    ///
+    /// If the property exists but its type is not `ty` or its format is not 32, this returns
+    /// `BreadError::BadObjectRead`, rather than interpreting data that was written as something else.
+    #[inline]
+    pub fn get_property_u32<Dpy: Display + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+        property: Atom,
+        ty: Atom,
+    ) -> crate::Result<Option<Vec<u32>>> {
+        let gpr = dpy.exchange_request(self.get_entire_property_request(property, ty))?;
+        read_u32_property(&gpr, ty)
+    }
+
+    /// Read a property of this window that consists of 32-bit values, async redox.
+    #[cfg(feature = "async")]
+    #[inline]
+    pub async fn get_property_u32_async<Dpy: AsyncDisplay + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+        property: Atom,
+        ty: Atom,
+    ) -> crate::Result<Option<Vec<u32>>> {
+        let gpr = dpy
+            .exchange_request_async(self.get_entire_property_request(property, ty))
+            .await?;
+        read_u32_property(&gpr, ty)
+    }
+
+    /// Read a text property of this window, such as `WM_NAME`. `ty` is usually `ATOM_STRING`, whose data is
+    /// encoded in ISO Latin-1, or the `UTF8_STRING` atom. Returns `None` if the property does not exist.
+    ///
+    /// # Errors
+    ///
+    /// If the property exists but its type is not `ty` or its format is not 8, this returns
+    /// `BreadError::BadObjectRead`.
+    #[inline]
+    pub fn get_property_string<Dpy: Display + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+        property: Atom,
+        ty: Atom,
+    ) -> crate::Result<Option<String>> {
+        let gpr = dpy.exchange_request(self.get_entire_property_request(property, ty))?;
+        read_string_property(&gpr, ty)
+    }
+
+    /// Read a text property of this window, async redox.
+    #[cfg(feature = "async")]
+    #[inline]
+    pub async fn get_property_string_async<Dpy: AsyncDisplay + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+        property: Atom,
+        ty: Atom,
+    ) -> crate::Result<Option<String>> {
+        let gpr = dpy
+            .exchange_request_async(self.get_entire_property_request(property, ty))
+            .await?;
+        read_string_property(&gpr, ty)
+    }
+
+    /// Read all of a property on this window, and delete it in the same request. The property's
+    /// type must match `target`, unless `target` is `ATOM_ANY`.
+    ///
+    /// The selection protocol depends on this: the requestor deletes the property that a selection
+    /// was converted into in order to tell the owner that it has been read, and during an `INCR`
+    /// transfer, deleting each chunk is what tells the owner to send the next one. Since the server
+    /// only deletes a property that has been read in full and whose type matches, this always asks
+    /// for the entire property rather than a fixed length.
+    #[inline]
+    pub fn get_property_and_delete<Dpy: Display + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+        property: Atom,
+        target: Atom,
+    ) -> crate::Result<GetPropertyReply<'static>> {
+        dpy.exchange_request(self.get_property_and_delete_request(property, target))
+    }
+
+    /// Read all of a property on this window, and delete it in the same request, async redox.
+    #[cfg(feature = "async")]
+    #[inline]
+    pub async fn get_property_and_delete_async<Dpy: AsyncDisplay + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+        property: Atom,
+        target: Atom,
+    ) -> crate::Result<GetPropertyReply<'static>> {
+        dpy.exchange_request_async(self.get_property_and_delete_request(property, target))
+            .await
+    }
+
+    #[inline]
+    fn get_property_and_delete_request(self, property: Atom, target: Atom) -> GetPropertyRequest {
+        GetPropertyRequest {
+            window: self,
+            property,
+            ty: target,
+            long_offset: 0,
+            long_length: u32::MAX,
+            delete: true,
+            ..Default::default()
+        }
     }
 
     /// Request struct to change the property of a window.
@@ -233,7 +549,7 @@ impl Window {
     fn change_property_request<T: AsByteSequence>(
         self,
         property: Atom,
-        property_type: PropertyType,
+        property_type: Atom,
         format: PropertyFormat,
         mode: PropMode,
         data: &[T],
@@ -253,7 +569,7 @@ impl Window {
             mode,
             window: self,
             property,
-            ty: Atom::const_from_xid(property_type as u32),
+            ty: property_type,
             format,
             data_len: data.len() as u32,
             data: Cow::Owned(data_bytes),
@@ -262,6 +578,12 @@ impl Window {
     }
 
     /// Change a property of the window, given an atom that identifies that property.
+    ///
+    /// `mode` decides what happens to the property's existing data: `PropMode::Replace` discards it, while
+    /// `PropMode::Prepend` and `PropMode::Append` insert `data` before or after it. This allows a large
+    /// property (e.g. an `INCR` selection transfer) to be built up over several requests. When prepending or
+    /// appending to a property that already exists, `property_type` and `format` must match the existing
+    /// property, or else the server responds with a `Match` error.
     #[inline]
     pub fn change_property<Dpy: Display + ?Sized, T: AsByteSequence>(
         self,
@@ -272,13 +594,14 @@ impl Window {
         mode: PropMode,
         data: &[T],
     ) -> crate::Result<()> {
-        dpy.exchange_request(self.change_property_request(
+        self.change_property_with_type(
+            dpy,
             property,
-            property_type,
+            Atom::const_from_xid(property_type as u32),
             format,
             mode,
             data,
-        ))
+        )
     }
 
     /// Change a property of the window, async redox.
@@ -292,6 +615,51 @@ impl Window {
         format: PropertyFormat,
         mode: PropMode,
         data: &[T],
+    ) -> crate::Result<()> {
+        self.change_property_with_type_async(
+            dpy,
+            property,
+            Atom::const_from_xid(property_type as u32),
+            format,
+            mode,
+            data,
+        )
+        .await
+    }
+
+    /// Change a property of the window, where the type of the property is an arbitrary atom rather than
+    /// one of the predefined `PropertyType`s (e.g. `UTF8_STRING` or `INCR`). See `change_property` for
+    /// more information.
+    #[inline]
+    pub fn change_property_with_type<Dpy: Display + ?Sized, T: AsByteSequence>(
+        self,
+        dpy: &mut Dpy,
+        property: Atom,
+        property_type: Atom,
+        format: PropertyFormat,
+        mode: PropMode,
+        data: &[T],
+    ) -> crate::Result<()> {
+        dpy.exchange_request(self.change_property_request(
+            property,
+            property_type,
+            format,
+            mode,
+            data,
+        ))
+    }
+
+    /// Change a property of the window with an arbitrary type, async redox.
+    #[cfg(feature = "async")]
+    #[inline]
+    pub async fn change_property_with_type_async<Dpy: AsyncDisplay + ?Sized, T: AsByteSequence>(
+        self,
+        dpy: &mut Dpy,
+        property: Atom,
+        property_type: Atom,
+        format: PropertyFormat,
+        mode: PropMode,
+        data: &[T],
     ) -> crate::Result<()> {
         dpy.exchange_request_async(self.change_property_request(
             property,
@@ -333,7 +701,8 @@ impl Window {
         .await
     }
 
-    /// Set the protocols for the WM in regards to this window.
+    /// Set the protocols for the WM in regards to this window. This replaces any protocols that were set
+    /// before; use `merge_wm_protocols` to add to them instead.
     #[inline]
     pub fn set_wm_protocols<Dpy: Display + ?Sized>(
         self,
@@ -346,6 +715,7 @@ impl Window {
             set_wm_protocols_atom,
             "WM_PROTOCOLS"
         );
+        let protocols = with_net_wm_ping(dpy.net_wm_ping_atom(), protocols);
 
         self.change_property(
             dpy,
@@ -353,7 +723,7 @@ impl Window {
             PropertyType::Atom,
             PropertyFormat::ThirtyTwo,
             PropMode::Replace,
-            protocols,
+            &protocols,
         )
     }
 
@@ -371,6 +741,77 @@ impl Window {
             set_wm_protocols_atom,
             "WM_PROTOCOLS"
         );
+        let protocols = with_net_wm_ping(dpy.net_wm_ping_atom(), protocols);
+
+        self.change_property_async(
+            dpy,
+            wm_protocols_atom,
+            PropertyType::Atom,
+            PropertyFormat::ThirtyTwo,
+            PropMode::Replace,
+            &protocols,
+        )
+        .await
+    }
+
+    /// Add to the protocols the WM is told about for this window, keeping the ones that are already set.
+    /// Unlike `set_wm_protocols`, which replaces the whole list, this reads the current `WM_PROTOCOLS`
+    /// property first, so that different parts of a program can each register the protocols they handle.
+    ///
+    /// Note that the property is read and then written in two separate requests. If another client changes
+    /// it in between, that change is lost.
+    #[inline]
+    pub fn merge_wm_protocols<Dpy: Display + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+        protocols: &[Atom],
+    ) -> crate::Result<()> {
+        let wm_protocols_atom = retrieve_atom!(
+            dpy,
+            wm_protocols_atom,
+            set_wm_protocols_atom,
+            "WM_PROTOCOLS"
+        );
+        let existing = self
+            .get_property_u32(dpy, wm_protocols_atom, ATOM_ATOM)?
+            .unwrap_or_default();
+        let protocols = merge_protocols(
+            &existing,
+            &with_net_wm_ping(dpy.net_wm_ping_atom(), protocols),
+        );
+
+        self.change_property(
+            dpy,
+            wm_protocols_atom,
+            PropertyType::Atom,
+            PropertyFormat::ThirtyTwo,
+            PropMode::Replace,
+            &protocols,
+        )
+    }
+
+    /// Add to the WM protocols for this window, async redox.
+    #[cfg(feature = "async")]
+    #[inline]
+    pub async fn merge_wm_protocols_async<Dpy: AsyncDisplay + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+        protocols: &[Atom],
+    ) -> crate::Result<()> {
+        let wm_protocols_atom = retrieve_atom_async!(
+            dpy,
+            wm_protocols_atom,
+            set_wm_protocols_atom,
+            "WM_PROTOCOLS"
+        );
+        let existing = self
+            .get_property_u32_async(dpy, wm_protocols_atom, ATOM_ATOM)
+            .await?
+            .unwrap_or_default();
+        let protocols = merge_protocols(
+            &existing,
+            &with_net_wm_ping(dpy.net_wm_ping_atom(), protocols),
+        );
 
         self.change_property_async(
             dpy,
@@ -378,7 +819,7 @@ impl Window {
             PropertyType::Atom,
             PropertyFormat::ThirtyTwo,
             PropMode::Replace,
-            protocols,
+            &protocols,
         )
         .await
     }
@@ -569,6 +1010,39 @@ impl Window {
         self.change_attributes_async(dpy, props).await
     }
 
+    /// Ask the server to preserve this window's contents while it is obscured or unmapped, so that
+    /// fewer `Expose` events need to be handled.
+    ///
+    /// This is only a hint. The server is free to ignore it, and most modern compositing servers do.
+    /// Programs should still be prepared to redraw on `Expose`.
+    #[inline]
+    pub fn request_backing_store<Dpy: Display + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+        mode: BackingStore,
+    ) -> crate::Result {
+        let props = WindowParameters {
+            backing_store: Some(mode),
+            ..Default::default()
+        };
+        self.change_attributes(dpy, props)
+    }
+
+    /// Ask the server to preserve this window's contents, async redox.
+    #[cfg(feature = "async")]
+    #[inline]
+    pub async fn request_backing_store_async<Dpy: AsyncDisplay + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+        mode: BackingStore,
+    ) -> crate::Result {
+        let props = WindowParameters {
+            backing_store: Some(mode),
+            ..Default::default()
+        };
+        self.change_attributes_async(dpy, props).await
+    }
+
     /// Request to configure window.
     #[inline]
     fn configure_window_request(self, props: ConfigureWindowParameters) -> ConfigureWindowRequest {
@@ -863,14 +1337,14 @@ impl Window {
         selection: Atom,
         target: Atom,
         property: Atom,
-        time: Timestamp,
+        time: Time,
     ) -> ConvertSelectionRequest {
         ConvertSelectionRequest {
             requestor: self,
             selection,
             target,
             property,
-            time,
+            time: time.into(),
             ..Default::default()
         }
     }
@@ -883,7 +1357,7 @@ impl Window {
         selection: Atom,
         target: Atom,
         property: Atom,
-        time: Timestamp,
+        time: Time,
     ) -> crate::Result {
         dpy.exchange_request(self.convert_selection_request(selection, target, property, time))
     }
@@ -897,7 +1371,7 @@ impl Window {
         selection: Atom,
         target: Atom,
         property: Atom,
-        time: Timestamp,
+        time: Time,
     ) -> crate::Result {
         dpy.exchange_request_async(
             self.convert_selection_request(selection, target, property, time),
@@ -905,6 +1379,403 @@ impl Window {
         .await
     }
 
+    #[inline]
+    fn set_selection_owner_request(self, selection: Atom, time: Time) -> SetSelectionOwnerRequest {
+        SetSelectionOwnerRequest {
+            owner: self,
+            selection,
+            time: time.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Make this window the owner of a selection.
+    #[inline]
+    pub fn set_selection_owner<Dpy: Display + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+        selection: Atom,
+        time: Time,
+    ) -> crate::Result {
+        dpy.exchange_request(self.set_selection_owner_request(selection, time))
+    }
+
+    /// Make this window the owner of a selection, async redox.
+    #[cfg(feature = "async")]
+    #[inline]
+    pub async fn set_selection_owner_async<Dpy: AsyncDisplay + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+        selection: Atom,
+        time: Time,
+    ) -> crate::Result {
+        dpy.exchange_request_async(self.set_selection_owner_request(selection, time))
+            .await
+    }
+
+    #[inline]
+    pub(crate) fn set_input_focus_request(
+        self,
+        revert_to: InputFocus,
+        time: Time,
+    ) -> SetInputFocusRequest {
+        SetInputFocusRequest {
+            focus: self,
+            revert_to,
+            time: time.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Give the input focus to this window.
+    #[inline]
+    pub fn set_input_focus<Dpy: Display + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+        revert_to: InputFocus,
+        time: Time,
+    ) -> crate::Result {
+        dpy.exchange_request(self.set_input_focus_request(revert_to, time))
+    }
+
+    /// Give the input focus to this window, async redox.
+    #[cfg(feature = "async")]
+    #[inline]
+    pub async fn set_input_focus_async<Dpy: AsyncDisplay + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+        revert_to: InputFocus,
+        time: Time,
+    ) -> crate::Result {
+        dpy.exchange_request_async(self.set_input_focus_request(revert_to, time))
+            .await
+    }
+
+    #[inline]
+    fn grab_pointer_request(
+        self,
+        owner_events: bool,
+        event_mask: EventMask,
+        pointer_mode: GrabMode,
+        keyboard_mode: GrabMode,
+        confine_to: Window,
+        cursor: Cursor,
+        time: Time,
+    ) -> GrabPointerRequest {
+        GrabPointerRequest {
+            owner_events,
+            grab_window: self,
+            event_mask,
+            pointer_mode,
+            keyboard_mode,
+            confine_to,
+            cursor,
+            time: time.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Actively grab the pointer, using this window as the grab window.
+    #[inline]
+    pub fn grab_pointer<Dpy: Display + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+        owner_events: bool,
+        event_mask: EventMask,
+        pointer_mode: GrabMode,
+        keyboard_mode: GrabMode,
+        confine_to: Window,
+        cursor: Cursor,
+        time: Time,
+    ) -> crate::Result<GrabStatus> {
+        dpy.exchange_request(self.grab_pointer_request(
+            owner_events,
+            event_mask,
+            pointer_mode,
+            keyboard_mode,
+            confine_to,
+            cursor,
+            time,
+        ))
+        .map(|gpr| gpr.status)
+    }
+
+    /// Actively grab the pointer, using this window as the grab window, async redox.
+    #[cfg(feature = "async")]
+    #[inline]
+    pub async fn grab_pointer_async<Dpy: AsyncDisplay + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+        owner_events: bool,
+        event_mask: EventMask,
+        pointer_mode: GrabMode,
+        keyboard_mode: GrabMode,
+        confine_to: Window,
+        cursor: Cursor,
+        time: Time,
+    ) -> crate::Result<GrabStatus> {
+        dpy.exchange_request_async(self.grab_pointer_request(
+            owner_events,
+            event_mask,
+            pointer_mode,
+            keyboard_mode,
+            confine_to,
+            cursor,
+            time,
+        ))
+        .await
+        .map(|gpr| gpr.status)
+    }
+
+    #[inline]
+    fn grab_button_request(
+        self,
+        button: ButtonIndex,
+        modifiers: ModMask,
+        owner_events: bool,
+        event_mask: EventMask,
+        pointer_mode: GrabMode,
+        keyboard_mode: GrabMode,
+        confine_to: Window,
+        cursor: Cursor,
+    ) -> GrabButtonRequest {
+        GrabButtonRequest {
+            owner_events,
+            grab_window: self,
+            event_mask,
+            pointer_mode,
+            keyboard_mode,
+            confine_to,
+            cursor,
+            button,
+            modifiers,
+            ..Default::default()
+        }
+    }
+
+    /// Establish a passive grab on a pointer button, using this window as the grab window. The pointer is
+    /// grabbed once `button` is pressed with exactly the given `modifiers` held down inside of this window.
+    /// `ButtonIndex::Any` and `ModMask::ANY` can be used to grab any button or any combination of modifiers.
+    ///
+    /// Note that a grab for a specific set of modifiers does not trigger while Caps Lock or Num Lock is
+    /// active. Use `grab_button_ignoring_locks` for that.
+    #[inline]
+    pub fn grab_button<Dpy: Display + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+        button: ButtonIndex,
+        modifiers: ModMask,
+        owner_events: bool,
+        event_mask: EventMask,
+        pointer_mode: GrabMode,
+        keyboard_mode: GrabMode,
+        confine_to: Window,
+        cursor: Cursor,
+    ) -> crate::Result {
+        dpy.exchange_request(self.grab_button_request(
+            button,
+            modifiers,
+            owner_events,
+            event_mask,
+            pointer_mode,
+            keyboard_mode,
+            confine_to,
+            cursor,
+        ))
+    }
+
+    /// Establish a passive grab on a pointer button, async redox.
+    #[cfg(feature = "async")]
+    #[inline]
+    pub async fn grab_button_async<Dpy: AsyncDisplay + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+        button: ButtonIndex,
+        modifiers: ModMask,
+        owner_events: bool,
+        event_mask: EventMask,
+        pointer_mode: GrabMode,
+        keyboard_mode: GrabMode,
+        confine_to: Window,
+        cursor: Cursor,
+    ) -> crate::Result {
+        dpy.exchange_request_async(self.grab_button_request(
+            button,
+            modifiers,
+            owner_events,
+            event_mask,
+            pointer_mode,
+            keyboard_mode,
+            confine_to,
+            cursor,
+        ))
+        .await
+    }
+
+    /// Establish a passive grab on a pointer button that triggers regardless of whether Caps Lock or Num Lock
+    /// is active. This registers the same grab for `modifiers`, `modifiers | Lock`, `modifiers | Mod2` and
+    /// `modifiers | Lock | Mod2`. Release it with `ungrab_button_ignoring_locks`.
+    ///
+    /// This assumes that Num Lock is bound to `Mod2`, which is the case on nearly every system.
+    #[inline]
+    pub fn grab_button_ignoring_locks<Dpy: Display + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+        button: ButtonIndex,
+        modifiers: ModMask,
+        owner_events: bool,
+        event_mask: EventMask,
+        pointer_mode: GrabMode,
+        keyboard_mode: GrabMode,
+        confine_to: Window,
+        cursor: Cursor,
+    ) -> crate::Result {
+        let toks = with_lock_combinations(modifiers)
+            .map(|modifiers| {
+                dpy.send_request(self.grab_button_request(
+                    button,
+                    modifiers,
+                    owner_events,
+                    event_mask,
+                    pointer_mode,
+                    keyboard_mode,
+                    confine_to,
+                    cursor,
+                ))
+            })
+            .collect::<crate::Result<Vec<_>>>()?;
+        toks.into_iter()
+            .try_for_each(|tok| dpy.resolve_request(tok))
+    }
+
+    /// Establish a passive grab on a pointer button regardless of lock modifiers, async redox.
+    #[cfg(feature = "async")]
+    #[inline]
+    pub async fn grab_button_ignoring_locks_async<Dpy: AsyncDisplay + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+        button: ButtonIndex,
+        modifiers: ModMask,
+        owner_events: bool,
+        event_mask: EventMask,
+        pointer_mode: GrabMode,
+        keyboard_mode: GrabMode,
+        confine_to: Window,
+        cursor: Cursor,
+    ) -> crate::Result {
+        for modifiers in with_lock_combinations(modifiers) {
+            dpy.exchange_request_async(self.grab_button_request(
+                button,
+                modifiers,
+                owner_events,
+                event_mask,
+                pointer_mode,
+                keyboard_mode,
+                confine_to,
+                cursor,
+            ))
+            .await?;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn ungrab_button_request(self, button: ButtonIndex, modifiers: ModMask) -> UngrabButtonRequest {
+        UngrabButtonRequest {
+            button,
+            grab_window: self,
+            modifiers,
+            ..Default::default()
+        }
+    }
+
+    /// Release a passive button grab established by `grab_button_ignoring_locks`.
+    #[inline]
+    pub fn ungrab_button_ignoring_locks<Dpy: Display + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+        button: ButtonIndex,
+        modifiers: ModMask,
+    ) -> crate::Result {
+        with_lock_combinations(modifiers).try_for_each(|modifiers| {
+            dpy.exchange_request(self.ungrab_button_request(button, modifiers))
+        })
+    }
+
+    /// Release a passive button grab established by `grab_button_ignoring_locks`, async redox.
+    #[cfg(feature = "async")]
+    #[inline]
+    pub async fn ungrab_button_ignoring_locks_async<Dpy: AsyncDisplay + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+        button: ButtonIndex,
+        modifiers: ModMask,
+    ) -> crate::Result {
+        for modifiers in with_lock_combinations(modifiers) {
+            dpy.exchange_request_async(self.ungrab_button_request(button, modifiers))
+                .await?;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn grab_keyboard_request(
+        self,
+        owner_events: bool,
+        pointer_mode: GrabMode,
+        keyboard_mode: GrabMode,
+        time: Time,
+    ) -> GrabKeyboardRequest {
+        GrabKeyboardRequest {
+            owner_events,
+            grab_window: self,
+            pointer_mode,
+            keyboard_mode,
+            time: time.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Actively grab the keyboard, using this window as the grab window.
+    #[inline]
+    pub fn grab_keyboard<Dpy: Display + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+        owner_events: bool,
+        pointer_mode: GrabMode,
+        keyboard_mode: GrabMode,
+        time: Time,
+    ) -> crate::Result<GrabStatus> {
+        dpy.exchange_request(self.grab_keyboard_request(
+            owner_events,
+            pointer_mode,
+            keyboard_mode,
+            time,
+        ))
+        .map(|gkr| gkr.status)
+    }
+
+    /// Actively grab the keyboard, using this window as the grab window, async redox.
+    #[cfg(feature = "async")]
+    #[inline]
+    pub async fn grab_keyboard_async<Dpy: AsyncDisplay + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+        owner_events: bool,
+        pointer_mode: GrabMode,
+        keyboard_mode: GrabMode,
+        time: Time,
+    ) -> crate::Result<GrabStatus> {
+        dpy.exchange_request_async(self.grab_keyboard_request(
+            owner_events,
+            pointer_mode,
+            keyboard_mode,
+            time,
+        ))
+        .await
+        .map(|gkr| gkr.status)
+    }
+
     /// Set the cursor used by this window.
     #[inline]
     pub fn set_cursor<Dpy: Display + ?Sized>(self, dpy: &mut Dpy, cursor: Cursor) -> crate::Result {
@@ -1166,7 +2037,8 @@ impl Window {
         })
     }
 
-    /// Get a property of this window, resolving immediately, async redox.
+    /// Get a property of this window, resolving immediately, async redox. See `get_property_immediate` for
+    /// more information.
     #[cfg(feature = "async")]
     #[inline]
     pub async fn get_property_immediate_async<Dpy: AsyncDisplay + ?Sized, T: AsByteSequence>(
@@ -1186,7 +2058,7 @@ impl Window {
             ..Default::default()
         })
         .await
-        .map(|gpr| T::from_bytes(&gpr.value).map(|(x, _)| x))
+        .and_then(|gpr| read_typed_property(&gpr, ty))
     }
 }
 
@@ -1294,3 +2166,86 @@ pub enum PropertyFormat {
     Sixteen = 16,
     ThirtyTwo = 32,
 }
+
+#[test]
+fn get_property_and_delete_reads_everything() {
+    let gpr = Window::const_from_xid(1)
+        .get_property_and_delete_request(Atom::const_from_xid(2), Atom::const_from_xid(0));
+    assert!(gpr.delete);
+    assert_eq!(gpr.long_offset, 0);
+    assert_eq!(gpr.long_length, u32::MAX);
+}
+
+#[test]
+fn configure_request_honors_value_mask() {
+    let mut value_mask = ConfigWindow::default();
+    value_mask.set_width(true).set_stack_mode(true);
+    let cre = ConfigureRequestEvent {
+        x: 10,
+        width: 200,
+        stack_mode: StackMode::Below,
+        value_mask,
+        ..Default::default()
+    };
+
+    let params = ConfigureWindowParameters::from(&cre);
+    assert_eq!(
+        params,
+        ConfigureWindowParameters {
+            width: Some(200),
+            stack_mode: Some(StackMode::Below),
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn lock_combinations_cover_caps_and_num_lock() {
+    let combos: Vec<u16> = with_lock_combinations(ModMask::CONTROL)
+        .map(|m| m.inner)
+        .collect();
+    assert_eq!(combos, [0x04, 0x06, 0x14, 0x16]);
+
+    // AnyModifier already covers the locks
+    assert_eq!(with_lock_combinations(ModMask::ANY).count(), 1);
+}
+
+#[test]
+fn property_type_mismatch_is_an_error() {
+    let gpr = GetPropertyReply {
+        ty: ATOM_STRING,
+        format: 8,
+        value: Cow::Borrowed(b"caf\xe9"),
+        ..Default::default()
+    };
+    assert_eq!(
+        read_string_property(&gpr, ATOM_STRING).unwrap().as_deref(),
+        Some("caf\u{e9}")
+    );
+    assert!(read_string_property(&gpr, ATOM_ANY).is_ok());
+    assert!(matches!(
+        read_u32_property(&gpr, ATOM_STRING),
+        Err(crate::BreadError::BadObjectRead(_))
+    ));
+    assert!(matches!(
+        read_u32_property(&gpr, Atom::const_from_xid(4)),
+        Err(crate::BreadError::BadObjectRead(_))
+    ));
+
+    let missing = GetPropertyReply::default();
+    assert_eq!(read_u32_property(&missing, ATOM_STRING).unwrap(), None);
+}
+
+#[test]
+fn merged_protocols_keep_existing_ones() {
+    let delete_window = Atom::const_from_xid(10);
+    let ping = Atom::const_from_xid(11);
+    let take_focus = Atom::const_from_xid(12);
+
+    let merged = merge_protocols(&[ping.xid(), delete_window.xid()], &[take_focus, ping]);
+    assert_eq!(merged, alloc::vec![ping, delete_window, take_focus]);
+
+    // duplicates already in the property are dropped too
+    let merged = merge_protocols(&[ping.xid(), ping.xid()], &[]);
+    assert_eq!(merged, alloc::vec![ping]);
+}
@@ -10,7 +10,7 @@ use crate::auto::{
         ButtonPressEvent, ButtonReleaseEvent, CirculateNotifyEvent, CirculateRequestEvent,
         ClientMessageEvent, ConfigureNotifyEvent, ConfigureRequestEvent, CreateNotifyEvent,
         DestroyNotifyEvent, EnterNotifyEvent, ExposeEvent, FocusInEvent, FocusOutEvent,
-        GraphicsExposureEvent, GravityNotifyEvent, KeyPressEvent, KeyReleaseEvent,
+        GraphicsExposureEvent, GravityNotifyEvent, KeyPressEvent, KeyReleaseEvent, Keycode,
         KeymapNotifyEvent, LeaveNotifyEvent, MapNotifyEvent, MapRequestEvent, MappingNotifyEvent,
         NoExposureEvent, PropertyNotifyEvent, ReparentNotifyEvent, ResizeRequestEvent,
         SelectionClearEvent, SelectionNotifyEvent, SelectionRequestEvent, UnmapNotifyEvent,
@@ -309,3 +309,48 @@ impl Event {
         }
     }
 }
+
+impl KeymapNotifyEvent {
+    /// Tell whether or not the key with the given keycode was held down when this event was generated.
+    ///
+    /// The server sends this event directly after a `FocusIn` or `EnterNotify` event if the client selected
+    /// `KEYMAP_STATE`, so that clients can find out which keys were already held down when they gained
+    /// focus. Unlike every other event, it does not contain a sequence number; its bytes are a bitmap of
+    /// the keyboard, with one bit per keycode. The byte for keycodes 0 through 7 is left out, since those
+    /// keycodes are never used, so `keys[0]` holds keycodes 8 through 15.
+    #[inline]
+    #[must_use]
+    pub fn is_pressed(&self, keycode: Keycode) -> bool {
+        match (usize::from(keycode) / 8).checked_sub(1) {
+            Some(index) => self.keys[index] & (1 << (keycode % 8)) != 0,
+            None => false,
+        }
+    }
+
+    /// Iterate over the keycodes of every key that was held down when this event was generated.
+    #[inline]
+    pub fn pressed_keys(&self) -> impl Iterator<Item = Keycode> + '_ {
+        (8..=Keycode::MAX).filter(move |&keycode| self.is_pressed(keycode))
+    }
+}
+
+#[test]
+fn keymap_notify_skips_first_byte() {
+    let mut keys = [0; 31];
+    // keycode 9 (usually Escape) and keycode 255
+    keys[0] = 0b10;
+    keys[30] = 0b1000_0000;
+    let event = KeymapNotifyEvent {
+        event_type: KeymapNotifyEvent::OPCODE,
+        keys,
+    };
+
+    assert!(event.is_pressed(9));
+    assert!(event.is_pressed(255));
+    assert!(!event.is_pressed(1));
+    assert!(!event.is_pressed(8));
+    assert_eq!(
+        event.pressed_keys().collect::<alloc::vec::Vec<_>>(),
+        [9, 255]
+    );
+}
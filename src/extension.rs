@@ -1,6 +1,7 @@
 // MIT/Apache2 License
 
 use super::auto::xproto::QueryExtensionReply;
+use alloc::string::String;
 
 /// Data related to an extension.
 #[derive(Debug, Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash)]
@@ -12,7 +13,7 @@ pub struct Extension {
 
 impl Extension {
     #[inline]
-    pub(crate) fn from_reply(qer: QueryExtensionReply) -> crate::Result<Extension> {
+    pub(crate) fn from_reply(qer: QueryExtensionReply, name: &str) -> crate::Result<Extension> {
         if qer.present {
             Ok(Self {
                 major_opcode: qer.major_opcode,
@@ -20,8 +21,9 @@ impl Extension {
                 first_error: qer.first_error,
             })
         } else {
-            //Err(crate::BreadError::ExtensionNotPresent(ext_name))
-            unimplemented!("Extension not present")
+            Err(crate::BreadError::ExtensionNotPresent(
+                String::from(name).into(),
+            ))
         }
     }
 }
@@ -356,10 +356,8 @@ where
             _ => 1,
         };
 
-        let min_bytes_per_line = match format {
-            ImageFormat::ZPixmap => roundup(bits_per_pixel as usize * width, quantum as usize) >> 3,
-            _ => roundup(width + x_offset, quantum as usize),
-        };
+        let min_bytes_per_line =
+            min_bytes_per_line(format, bits_per_pixel, width, x_offset, quantum);
 
         let bytes_per_line = match bytes_per_line {
             None => min_bytes_per_line,
@@ -397,6 +395,26 @@ where
         &*self.data
     }
 
+    /// Get the data for a single plane of an `XyPixmap` or `XyBitmap` image.
+    ///
+    /// Unlike `ZPixmap` images, which store all of the bits of a pixel together, these images store each
+    /// plane separately, one after another, starting with the most significant plane. Each plane is laid
+    /// out like a 1-bit image, with `bytes_per_line` bytes per scanline. Note that an image read with a
+    /// plane mask only contains the planes in that mask, so index 0 is the most significant plane that was
+    /// read rather than the most significant plane of the drawable.
+    ///
+    /// Returns `None` if this is a `ZPixmap` image, or if it has no plane at the given index.
+    #[inline]
+    #[must_use]
+    pub fn plane(&self, index: usize) -> Option<&[u8]> {
+        if self.format == ImageFormat::ZPixmap || index >= self.depth as usize {
+            return None;
+        }
+
+        let plane_len = self.bytes_per_line * self.height;
+        self.data.get(index * plane_len..(index + 1) * plane_len)
+    }
+
     /// Clone this image to an equivalent but with a boxed slice as its data.
     #[inline]
     pub fn clone_to_boxed_slice(&self) -> Image<Box<[u8]>> {
@@ -432,7 +450,8 @@ impl Image<Box<[u8]>> {
         reply: GetImageReply,
     ) -> Self {
         if format == ImageFormat::XyPixmap {
-            let depth = (plane_mask & (0xffff_ffff >> (32 - reply.depth))).count_ones();
+            // only the planes in the plane mask are sent, so that's the depth of the image
+            let depth = (plane_mask & LOW_BITS_TABLE[reply.depth as usize] as usize).count_ones();
 
             Self::new(
                 dpy,
@@ -686,6 +705,23 @@ where
 }
 
 // helper functions
+
+/// The smallest number of bytes a scanline of an image can take up. For the `XyPixmap` and `XyBitmap` formats,
+/// this is the size of a scanline of a single plane.
+#[inline]
+fn min_bytes_per_line(
+    format: ImageFormat,
+    bits_per_pixel: u8,
+    width: usize,
+    x_offset: usize,
+    quantum: u32,
+) -> usize {
+    match format {
+        ImageFormat::ZPixmap => roundup(bits_per_pixel as usize * width, quantum as usize) >> 3,
+        _ => roundup(width + x_offset, quantum as usize) >> 3,
+    }
+}
+
 #[inline]
 fn xyindex(x: usize, y: usize, image: &dyn GenericImage) -> usize {
     (y * image.bytes_per_line())
@@ -700,18 +736,19 @@ fn zindex(x: usize, y: usize, image: &dyn GenericImage) -> usize {
 
 #[inline]
 fn xy_normalize_bits(bits: &mut [u8], image: &dyn GenericImage) {
-    if image.byte_order() == ImageOrder::MsbFirst || image.bit_order() == ImageOrder::MsbFirst {
-        if image.byte_order() != image.bit_order() {
-            match image.bitmap_unit() {
-                16 => bits.swap(0, 1),
-                32 => {
-                    bits.swap(0, 3);
-                    bits.swap(1, 2);
-                }
-                _ => (),
+    if image.byte_order() != image.bit_order() {
+        match image.bitmap_unit() {
+            16 => bits.swap(0, 1),
+            32 => {
+                bits.swap(0, 3);
+                bits.swap(1, 2);
             }
+            _ => (),
         }
+    }
 
+    // the bits within each byte only need to be reversed if they start from the most significant one
+    if image.bit_order() == ImageOrder::MsbFirst {
         reverse_bytes(bits);
     }
 }
@@ -774,3 +811,64 @@ fn put_bits(source: &[u8], mut dstoffset: usize, mut numbits: usize, dest: &mut
         numbits -= dstoffset;
     }
 }
+
+#[test]
+fn xy_pixmap_planes_are_stored_separately() {
+    // a 2x2 image with two planes: the most significant plane first, then the least significant one
+    let width = 2;
+    let height = 2;
+    let bytes_per_line = min_bytes_per_line(ImageFormat::XyPixmap, 1, width, 0, 8);
+    assert_eq!(bytes_per_line, 1);
+
+    let image = Image {
+        width,
+        height,
+        x_offset: 0,
+        format: ImageFormat::XyPixmap,
+        depth: 2,
+        bit_order: ImageOrder::LsbFirst,
+        byte_order: ImageOrder::LsbFirst,
+        bitmap_unit: 8,
+        bitmap_pad: 8,
+        bytes_per_line,
+        bits_per_pixel: 1,
+        red_mask: 0,
+        green_mask: 0,
+        blue_mask: 0,
+        data: alloc::vec![0b01, 0b11, 0b10, 0b01],
+    };
+
+    assert_eq!(image.pixel(0, 0), 0b10);
+    assert_eq!(image.pixel(1, 0), 0b01);
+    assert_eq!(image.pixel(0, 1), 0b11);
+    assert_eq!(image.pixel(1, 1), 0b10);
+
+    assert_eq!(image.plane(0), Some(&[0b01, 0b11][..]));
+    assert_eq!(image.plane(1), Some(&[0b10, 0b01][..]));
+    assert_eq!(image.plane(2), None);
+}
+
+#[test]
+fn msb_first_bitmaps_are_normalized() {
+    let image = Image {
+        width: 8,
+        height: 1,
+        x_offset: 0,
+        format: ImageFormat::XyPixmap,
+        depth: 1,
+        bit_order: ImageOrder::MsbFirst,
+        byte_order: ImageOrder::LsbFirst,
+        bitmap_unit: 8,
+        bitmap_pad: 8,
+        bytes_per_line: 1,
+        bits_per_pixel: 1,
+        red_mask: 0,
+        green_mask: 0,
+        blue_mask: 0,
+        data: alloc::vec![0b1000_0001],
+    };
+
+    assert_eq!(image.pixel(0, 0), 1);
+    assert_eq!(image.pixel(1, 0), 0);
+    assert_eq!(image.pixel(7, 0), 1);
+}
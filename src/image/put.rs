@@ -242,12 +242,8 @@ fn put_sub_image_req<Dpy: DisplayBase + ?Sized, Data: Deref<Target = [u8]>>(
     // How many bits are available to use.
     let mut req: PutImageRequest = Default::default();
 
-    #[allow(unused_comparisons, clippy::absurd_extreme_comparisons)]
-    let available: usize = if dpy.setup().maximum_request_length > 65535 {
-        65536 << 2
-    } else {
-        (dpy.setup().maximum_request_length as usize) << 2
-    } - req.size();
+    // the setup only has the 16-bit limit, so ask the display, which knows about bigreq
+    let available: usize = dpy.effective_max_request_bytes().saturating_sub(req.size());
 
     let (left_pad, bytes_per_row) =
         if image.bits_per_pixel() == 1 || image.format() != ImageFormat::ZPixmap {
@@ -14,10 +14,18 @@ both of the above.
 
 use crate::{
     auto::xproto::{KeyButMask, Keycode, Keysym},
-    display::Display,
+    display::{
+        prelude::*,
+        traits::{ModifierMapDiff, ModifierMapping},
+        Display,
+    },
 };
+use alloc::boxed::Box;
 use gluten_keyboard::Key;
 
+#[cfg(feature = "xkb")]
+use crate::auto::xkb::StateNotifyEvent;
+
 #[cfg(feature = "async")]
 use crate::display::AsyncDisplay;
 
@@ -29,16 +37,25 @@ pub use xproto::*;
 
 pub type DefaultKeymap = XprotoKeymap;
 
+/// The keysym used to fill the unused entries of a keycode's keysym list.
+const NO_SYMBOL: Keysym = 0;
+
 /// Keep track of keys and currently tracked modifiers.
 #[derive(Debug, Clone)]
 pub struct KeyboardState<Km: ?Sized = DefaultKeymap> {
+    group: u8,
+    modifier_mapping: Option<ModifierMapping>,
     keymap: Km,
 }
 
 impl<Km> KeyboardState<Km> {
     #[inline]
     pub fn from_keymap(keymap: Km) -> Self {
-        Self { keymap }
+        Self {
+            group: 0,
+            modifier_mapping: None,
+            keymap,
+        }
     }
 }
 
@@ -57,28 +74,145 @@ impl KeyboardState<XprotoKeymap> {
     }
 }
 
+impl<Km: ?Sized> KeyboardState<Km> {
+    /// The index of the active keyboard group (i.e. layout), starting from zero. This can be used to show
+    /// which layout is active.
+    #[inline]
+    pub fn current_group(&self) -> u8 {
+        self.group
+    }
+
+    /// Set the active keyboard group. This is kept up to date by `process_xkb_state`, but it can also be
+    /// set by hand if the group is tracked some other way.
+    #[inline]
+    pub fn set_group(&mut self, group: u8) {
+        self.group = group;
+    }
+
+    /// The modifier mapping as of the last call to `refresh_modifier_mapping_diff`, if any.
+    #[inline]
+    pub fn modifier_mapping(&self) -> Option<&ModifierMapping> {
+        self.modifier_mapping.as_ref()
+    }
+
+    /// Fetch the modifier mapping from the server and find out which modifiers changed since the last time
+    /// it was fetched. This should be called when a `MappingNotify` event with a request of
+    /// `Mapping::Modifier` is received; programs that grab keys with modifiers then only need to re-grab the
+    /// keys involving the modifiers in the diff.
+    ///
+    /// The first time this is called, every modifier that has keycodes mapped to it is considered changed.
+    #[inline]
+    pub fn refresh_modifier_mapping_diff<Dpy: Display + ?Sized>(
+        &mut self,
+        display: &mut Dpy,
+    ) -> crate::Result<ModifierMapDiff> {
+        let mapping = display.get_modifier_mapping_immediate()?;
+        Ok(self.store_modifier_mapping(mapping))
+    }
+
+    /// Fetch the modifier mapping and find out which modifiers changed, async redox.
+    #[cfg(feature = "async")]
+    #[inline]
+    pub async fn refresh_modifier_mapping_diff_async<Dpy: AsyncDisplay + ?Sized>(
+        &mut self,
+        display: &mut Dpy,
+    ) -> crate::Result<ModifierMapDiff> {
+        let mapping = display.get_modifier_mapping_immediate_async().await?;
+        Ok(self.store_modifier_mapping(mapping))
+    }
+
+    #[inline]
+    fn store_modifier_mapping(&mut self, mapping: ModifierMapping) -> ModifierMapDiff {
+        let diff = match self.modifier_mapping {
+            Some(ref old) => old.diff(&mapping),
+            None => ModifierMapping {
+                keycodes_per_modifier: 0,
+                keycodes: Box::new([]),
+            }
+            .diff(&mapping),
+        };
+        self.modifier_mapping = Some(mapping);
+        diff
+    }
+
+    /// Update the active keyboard group from an XKB `StateNotify` event. The event can be parsed from
+    /// the bytes of an `Event::NoneOfTheAbove` whose opcode is the XKB extension's first event.
+    #[cfg(feature = "xkb")]
+    #[inline]
+    pub fn process_xkb_state(&mut self, event: &StateNotifyEvent) {
+        self.group = event.group as u8;
+    }
+}
+
 impl<Km: Keymap + ?Sized> KeyboardState<Km> {
     #[inline]
     pub fn lookup_keysyms(&self, keycode: Keycode) -> &[Keysym] {
         self.keymap.lookup_keysyms(keycode)
     }
 
+    /// Convert a keycode into a key, using the active group and the shift state in `modifiers`.
+    ///
+    /// The core protocol lists the keysyms of each group in pairs: the unshifted keysym, then the shifted
+    /// one. If the keycode has no keysyms for the active group, the first group is used instead, and if a
+    /// group has no shifted keysym, the unshifted one is used regardless of the shift state.
     #[inline]
     pub fn process_keycode(&mut self, keycode: Keycode, modifiers: KeyButMask) -> Option<Key> {
         // get the index we need
         let index = if modifiers.shift() { 1 } else { 0 };
+        let group = usize::from(self.group);
 
         let syms = self.lookup_keysyms(keycode);
-        if syms.is_empty() {
-            None
-        } else if syms.len() == 1 {
-            keysym_to_key(syms[0])
-        } else {
-            keysym_to_key(syms[index])
-        }
+        let syms = match syms.get(group * 2..) {
+            Some(group_syms) if group_syms.iter().take(2).any(|&sym| sym != NO_SYMBOL) => {
+                group_syms
+            }
+            _ => syms,
+        };
+
+        syms.get(index)
+            .copied()
+            .filter(|&sym| sym != NO_SYMBOL)
+            .or_else(|| syms.first().copied())
+            .and_then(keysym_to_key)
     }
 }
 
 pub trait Keymap {
     fn lookup_keysyms(&self, keycode: Keycode) -> &[Keysym];
 }
+
+#[test]
+fn process_keycode_uses_active_group() {
+    struct TestKeymap;
+
+    impl Keymap for TestKeymap {
+        fn lookup_keysyms(&self, keycode: Keycode) -> &[Keysym] {
+            match keycode {
+                // a, A, Cyrillic ef, Cyrillic EF
+                8 => &[0x61, 0x41, 0x6c6, 0x6e6],
+                // 1 with no shifted keysym, and nothing in the second group
+                9 => &[0x31, NO_SYMBOL],
+                _ => &[],
+            }
+        }
+    }
+
+    let mut state = KeyboardState::from_keymap(TestKeymap);
+    let mut shift = KeyButMask::default();
+    shift.set_shift(true);
+
+    assert_eq!(
+        state.process_keycode(8, KeyButMask::default()),
+        keysym_to_key(0x61)
+    );
+    assert_eq!(state.process_keycode(9, shift), keysym_to_key(0x31));
+
+    state.set_group(1);
+    assert_eq!(state.current_group(), 1);
+    assert_eq!(state.process_keycode(8, shift), keysym_to_key(0x6e6));
+    assert_eq!(
+        state.process_keycode(9, KeyButMask::default()),
+        keysym_to_key(0x31)
+    );
+    assert_eq!(state.process_keycode(10, KeyButMask::default()), None);
+}
@@ -64,7 +64,7 @@
 //!     // set up the exit protocol, this ensures the window exits when the "X"
 //!     // button is clicked
 //!     let wm_delete_window = conn
-//!         .intern_atom_immediate("WM_DELETE_WINDOW".to_owned(), false)?;
+//!         .intern_atom_immediate("WM_DELETE_WINDOW", false)?;
 //!     window.set_wm_protocols(&mut conn, &[wm_delete_window])?;
 //!
 //!     'evloop: loop {
@@ -91,7 +91,7 @@
 //! * `std` - Enabled by default. This enables the use of the standard library, and enables
 //!           `DisplayConnection` and `DisplayConnection::create`. This library can be used without
 //!           the standard library; however, it requires the programmer to provide a connection, rather
-//!           than building a connection itself.
+//!           than building a connection itself by implementing the `Connection` trait.
 //! * `async` - Enables the `_async` suffix family of functions. These functions and methods are similar
 //!             to their blocking variants, but they use non-blocking variants of network calls. This uses
 //!             the [`async-io`](https://crates.io/crates/async-io) crate to provide non-blocking calls.
@@ -151,7 +151,9 @@ pub mod extension;
 pub mod image;
 pub mod keyboard;
 pub(crate) mod paramatizer;
+mod time;
 pub(crate) mod util;
+pub mod xdnd;
 mod xid;
 
 #[cfg(feature = "xkb")]
@@ -172,6 +174,7 @@ pub use error::*;
 pub use event::Event;
 pub use extension::*;
 pub use keyboard::*;
+pub use time::Time;
 pub use xid::*;
 
 pub type Fd = cty::c_int;
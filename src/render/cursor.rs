@@ -0,0 +1,410 @@
+// MIT/Apache2 License
+
+use super::{RenderDisplay, StandardFormat};
+use crate::{
+    auto::{
+        render::{CreateCursorRequest, Pictformat, Picture},
+        xproto::{
+            Cursor, ImageFormat, Pixmap, QueryBestSizeReply, QueryBestSizeRequest, QueryShapeOf,
+            Window,
+        },
+    },
+    display::{generate_xid, prelude::*, Display, DisplayBase},
+    image::Image,
+    BreadError,
+};
+use alloc::{vec, vec::Vec};
+
+/// An ARGB image that has been resized to fit the server's cursor size limits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FittedCursorImage {
+    width: u16,
+    height: u16,
+    pixels: Vec<u32>,
+    hotspot_x: u16,
+    hotspot_y: u16,
+}
+
+impl<Dpy: Display + ?Sized> RenderDisplay<Dpy> {
+    /// Create a cursor from an ARGB image, using the `XRender` extension.
+    ///
+    /// `pixels` contains `width * height` pixels in row-major order, each of which is a 32-bit ARGB value
+    /// with premultiplied alpha (the same format used by Xcursor themes). The hotspot is the point within
+    /// the image that lines up with the pointer's position.
+    ///
+    /// Some drivers only support hardware cursors up to a certain size (often 64x64), and a larger cursor
+    /// may silently fail to display. In order to avoid this, the server is first asked for the largest
+    /// cursor size it supports via `QueryBestSize`. If the image is larger than that, it is scaled down with
+    /// a box filter, keeping its aspect ratio, and a warning is logged. Images that are already small
+    /// enough are left alone.
+    ///
+    /// # Errors
+    ///
+    /// This returns `BreadError::StaticMsg` if `pixels` does not contain `width * height` pixels, or if the
+    /// server does not have an ARGB32 picture format. Otherwise, it can return any error that sending the
+    /// requests involved can.
+    #[inline]
+    pub fn create_argb_cursor(
+        &mut self,
+        root: Window,
+        width: u16,
+        height: u16,
+        pixels: &[u32],
+        hotspot_x: u16,
+        hotspot_y: u16,
+    ) -> crate::Result<Cursor> {
+        check_cursor_pixels(width, height, pixels)?;
+        let format = self.argb32_format()?;
+
+        let QueryBestSizeReply {
+            width: max_width,
+            height: max_height,
+            ..
+        } = self.exchange_request(query_best_cursor_size_request(root, width, height))?;
+
+        let fitted = fit_cursor_image(
+            width, height, pixels, hotspot_x, hotspot_y, max_width, max_height,
+        );
+        warn_if_scaled(width, height, max_width, max_height, &fitted);
+        let image = cursor_image(&*self, &fitted)?;
+
+        // upload the image to a 32-bit pixmap, which is freed whether or not that works
+        let pixmap = self.create_pixmap(root, fitted.width, fitted.height, 32)?;
+        let cursor = self.create_cursor_from_pixmap(pixmap, format, &image, &fitted);
+        let freed = pixmap.free(self);
+        let cursor = cursor?;
+        freed?;
+
+        Ok(cursor)
+    }
+
+    #[inline]
+    fn create_cursor_from_pixmap(
+        &mut self,
+        pixmap: Pixmap,
+        format: Pictformat,
+        image: &Image<Vec<u8>>,
+        fitted: &FittedCursorImage,
+    ) -> crate::Result<Cursor> {
+        let gc = self.create_gc(pixmap, Default::default())?;
+        let uploaded = self.put_image(
+            pixmap,
+            gc,
+            image,
+            0,
+            0,
+            0,
+            0,
+            usize::from(fitted.width),
+            usize::from(fitted.height),
+        );
+        let freed = gc.free(self);
+        uploaded?;
+        freed?;
+
+        // turn it into a cursor
+        let picture = self.create_picture(pixmap, format, Default::default())?;
+        let cursor = generate_xid(self)
+            .map(Cursor::const_from_xid)
+            .and_then(|cursor| {
+                self.exchange_request(create_cursor_request(cursor, picture, fitted))
+                    .map(|()| cursor)
+            });
+        let freed = picture.free(self);
+        let cursor = cursor?;
+        freed?;
+
+        Ok(cursor)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<Dpy: AsyncDisplay + ?Sized> RenderDisplay<Dpy> {
+    /// Create a cursor from an ARGB image, using the `XRender` extension, async redox. See
+    /// `create_argb_cursor` for more information.
+    #[inline]
+    pub async fn create_argb_cursor_async(
+        &mut self,
+        root: Window,
+        width: u16,
+        height: u16,
+        pixels: &[u32],
+        hotspot_x: u16,
+        hotspot_y: u16,
+    ) -> crate::Result<Cursor> {
+        check_cursor_pixels(width, height, pixels)?;
+        let format = self.argb32_format()?;
+
+        let QueryBestSizeReply {
+            width: max_width,
+            height: max_height,
+            ..
+        } = self
+            .exchange_request_async(query_best_cursor_size_request(root, width, height))
+            .await?;
+
+        let fitted = fit_cursor_image(
+            width, height, pixels, hotspot_x, hotspot_y, max_width, max_height,
+        );
+        warn_if_scaled(width, height, max_width, max_height, &fitted);
+        let image = cursor_image(&*self, &fitted)?;
+
+        // upload the image to a 32-bit pixmap, which is freed whether or not that works
+        let pixmap = self
+            .create_pixmap_async(root, fitted.width, fitted.height, 32)
+            .await?;
+        let cursor = self
+            .create_cursor_from_pixmap_async(pixmap, format, &image, &fitted)
+            .await;
+        let freed = pixmap.free_async(self).await;
+        let cursor = cursor?;
+        freed?;
+
+        Ok(cursor)
+    }
+
+    #[inline]
+    async fn create_cursor_from_pixmap_async(
+        &mut self,
+        pixmap: Pixmap,
+        format: Pictformat,
+        image: &Image<Vec<u8>>,
+        fitted: &FittedCursorImage,
+    ) -> crate::Result<Cursor> {
+        let gc = self.create_gc_async(pixmap, Default::default()).await?;
+        let uploaded = self
+            .put_image_async(
+                pixmap,
+                gc,
+                image,
+                0,
+                0,
+                0,
+                0,
+                usize::from(fitted.width),
+                usize::from(fitted.height),
+            )
+            .await;
+        let freed = gc.free_async(self).await;
+        uploaded?;
+        freed?;
+
+        // turn it into a cursor
+        let picture = self
+            .create_picture_async(pixmap, format, Default::default())
+            .await?;
+        let cursor = match generate_xid(self).map(Cursor::const_from_xid) {
+            Ok(cursor) => self
+                .exchange_request_async(create_cursor_request(cursor, picture, fitted))
+                .await
+                .map(|()| cursor),
+            Err(e) => Err(e),
+        };
+        let freed = picture.free_async(self).await;
+        let cursor = cursor?;
+        freed?;
+
+        Ok(cursor)
+    }
+}
+
+impl<Dpy: ?Sized> RenderDisplay<Dpy> {
+    #[inline]
+    fn argb32_format(&self) -> crate::Result<Pictformat> {
+        self.find_standard_format(StandardFormat::Argb32)
+            .ok_or(BreadError::StaticMsg("Server has no ARGB32 picture format"))
+    }
+}
+
+#[inline]
+fn check_cursor_pixels(width: u16, height: u16, pixels: &[u32]) -> crate::Result {
+    if pixels.len() != usize::from(width) * usize::from(height) || pixels.is_empty() {
+        Err(BreadError::StaticMsg(
+            "Cursor image does not match its dimensions",
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+#[inline]
+fn query_best_cursor_size_request(root: Window, width: u16, height: u16) -> QueryBestSizeRequest {
+    QueryBestSizeRequest {
+        class: QueryShapeOf::LargestCursor,
+        drawable: root.into(),
+        width,
+        height,
+        ..Default::default()
+    }
+}
+
+#[inline]
+fn create_cursor_request(
+    cursor: Cursor,
+    picture: Picture,
+    fitted: &FittedCursorImage,
+) -> CreateCursorRequest {
+    CreateCursorRequest {
+        cid: cursor,
+        source: picture,
+        x: fitted.hotspot_x,
+        y: fitted.hotspot_y,
+        ..Default::default()
+    }
+}
+
+#[inline]
+fn warn_if_scaled(
+    width: u16,
+    height: u16,
+    max_width: u16,
+    max_height: u16,
+    fitted: &FittedCursorImage,
+) {
+    if fitted.width != width || fitted.height != height {
+        log::warn!(
+            "Cursor image of size {}x{} is larger than the largest cursor the server supports ({}x{}), scaling it down to {}x{}",
+            width,
+            height,
+            max_width,
+            max_height,
+            fitted.width,
+            fitted.height
+        );
+    }
+}
+
+/// Convert the fitted cursor image into a 32-bit `ZPixmap` image.
+#[inline]
+fn cursor_image<Dpy: DisplayBase + ?Sized>(
+    dpy: &Dpy,
+    fitted: &FittedCursorImage,
+) -> crate::Result<Image<Vec<u8>>> {
+    let mut image = Image::new(
+        dpy,
+        None,
+        32,
+        ImageFormat::ZPixmap,
+        0,
+        vec![0_u8; usize::from(fitted.width) * usize::from(fitted.height) * 4],
+        usize::from(fitted.width),
+        usize::from(fitted.height),
+        32,
+        None,
+    )
+    .ok_or(BreadError::StaticMsg("Failed to create cursor image"))?;
+    fitted
+        .pixels
+        .chunks(usize::from(fitted.width))
+        .enumerate()
+        .for_each(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .for_each(|(x, &pixel)| image.set_pixel(x, y, pixel));
+        });
+    Ok(image)
+}
+
+/// Scale a cursor image down so that it fits within `max_width` by `max_height`, keeping its aspect ratio.
+/// A maximum size of zero is taken to mean that the server has no limit.
+#[inline]
+fn fit_cursor_image(
+    width: u16,
+    height: u16,
+    pixels: &[u32],
+    hotspot_x: u16,
+    hotspot_y: u16,
+    max_width: u16,
+    max_height: u16,
+) -> FittedCursorImage {
+    let max_width = if max_width == 0 { width } else { max_width };
+    let max_height = if max_height == 0 { height } else { max_height };
+
+    if width <= max_width && height <= max_height {
+        return FittedCursorImage {
+            width,
+            height,
+            pixels: pixels.to_vec(),
+            hotspot_x,
+            hotspot_y,
+        };
+    }
+
+    // use whichever axis needs to shrink the most, so that both fit
+    let (w, h) = (u32::from(width), u32::from(height));
+    let (new_w, new_h) = if u32::from(max_width) * h <= u32::from(max_height) * w {
+        let new_w = u32::from(max_width);
+        (new_w, (h * new_w / w).max(1))
+    } else {
+        let new_h = u32::from(max_height);
+        ((w * new_h / h).max(1), new_h)
+    };
+
+    FittedCursorImage {
+        width: new_w as u16,
+        height: new_h as u16,
+        pixels: box_filter(pixels, w, h, new_w, new_h),
+        hotspot_x: ((u32::from(hotspot_x) * new_w / w).min(new_w - 1)) as u16,
+        hotspot_y: ((u32::from(hotspot_y) * new_h / h).min(new_h - 1)) as u16,
+    }
+}
+
+/// Downscale an ARGB image by averaging every source pixel that falls within each destination pixel. Since
+/// the pixels use premultiplied alpha, each channel can be averaged on its own.
+#[inline]
+fn box_filter(pixels: &[u32], w: u32, h: u32, new_w: u32, new_h: u32) -> Vec<u32> {
+    (0..new_h)
+        .flat_map(|y| (0..new_w).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let (x0, x1) = (x * w / new_w, ((x + 1) * w / new_w).max(x * w / new_w + 1));
+            let (y0, y1) = (y * h / new_h, ((y + 1) * h / new_h).max(y * h / new_h + 1));
+
+            let mut sums = [0_u32; 4];
+            (y0..y1)
+                .flat_map(|sy| (x0..x1).map(move |sx| (sy * w + sx) as usize))
+                .for_each(|index| {
+                    let pixel = pixels[index];
+                    sums.iter_mut()
+                        .enumerate()
+                        .for_each(|(channel, sum)| *sum += (pixel >> (channel * 8)) & 0xFF);
+                });
+
+            let count = (x1 - x0) * (y1 - y0);
+            sums.iter().enumerate().fold(0, |pixel, (channel, sum)| {
+                pixel | ((sum / count) << (channel * 8))
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn fit_cursor_image_leaves_small_images_alone() {
+    let pixels = [0xFF00_00FF; 4];
+    let fitted = fit_cursor_image(2, 2, &pixels, 1, 1, 64, 64);
+    assert_eq!(fitted.width, 2);
+    assert_eq!(fitted.height, 2);
+    assert_eq!(fitted.pixels, pixels);
+}
+
+#[test]
+fn fit_cursor_image_box_filters_large_images() {
+    // a 4x2 image whose left half is opaque white and right half is transparent
+    let pixels = [
+        0xFFFF_FFFF,
+        0xFFFF_FFFF,
+        0,
+        0, //
+        0xFFFF_FFFF,
+        0xFFFF_FFFF,
+        0,
+        0,
+    ];
+    let fitted = fit_cursor_image(4, 2, &pixels, 3, 1, 2, 2);
+    assert_eq!((fitted.width, fitted.height), (2, 1));
+    assert_eq!(fitted.pixels, [0xFFFF_FFFF, 0]);
+    assert_eq!((fitted.hotspot_x, fitted.hotspot_y), (1, 0));
+
+    let halves = [0xFFFF_FFFF, 0, 0xFFFF_FFFF, 0];
+    let fitted = fit_cursor_image(2, 2, &halves, 0, 0, 1, 1);
+    assert_eq!(fitted.pixels, [0x7F7F_7F7F]);
+}
@@ -16,6 +16,7 @@ use crate::{
         EXT_KEY_SIZE,
     },
     event::Event,
+    extension::Extension,
     BreadError, XID,
 };
 use alloc::{borrow::Cow, boxed::Box};
@@ -328,6 +329,16 @@ impl<Dpy: DisplayBase + ?Sized> DisplayBase for RenderDisplay<Dpy> {
         self.inner.set_checked(checked);
     }
 
+    #[inline]
+    fn motion_compression(&self) -> bool {
+        self.inner.motion_compression()
+    }
+
+    #[inline]
+    fn set_motion_compression(&mut self, compress: bool) {
+        self.inner.set_motion_compression(compress);
+    }
+
     #[inline]
     fn bigreq_enabled(&self) -> bool {
         self.inner.bigreq_enabled()
@@ -337,6 +348,16 @@ impl<Dpy: DisplayBase + ?Sized> DisplayBase for RenderDisplay<Dpy> {
         self.inner.max_request_len()
     }
 
+    #[inline]
+    fn get_extension(&mut self, key: &[u8; EXT_KEY_SIZE]) -> Option<Extension> {
+        self.inner.get_extension(key)
+    }
+
+    #[inline]
+    fn set_extension(&mut self, key: [u8; EXT_KEY_SIZE], extension: Extension) {
+        self.inner.set_extension(key, extension);
+    }
+
     #[inline]
     fn get_extension_opcode(&mut self, key: &[u8; EXT_KEY_SIZE]) -> Option<u8> {
         self.inner.get_extension_opcode(key)
@@ -347,6 +368,21 @@ impl<Dpy: DisplayBase + ?Sized> DisplayBase for RenderDisplay<Dpy> {
         self.inner.set_extension_opcode(key, opcode);
     }
 
+    #[inline]
+    fn extension_absent(&mut self, key: &[u8; EXT_KEY_SIZE]) -> bool {
+        self.inner.extension_absent(key)
+    }
+
+    #[inline]
+    fn set_extension_absent(&mut self, key: [u8; EXT_KEY_SIZE]) {
+        self.inner.set_extension_absent(key);
+    }
+
+    #[inline]
+    fn extension_name(&mut self, major_opcode: u8) -> Option<[u8; EXT_KEY_SIZE]> {
+        self.inner.extension_name(major_opcode)
+    }
+
     #[inline]
     fn wm_protocols_atom(&self) -> Option<NonZeroU32> {
         self.inner.wm_protocols_atom()
@@ -356,6 +392,16 @@ impl<Dpy: DisplayBase + ?Sized> DisplayBase for RenderDisplay<Dpy> {
     fn set_wm_protocols_atom(&mut self, a: NonZeroU32) {
         self.inner.set_wm_protocols_atom(a)
     }
+
+    #[inline]
+    fn net_wm_ping_atom(&self) -> Option<NonZeroU32> {
+        self.inner.net_wm_ping_atom()
+    }
+
+    #[inline]
+    fn set_net_wm_ping_atom(&mut self, a: Option<NonZeroU32>) {
+        self.inner.set_net_wm_ping_atom(a)
+    }
 }
 
 impl<'a, Dpy: DisplayBase + ?Sized> DisplayBase for &'a RenderDisplay<Dpy>
@@ -442,6 +488,16 @@ where
         self.inner().set_checked(checked);
     }
 
+    #[inline]
+    fn motion_compression(&self) -> bool {
+        self.inner().motion_compression()
+    }
+
+    #[inline]
+    fn set_motion_compression(&mut self, compress: bool) {
+        self.inner().set_motion_compression(compress);
+    }
+
     #[inline]
     fn bigreq_enabled(&self) -> bool {
         self.inner.bigreq_enabled()
@@ -451,6 +507,16 @@ where
         self.inner.max_request_len()
     }
 
+    #[inline]
+    fn get_extension(&mut self, key: &[u8; EXT_KEY_SIZE]) -> Option<Extension> {
+        self.inner().get_extension(key)
+    }
+
+    #[inline]
+    fn set_extension(&mut self, key: [u8; EXT_KEY_SIZE], extension: Extension) {
+        self.inner().set_extension(key, extension);
+    }
+
     #[inline]
     fn get_extension_opcode(&mut self, key: &[u8; EXT_KEY_SIZE]) -> Option<u8> {
         self.inner().get_extension_opcode(key)
@@ -461,6 +527,21 @@ where
         self.inner().set_extension_opcode(key, opcode);
     }
 
+    #[inline]
+    fn extension_absent(&mut self, key: &[u8; EXT_KEY_SIZE]) -> bool {
+        self.inner().extension_absent(key)
+    }
+
+    #[inline]
+    fn set_extension_absent(&mut self, key: [u8; EXT_KEY_SIZE]) {
+        self.inner().set_extension_absent(key);
+    }
+
+    #[inline]
+    fn extension_name(&mut self, major_opcode: u8) -> Option<[u8; EXT_KEY_SIZE]> {
+        self.inner().extension_name(major_opcode)
+    }
+
     #[inline]
     fn wm_protocols_atom(&self) -> Option<NonZeroU32> {
         self.inner().wm_protocols_atom()
@@ -470,6 +551,16 @@ where
     fn set_wm_protocols_atom(&mut self, a: NonZeroU32) {
         self.inner().set_wm_protocols_atom(a)
     }
+
+    #[inline]
+    fn net_wm_ping_atom(&self) -> Option<NonZeroU32> {
+        self.inner().net_wm_ping_atom()
+    }
+
+    #[inline]
+    fn set_net_wm_ping_atom(&mut self, a: Option<NonZeroU32>) {
+        self.inner().set_net_wm_ping_atom(a)
+    }
 }
 
 impl<Dpy: Display + ?Sized> Display for RenderDisplay<Dpy> {
@@ -482,6 +573,11 @@ impl<Dpy: Display + ?Sized> Display for RenderDisplay<Dpy> {
     fn send_request_raw(&mut self, request: RequestInfo) -> crate::Result<u16> {
         self.inner.send_request_raw(request)
     }
+
+    #[inline]
+    fn take_bytes_written(&mut self) -> usize {
+        self.inner.take_bytes_written()
+    }
 }
 
 impl<'a, Dpy: DisplayBase + ?Sized> Display for &'a RenderDisplay<Dpy>
@@ -497,6 +593,11 @@ where
     fn send_request_raw(&mut self, request: RequestInfo) -> crate::Result<u16> {
         self.inner().send_request_raw(request)
     }
+
+    #[inline]
+    fn take_bytes_written(&mut self) -> usize {
+        self.inner().take_bytes_written()
+    }
 }
 
 #[cfg(feature = "async")]
@@ -2,6 +2,7 @@
 
 //! First-class support for the XRender extension.
 
+mod cursor;
 mod display;
 mod picture;
 mod tesselate;
@@ -0,0 +1,64 @@
+// MIT/Apache2 License
+
+//! This module provides the `Time` enum, a type-safe representation of the timestamps taken by many
+//! requests.
+
+use crate::auto::xproto::Timestamp;
+
+/// A timestamp, as used by requests that deal with grabs, input focus and selections. On the wire,
+/// this is a 32-bit integer where zero is reserved for the `CurrentTime` sentinel, which the server
+/// replaces with its current time.
+///
+/// The ICCCM warns against using `CurrentTime` for anything that may race with other clients (e.g.
+/// taking ownership of a selection). When possible, use the timestamp of the event that triggered
+/// the action instead.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Time {
+    /// The server's current time, or `CurrentTime`.
+    Current,
+    /// A specific server timestamp, usually taken from an event.
+    Server(Timestamp),
+}
+
+impl Time {
+    /// Convert this time into the value that will be sent over the wire.
+    #[inline]
+    #[must_use]
+    pub fn as_timestamp(self) -> Timestamp {
+        match self {
+            Self::Current => 0,
+            Self::Server(ts) => ts,
+        }
+    }
+
+    /// Whether or not this is the `CurrentTime` sentinel.
+    #[inline]
+    #[must_use]
+    pub fn is_current(self) -> bool {
+        matches!(self.as_timestamp(), 0)
+    }
+}
+
+impl Default for Time {
+    #[inline]
+    fn default() -> Self {
+        Self::Current
+    }
+}
+
+impl From<Timestamp> for Time {
+    #[inline]
+    fn from(ts: Timestamp) -> Self {
+        match ts {
+            0 => Self::Current,
+            ts => Self::Server(ts),
+        }
+    }
+}
+
+impl From<Time> for Timestamp {
+    #[inline]
+    fn from(time: Time) -> Self {
+        time.as_timestamp()
+    }
+}
@@ -0,0 +1,1183 @@
+// MIT/Apache2 License
+
+//! This module implements the XDND protocol, which is used to drag and drop files, text and other data between
+//! programs. GTK, Qt and most other toolkits speak it.
+//!
+//! XDND works entirely through client messages and selections. The window being dragged from (the source)
+//! sends `XdndEnter`, `XdndPosition` and `XdndLeave` messages to the window under the pointer, which has to
+//! advertise that it understands the protocol via its `XdndAware` property. The window being dragged over
+//! (the target) answers every `XdndPosition` with an `XdndStatus` message, telling the source whether it
+//! would accept a drop. Once the user releases the button, the source sends `XdndDrop`, the target converts
+//! the `XdndSelection` selection to get the data, and then it tells the source that it is done with an
+//! `XdndFinished` message.
+//!
+//! `DragSource` and `DropTarget` keep track of this exchange. Every event the program receives should be
+//! passed to their `handle_event` methods, which send the replies the protocol requires and report anything
+//! interesting back. Note that the data is transferred in a single property, so if the data is larger than
+//! the maximum request length, the source tells the target that it could not be converted. `XdndProxy`
+//! windows are not supported either.
+
+use crate::{
+    auto::xproto::{
+        Atom, ClientMessageEvent, EventMask, InternAtomRequest, PropMode, SelectionNotifyEvent,
+        SelectionRequestEvent, TranslateCoordinatesRequest, Window, ATOM_ANY, ATOM_ATOM, ATOM_NONE,
+    },
+    client_message_data::ClientMessageData,
+    display::{prelude::*, Display, RequestCookie},
+    prelude::{PropertyFormat, PropertyType},
+    xid::XidType,
+    BreadError, Event, Time,
+};
+use alloc::vec::Vec;
+use core::cmp;
+
+/// The newest version of the XDND protocol that this module speaks.
+pub const XDND_VERSION: u8 = 5;
+
+/// The oldest version of the XDND protocol that this module speaks. Windows that only support older versions
+/// are treated as if they did not support XDND at all.
+pub const XDND_MIN_VERSION: u8 = 3;
+
+/// The size of the fixed part of a `ChangeProperty` request.
+const CHANGE_PROPERTY_HEADER_SIZE: usize = 24;
+
+/// The number of types an `XdndEnter` message has room for. If the source offers more, the target has to read
+/// them from the `XdndTypeList` property.
+const ENTER_TYPES: usize = 3;
+
+const ATOM_NAMES: [&str; 14] = [
+    "XdndAware",
+    "XdndEnter",
+    "XdndPosition",
+    "XdndStatus",
+    "XdndLeave",
+    "XdndDrop",
+    "XdndFinished",
+    "XdndSelection",
+    "XdndTypeList",
+    "XdndActionCopy",
+    "XdndActionMove",
+    "XdndActionLink",
+    "XdndActionAsk",
+    "XdndActionPrivate",
+];
+
+/// The atoms used by the XDND protocol. These are needed to encode and decode its messages.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct XdndAtoms {
+    pub aware: Atom,
+    pub enter: Atom,
+    pub position: Atom,
+    pub status: Atom,
+    pub leave: Atom,
+    pub drop: Atom,
+    pub finished: Atom,
+    pub selection: Atom,
+    pub type_list: Atom,
+    pub action_copy: Atom,
+    pub action_move: Atom,
+    pub action_link: Atom,
+    pub action_ask: Atom,
+    pub action_private: Atom,
+}
+
+impl XdndAtoms {
+    /// Intern every atom the XDND protocol uses. All of the requests are sent before any of them are
+    /// resolved, so this only takes a single round trip.
+    #[inline]
+    pub fn intern<Dpy: Display + ?Sized>(dpy: &mut Dpy) -> crate::Result<Self> {
+        let cookies = ATOM_NAMES
+            .iter()
+            .map(|&name| dpy.intern_atom(name, false))
+            .collect::<crate::Result<Vec<RequestCookie<InternAtomRequest<'_>>>>>()?;
+
+        let mut atoms = [ATOM_NONE; 14];
+        atoms
+            .iter_mut()
+            .zip(cookies)
+            .try_for_each(|(atom, cookie)| {
+                *atom = dpy.resolve_request(cookie)?.atom;
+                crate::Result::Ok(())
+            })?;
+        Ok(Self::from_array(atoms))
+    }
+
+    #[inline]
+    fn from_array(atoms: [Atom; 14]) -> Self {
+        let [aware, enter, position, status, leave, drop, finished, selection, type_list, action_copy, action_move, action_link, action_ask, action_private] =
+            atoms;
+        Self {
+            aware,
+            enter,
+            position,
+            status,
+            leave,
+            drop,
+            finished,
+            selection,
+            type_list,
+            action_copy,
+            action_move,
+            action_link,
+            action_ask,
+            action_private,
+        }
+    }
+
+    /// Tell whether an atom is one of the actions defined by the protocol.
+    #[inline]
+    #[must_use]
+    pub fn is_action(&self, atom: Atom) -> bool {
+        [
+            self.action_copy,
+            self.action_move,
+            self.action_link,
+            self.action_ask,
+            self.action_private,
+        ]
+        .contains(&atom)
+    }
+}
+
+/// A message of the XDND protocol. Positions are relative to the root window.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum XdndMessage {
+    /// Sent by the source when the pointer enters the target. `version` is the version of the protocol that
+    /// both sides will use. Only the first three types fit into the message; if the source offers more,
+    /// `more_types` is set and the full list is in the source window's `XdndTypeList` property.
+    Enter {
+        source: Window,
+        version: u8,
+        more_types: bool,
+        types: [Atom; ENTER_TYPES],
+    },
+    /// Sent by the source when the pointer moves within the target, along with the action the user wants.
+    Position {
+        source: Window,
+        root_x: i16,
+        root_y: i16,
+        time: Time,
+        action: Atom,
+    },
+    /// Sent by the target in reply to every `Position`. If `want_position` is not set, the source does not
+    /// need to send any more `Position` messages while the pointer stays within the given rectangle.
+    Status {
+        target: Window,
+        accept: bool,
+        want_position: bool,
+        x: i16,
+        y: i16,
+        width: u16,
+        height: u16,
+        action: Atom,
+    },
+    /// Sent by the source when the pointer leaves the target, or the drag is cancelled.
+    Leave { source: Window },
+    /// Sent by the source when the user drops the data on the target.
+    Drop { source: Window, time: Time },
+    /// Sent by the target once it has received the dropped data, or given up on it.
+    Finished {
+        target: Window,
+        accepted: bool,
+        action: Atom,
+    },
+}
+
+#[inline]
+fn pack_pair(high: u16, low: u16) -> u32 {
+    (u32::from(high) << 16) | u32::from(low)
+}
+
+/// Pack a pair of coordinates, keeping their two's complement representation.
+#[allow(clippy::cast_sign_loss)]
+#[inline]
+fn pack_coordinates(x: i16, y: i16) -> u32 {
+    pack_pair(x as u16, y as u16)
+}
+
+#[inline]
+fn unpack_pair(value: u32) -> (u16, u16) {
+    ((value >> 16) as u16, value as u16)
+}
+
+impl XdndMessage {
+    /// Decode an XDND message from a client message. Returns `None` if the client message is not part of
+    /// the XDND protocol.
+    #[must_use]
+    pub fn from_client_message(atoms: &XdndAtoms, cme: &ClientMessageEvent) -> Option<Self> {
+        if cme.format != 32 {
+            return None;
+        }
+
+        let l = cme.data.longs();
+        let window = Window::const_from_xid(l[0]);
+        let ty = cme.ty;
+
+        Some(if ty == atoms.enter {
+            Self::Enter {
+                source: window,
+                version: (l[1] >> 24) as u8,
+                more_types: l[1] & 1 != 0,
+                types: [
+                    Atom::const_from_xid(l[2]),
+                    Atom::const_from_xid(l[3]),
+                    Atom::const_from_xid(l[4]),
+                ],
+            }
+        } else if ty == atoms.position {
+            let (root_x, root_y) = unpack_pair(l[2]);
+            Self::Position {
+                source: window,
+                root_x: root_x as i16,
+                root_y: root_y as i16,
+                time: l[3].into(),
+                action: Atom::const_from_xid(l[4]),
+            }
+        } else if ty == atoms.status {
+            let (x, y) = unpack_pair(l[2]);
+            let (width, height) = unpack_pair(l[3]);
+            Self::Status {
+                target: window,
+                accept: l[1] & 1 != 0,
+                want_position: l[1] & 2 != 0,
+                x: x as i16,
+                y: y as i16,
+                width,
+                height,
+                action: Atom::const_from_xid(l[4]),
+            }
+        } else if ty == atoms.leave {
+            Self::Leave { source: window }
+        } else if ty == atoms.drop {
+            Self::Drop {
+                source: window,
+                time: l[2].into(),
+            }
+        } else if ty == atoms.finished {
+            Self::Finished {
+                target: window,
+                accepted: l[1] & 1 != 0,
+                action: Atom::const_from_xid(l[2]),
+            }
+        } else {
+            return None;
+        })
+    }
+
+    /// Encode this message as a client message that is sent to `destination`.
+    #[must_use]
+    pub fn to_client_message(&self, atoms: &XdndAtoms, destination: Window) -> ClientMessageEvent {
+        let (ty, longs) = match *self {
+            Self::Enter {
+                source,
+                version,
+                more_types,
+                types,
+            } => (
+                atoms.enter,
+                [
+                    source.xid(),
+                    (u32::from(version) << 24) | u32::from(more_types),
+                    types[0].xid(),
+                    types[1].xid(),
+                    types[2].xid(),
+                ],
+            ),
+            Self::Position {
+                source,
+                root_x,
+                root_y,
+                time,
+                action,
+            } => (
+                atoms.position,
+                [
+                    source.xid(),
+                    0,
+                    pack_coordinates(root_x, root_y),
+                    time.as_timestamp(),
+                    action.xid(),
+                ],
+            ),
+            Self::Status {
+                target,
+                accept,
+                want_position,
+                x,
+                y,
+                width,
+                height,
+                action,
+            } => (
+                atoms.status,
+                [
+                    target.xid(),
+                    u32::from(accept) | (u32::from(want_position) << 1),
+                    pack_coordinates(x, y),
+                    pack_pair(width, height),
+                    action.xid(),
+                ],
+            ),
+            Self::Leave { source } => (atoms.leave, [source.xid(), 0, 0, 0, 0]),
+            Self::Drop { source, time } => {
+                (atoms.drop, [source.xid(), 0, time.as_timestamp(), 0, 0])
+            }
+            Self::Finished {
+                target,
+                accepted,
+                action,
+            } => (
+                atoms.finished,
+                [target.xid(), u32::from(accepted), action.xid(), 0, 0],
+            ),
+        };
+
+        let mut data = ClientMessageData::default();
+        data.longs_mut().copy_from_slice(&longs);
+        ClientMessageEvent {
+            format: 32,
+            window: destination,
+            ty,
+            data,
+            ..Default::default()
+        }
+    }
+
+    /// Send this message to `destination`.
+    #[inline]
+    pub fn send<Dpy: Display + ?Sized>(
+        &self,
+        dpy: &mut Dpy,
+        atoms: &XdndAtoms,
+        destination: Window,
+    ) -> crate::Result {
+        dpy.send_event(
+            false,
+            destination,
+            EventMask::default(),
+            Event::ClientMessage(self.to_client_message(atoms, destination)),
+        )
+    }
+}
+
+impl Window {
+    /// Advertise that this window accepts drops using the XDND protocol, by setting its `XdndAware`
+    /// property. This should be set on the top-level window.
+    #[inline]
+    pub fn set_xdnd_aware<Dpy: Display + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+        atoms: &XdndAtoms,
+    ) -> crate::Result {
+        self.change_property(
+            dpy,
+            atoms.aware,
+            PropertyType::Atom,
+            PropertyFormat::ThirtyTwo,
+            PropMode::Replace,
+            &[u32::from(XDND_VERSION)],
+        )
+    }
+
+    /// Get the version of the XDND protocol this window supports, or `None` if it does not accept drops.
+    #[inline]
+    pub fn xdnd_aware_version<Dpy: Display + ?Sized>(
+        self,
+        dpy: &mut Dpy,
+        atoms: &XdndAtoms,
+    ) -> crate::Result<Option<u8>> {
+        match self.get_property_u32(dpy, atoms.aware, ATOM_ATOM) {
+            Ok(version) => Ok(version
+                .and_then(|version| version.first().copied())
+                .map(|version| cmp::min(version, u32::from(u8::MAX)) as u8)),
+            // a property that isn't a list of atoms doesn't count
+            Err(BreadError::BadObjectRead(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Find the XDND aware window under the given point, along with the version of the protocol it supports.
+#[inline]
+fn find_aware_window<Dpy: Display + ?Sized>(
+    dpy: &mut Dpy,
+    atoms: &XdndAtoms,
+    root: Window,
+    root_x: i16,
+    root_y: i16,
+) -> crate::Result<Option<(Window, u8)>> {
+    // window managers reparent top-level windows into frames, so keep descending until we find a window
+    // with the property
+    let mut window = root;
+    loop {
+        let child = dpy
+            .exchange_request(TranslateCoordinatesRequest {
+                src_window: root,
+                dst_window: window,
+                src_x: root_x,
+                src_y: root_y,
+                ..Default::default()
+            })?
+            .child;
+        if child.xid() == 0 {
+            return Ok(None);
+        }
+
+        match child.xdnd_aware_version(dpy, atoms)? {
+            Some(version) if version >= XDND_MIN_VERSION => return Ok(Some((child, version))),
+            Some(version) => {
+                log::debug!("Window {:?} only supports XDND version {}", child, version);
+                return Ok(None);
+            }
+            None => window = child,
+        }
+    }
+}
+
+/// Something that happened during a drag, as reported by `DragSource`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DragEvent {
+    /// The pointer moved onto a different window. This is `None` if the window under the pointer does not
+    /// accept drops.
+    TargetChanged(Option<Window>),
+    /// The target told us whether it would accept a drop, and which action it would perform.
+    Status { accept: bool, action: Atom },
+    /// The target is done with the drop. If `accepted` is false, the drop failed and the data should not be
+    /// deleted, even if the action was `XdndActionMove`.
+    Finished { accepted: bool, action: Atom },
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct CurrentTarget {
+    window: Window,
+    version: u8,
+    accept: bool,
+    action: Atom,
+    awaiting_status: bool,
+}
+
+/// The source side of a drag. This owns the `XdndSelection` selection while the drag is in progress, and
+/// hands out the data when the target asks for it.
+///
+/// Call `motion` whenever the pointer moves (usually while a pointer grab is active), `release` when the
+/// button is released, and pass every event to `handle_event` until it reports `DragEvent::Finished`.
+#[derive(Debug, Clone)]
+pub struct DragSource {
+    window: Window,
+    atoms: XdndAtoms,
+    data: Vec<(Atom, Vec<u8>)>,
+    action: Atom,
+    target: Option<CurrentTarget>,
+    pending_position: Option<(i16, i16, Time)>,
+    dropped: bool,
+    time: Time,
+}
+
+impl DragSource {
+    /// Start dragging from `window`. `data` lists each type the data can be converted to (e.g. a
+    /// `text/uri-list` atom) along with the data itself, in order of preference. `action` is the action the
+    /// user asked for, usually `XdndActionCopy`.
+    ///
+    /// `time` should be the timestamp of the event that started the drag, since it is used to take
+    /// ownership of the `XdndSelection` selection.
+    #[inline]
+    pub fn start<Dpy: Display + ?Sized>(
+        dpy: &mut Dpy,
+        window: Window,
+        atoms: XdndAtoms,
+        data: Vec<(Atom, Vec<u8>)>,
+        action: Atom,
+        time: Time,
+    ) -> crate::Result<Self> {
+        window.set_selection_owner(dpy, atoms.selection, time)?;
+
+        if data.len() > ENTER_TYPES {
+            let types: Vec<u32> = data.iter().map(|(ty, _)| ty.xid()).collect();
+            window.change_property(
+                dpy,
+                atoms.type_list,
+                PropertyType::Atom,
+                PropertyFormat::ThirtyTwo,
+                PropMode::Replace,
+                &types,
+            )?;
+        }
+
+        Ok(Self {
+            window,
+            atoms,
+            data,
+            action,
+            target: None,
+            pending_position: None,
+            dropped: false,
+            time,
+        })
+    }
+
+    /// The window that the pointer is currently over, if it accepts drops.
+    #[inline]
+    #[must_use]
+    pub fn target(&self) -> Option<Window> {
+        self.target.map(|target| target.window)
+    }
+
+    #[inline]
+    fn enter_message(&self, version: u8) -> XdndMessage {
+        let mut types = [ATOM_NONE; ENTER_TYPES];
+        types
+            .iter_mut()
+            .zip(self.data.iter())
+            .for_each(|(slot, (ty, _))| *slot = *ty);
+
+        XdndMessage::Enter {
+            source: self.window,
+            version,
+            more_types: self.data.len() > ENTER_TYPES,
+            types,
+        }
+    }
+
+    #[inline]
+    fn send_position<Dpy: Display + ?Sized>(
+        &mut self,
+        dpy: &mut Dpy,
+        root_x: i16,
+        root_y: i16,
+        time: Time,
+    ) -> crate::Result {
+        if let Some(target) = self.target.as_mut() {
+            // the target has to answer every position before we send the next one
+            if target.awaiting_status {
+                self.pending_position = Some((root_x, root_y, time));
+                return Ok(());
+            }
+
+            target.awaiting_status = true;
+            let window = target.window;
+            XdndMessage::Position {
+                source: self.window,
+                root_x,
+                root_y,
+                time,
+                action: self.action,
+            }
+            .send(dpy, &self.atoms, window)?;
+        }
+
+        Ok(())
+    }
+
+    /// Tell the source that the pointer moved to the given position, relative to `root`. This finds the
+    /// window under the pointer and sends it the appropriate messages.
+    #[inline]
+    pub fn motion<Dpy: Display + ?Sized>(
+        &mut self,
+        dpy: &mut Dpy,
+        root: Window,
+        root_x: i16,
+        root_y: i16,
+        time: Time,
+    ) -> crate::Result<Option<DragEvent>> {
+        if self.dropped {
+            return Ok(None);
+        }
+
+        let found = find_aware_window(dpy, &self.atoms, root, root_x, root_y)?;
+        let mut event = None;
+
+        if found.map(|(window, _)| window) != self.target() {
+            if let Some(old) = self.target.take() {
+                XdndMessage::Leave {
+                    source: self.window,
+                }
+                .send(dpy, &self.atoms, old.window)?;
+            }
+
+            self.pending_position = None;
+            if let Some((window, version)) = found {
+                let version = cmp::min(version, XDND_VERSION);
+                self.enter_message(version).send(dpy, &self.atoms, window)?;
+                self.target = Some(CurrentTarget {
+                    window,
+                    version,
+                    accept: false,
+                    action: ATOM_NONE,
+                    awaiting_status: false,
+                });
+            }
+
+            event = Some(DragEvent::TargetChanged(self.target()));
+        }
+
+        self.send_position(dpy, root_x, root_y, time)?;
+        Ok(event)
+    }
+
+    /// Tell the source that the user released the button. If the target accepted the drop, this sends it
+    /// `XdndDrop` and returns `true`; the drag then continues until `handle_event` reports that it has
+    /// finished. Otherwise, the drag is cancelled and this returns `false`.
+    ///
+    /// Once the drag is over, either here or once the target finishes, the source gives up `XdndSelection`
+    /// and deletes its `XdndTypeList` property.
+    ///
+    /// This decides based on the last `XdndStatus` the target sent, even if it has not yet answered the
+    /// most recent position.
+    #[inline]
+    pub fn release<Dpy: Display + ?Sized>(
+        &mut self,
+        dpy: &mut Dpy,
+        time: Time,
+    ) -> crate::Result<bool> {
+        match self.target {
+            Some(target) if target.accept => {
+                XdndMessage::Drop {
+                    source: self.window,
+                    time,
+                }
+                .send(dpy, &self.atoms, target.window)?;
+                self.dropped = true;
+                Ok(true)
+            }
+            Some(target) => {
+                XdndMessage::Leave {
+                    source: self.window,
+                }
+                .send(dpy, &self.atoms, target.window)?;
+                self.target = None;
+                self.end(dpy)?;
+                Ok(false)
+            }
+            None => {
+                self.end(dpy)?;
+                Ok(false)
+            }
+        }
+    }
+
+    /// Process an event. This handles the target's replies and its requests for the data.
+    #[inline]
+    pub fn handle_event<Dpy: Display + ?Sized>(
+        &mut self,
+        dpy: &mut Dpy,
+        event: &Event,
+    ) -> crate::Result<Option<DragEvent>> {
+        match event {
+            Event::ClientMessage(cme) if cme.window == self.window => {
+                match XdndMessage::from_client_message(&self.atoms, cme) {
+                    Some(XdndMessage::Status {
+                        target,
+                        accept,
+                        action,
+                        ..
+                    }) => self.handle_status(dpy, target, accept, action),
+                    Some(XdndMessage::Finished {
+                        target,
+                        accepted,
+                        action,
+                    }) => self.handle_finished(dpy, target, accepted, action),
+                    _ => Ok(None),
+                }
+            }
+            Event::SelectionRequest(sre)
+                if sre.owner == self.window && sre.selection == self.atoms.selection =>
+            {
+                self.send_data(dpy, sre)?;
+                Ok(None)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    #[inline]
+    fn handle_status<Dpy: Display + ?Sized>(
+        &mut self,
+        dpy: &mut Dpy,
+        window: Window,
+        accept: bool,
+        action: Atom,
+    ) -> crate::Result<Option<DragEvent>> {
+        let target = match self.target.as_mut() {
+            Some(target) if target.window == window => target,
+            _ => return Ok(None),
+        };
+
+        target.accept = accept;
+        target.action = if accept { action } else { ATOM_NONE };
+        target.awaiting_status = false;
+        let event = DragEvent::Status {
+            accept,
+            action: target.action,
+        };
+
+        if let Some((root_x, root_y, time)) = self.pending_position.take() {
+            self.send_position(dpy, root_x, root_y, time)?;
+        }
+        Ok(Some(event))
+    }
+
+    #[inline]
+    fn handle_finished<Dpy: Display + ?Sized>(
+        &mut self,
+        dpy: &mut Dpy,
+        window: Window,
+        accepted: bool,
+        action: Atom,
+    ) -> crate::Result<Option<DragEvent>> {
+        let target = match self.target {
+            Some(target) if self.dropped && target.window == window => target,
+            _ => return Ok(None),
+        };
+
+        self.target = None;
+        self.dropped = false;
+        self.end(dpy)?;
+
+        // before version 5, the target couldn't tell us whether the drop worked
+        Ok(Some(if target.version >= 5 {
+            DragEvent::Finished { accepted, action }
+        } else {
+            DragEvent::Finished {
+                accepted: true,
+                action: target.action,
+            }
+        }))
+    }
+
+    /// Clean up after the drag is over, by removing the type list and giving up `XdndSelection`.
+    #[inline]
+    fn end<Dpy: Display + ?Sized>(&mut self, dpy: &mut Dpy) -> crate::Result {
+        if self.data.len() > ENTER_TYPES {
+            self.window.delete_property(dpy, self.atoms.type_list)?;
+        }
+
+        // use the time we took ownership at, so that we don't take the selection away from anyone who
+        // has taken it since then
+        Window::const_from_xid(0).set_selection_owner(dpy, self.atoms.selection, self.time)
+    }
+
+    /// Answer a request to convert `XdndSelection`, by writing the data to the requestor's property.
+    #[inline]
+    fn send_data<Dpy: Display + ?Sized>(
+        &self,
+        dpy: &mut Dpy,
+        sre: &SelectionRequestEvent,
+    ) -> crate::Result {
+        // obsolete clients leave the property empty, and expect the target to be used instead
+        let property = if sre.property == ATOM_NONE {
+            sre.target
+        } else {
+            sre.property
+        };
+
+        let max_len = dpy
+            .effective_max_request_bytes()
+            .saturating_sub(CHANGE_PROPERTY_HEADER_SIZE);
+        let property = match self.data.iter().find(|(ty, _)| *ty == sre.target) {
+            Some((_, data)) if data.len() > max_len => {
+                log::warn!(
+                    "Unable to send {} bytes of drag data in a single request",
+                    data.len()
+                );
+                ATOM_NONE
+            }
+            Some((ty, data)) => {
+                sre.requestor.change_property_with_type(
+                    dpy,
+                    property,
+                    *ty,
+                    PropertyFormat::Eight,
+                    PropMode::Replace,
+                    data,
+                )?;
+                property
+            }
+            None => ATOM_NONE,
+        };
+
+        dpy.send_event(
+            false,
+            sre.requestor,
+            EventMask::default(),
+            Event::SelectionNotify(SelectionNotifyEvent {
+                time: sre.time,
+                requestor: sre.requestor,
+                selection: sre.selection,
+                target: sre.target,
+                property,
+                ..Default::default()
+            }),
+        )
+    }
+}
+
+/// Something that happened to a `DropTarget`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DropEvent {
+    /// A drag entered the window. The types it offers are available via `DropTarget::offered_types`.
+    Enter,
+    /// The pointer moved to the given position, relative to the root window. `accepted` tells whether the
+    /// source offers one of the types the target accepts.
+    Position {
+        root_x: i16,
+        root_y: i16,
+        accepted: bool,
+    },
+    /// The drag left the window, or it was dropped but the data could not be transferred.
+    Leave,
+    /// The data was dropped on the window, and has been transferred.
+    Drop {
+        ty: Atom,
+        action: Atom,
+        data: Vec<u8>,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct IncomingDrag {
+    source: Window,
+    version: u8,
+    types: Vec<Atom>,
+    chosen_type: Option<Atom>,
+    action: Atom,
+    awaiting_data: bool,
+}
+
+/// The target side of a drag. This tells sources whether it accepts their data, and fetches the data once
+/// it is dropped.
+///
+/// Pass every event to `handle_event`. Other than the events it reports, everything is handled
+/// automatically.
+#[derive(Debug, Clone)]
+pub struct DropTarget {
+    window: Window,
+    atoms: XdndAtoms,
+    accepted_types: Vec<Atom>,
+    drag: Option<IncomingDrag>,
+}
+
+impl DropTarget {
+    /// Make `window` accept drops of data with any of the given types, listed in order of preference.
+    /// This sets the window's `XdndAware` property, so `window` should be a top-level window.
+    #[inline]
+    pub fn new<Dpy: Display + ?Sized>(
+        dpy: &mut Dpy,
+        window: Window,
+        atoms: XdndAtoms,
+        accepted_types: Vec<Atom>,
+    ) -> crate::Result<Self> {
+        window.set_xdnd_aware(dpy, &atoms)?;
+        Ok(Self {
+            window,
+            atoms,
+            accepted_types,
+            drag: None,
+        })
+    }
+
+    /// The window that accepts the drops.
+    #[inline]
+    #[must_use]
+    pub fn window(&self) -> Window {
+        self.window
+    }
+
+    /// The types offered by the drag currently over the window. This is empty if there is no drag.
+    #[inline]
+    #[must_use]
+    pub fn offered_types(&self) -> &[Atom] {
+        self.drag.as_ref().map_or(&[], |drag| &drag.types)
+    }
+
+    /// Process an event. This answers the source's messages and fetches the data once it is dropped.
+    #[inline]
+    pub fn handle_event<Dpy: Display + ?Sized>(
+        &mut self,
+        dpy: &mut Dpy,
+        event: &Event,
+    ) -> crate::Result<Option<DropEvent>> {
+        match event {
+            Event::ClientMessage(cme) if cme.window == self.window => {
+                match XdndMessage::from_client_message(&self.atoms, cme) {
+                    Some(message) => self.handle_message(dpy, message),
+                    None => Ok(None),
+                }
+            }
+            Event::SelectionNotify(sne)
+                if sne.requestor == self.window && sne.selection == self.atoms.selection =>
+            {
+                self.receive_data(dpy, sne)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    #[inline]
+    fn handle_message<Dpy: Display + ?Sized>(
+        &mut self,
+        dpy: &mut Dpy,
+        message: XdndMessage,
+    ) -> crate::Result<Option<DropEvent>> {
+        match message {
+            XdndMessage::Enter {
+                source,
+                version,
+                more_types,
+                types,
+            } => {
+                if version < XDND_MIN_VERSION {
+                    log::debug!("Ignoring drag using XDND version {}", version);
+                    return Ok(None);
+                }
+
+                let types: Vec<Atom> = if more_types {
+                    source
+                        .get_property_u32(dpy, self.atoms.type_list, ATOM_ATOM)?
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(Atom::const_from_xid)
+                        .collect()
+                } else {
+                    types
+                        .iter()
+                        .copied()
+                        .filter(|&ty| ty != ATOM_NONE)
+                        .collect()
+                };
+
+                self.drag = Some(IncomingDrag {
+                    source,
+                    version: cmp::min(version, XDND_VERSION),
+                    chosen_type: choose_type(&self.accepted_types, &types),
+                    types,
+                    action: ATOM_NONE,
+                    awaiting_data: false,
+                });
+                Ok(Some(DropEvent::Enter))
+            }
+            XdndMessage::Position {
+                source,
+                root_x,
+                root_y,
+                action,
+                ..
+            } => {
+                let atoms = self.atoms;
+                let drag = match self.drag.as_mut() {
+                    Some(drag) if drag.source == source => drag,
+                    _ => return Ok(None),
+                };
+
+                let accepted = drag.chosen_type.is_some();
+                drag.action = if !accepted {
+                    ATOM_NONE
+                } else if atoms.is_action(action) && action != atoms.action_ask {
+                    action
+                } else {
+                    atoms.action_copy
+                };
+
+                XdndMessage::Status {
+                    target: self.window,
+                    accept: accepted,
+                    want_position: true,
+                    x: 0,
+                    y: 0,
+                    width: 0,
+                    height: 0,
+                    action: drag.action,
+                }
+                .send(dpy, &atoms, source)?;
+                Ok(Some(DropEvent::Position {
+                    root_x,
+                    root_y,
+                    accepted,
+                }))
+            }
+            XdndMessage::Leave { source } => match self.drag {
+                Some(ref drag) if drag.source == source => {
+                    self.drag = None;
+                    Ok(Some(DropEvent::Leave))
+                }
+                _ => Ok(None),
+            },
+            XdndMessage::Drop { source, time } => self.handle_drop(dpy, source, time),
+            XdndMessage::Status { .. } | XdndMessage::Finished { .. } => Ok(None),
+        }
+    }
+
+    #[inline]
+    fn handle_drop<Dpy: Display + ?Sized>(
+        &mut self,
+        dpy: &mut Dpy,
+        source: Window,
+        time: Time,
+    ) -> crate::Result<Option<DropEvent>> {
+        let drag = match self.drag.as_mut() {
+            Some(drag) if drag.source == source => drag,
+            _ => return Ok(None),
+        };
+
+        // the source doesn't offer anything we accept, so there is nothing to fetch
+        let ty = if let Some(ty) = drag.chosen_type {
+            ty
+        } else {
+            self.finish(dpy, false)?;
+            return Ok(Some(DropEvent::Leave));
+        };
+
+        drag.awaiting_data = true;
+        self.window
+            .convert_selection(dpy, self.atoms.selection, ty, self.atoms.selection, time)?;
+        Ok(None)
+    }
+
+    #[inline]
+    fn receive_data<Dpy: Display + ?Sized>(
+        &mut self,
+        dpy: &mut Dpy,
+        sne: &SelectionNotifyEvent,
+    ) -> crate::Result<Option<DropEvent>> {
+        let (ty, action) = if let Some(IncomingDrag {
+            chosen_type: Some(ty),
+            action,
+            awaiting_data: true,
+            ..
+        }) = self.drag
+        {
+            (ty, action)
+        } else {
+            return Ok(None);
+        };
+
+        // the source couldn't convert the data
+        if sne.property == ATOM_NONE {
+            self.finish(dpy, false)?;
+            return Ok(Some(DropEvent::Leave));
+        }
+
+        let data = self
+            .window
+            .get_property_and_delete(dpy, sne.property, ATOM_ANY)?
+            .value
+            .into_owned();
+        self.finish(dpy, true)?;
+        Ok(Some(DropEvent::Drop { ty, action, data }))
+    }
+
+    /// Tell the source that we are done with the drop, and forget about the drag.
+    #[inline]
+    fn finish<Dpy: Display + ?Sized>(&mut self, dpy: &mut Dpy, accepted: bool) -> crate::Result {
+        if let Some(drag) = self.drag.take() {
+            let message = if drag.version >= 5 {
+                XdndMessage::Finished {
+                    target: self.window,
+                    accepted,
+                    action: if accepted { drag.action } else { ATOM_NONE },
+                }
+            } else {
+                XdndMessage::Finished {
+                    target: self.window,
+                    accepted: false,
+                    action: ATOM_NONE,
+                }
+            };
+            message.send(dpy, &self.atoms, drag.source)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Choose the first of the accepted types that the source offers.
+#[inline]
+fn choose_type(accepted: &[Atom], offered: &[Atom]) -> Option<Atom> {
+    accepted.iter().copied().find(|ty| offered.contains(ty))
+}
+
+#[cfg(test)]
+fn test_atoms() -> XdndAtoms {
+    let mut atoms = [ATOM_NONE; 14];
+    atoms
+        .iter_mut()
+        .enumerate()
+        .for_each(|(i, atom)| *atom = Atom::const_from_xid(100 + i as u32));
+    XdndAtoms::from_array(atoms)
+}
+
+#[test]
+fn messages_round_trip() {
+    let atoms = test_atoms();
+    let source = Window::const_from_xid(7);
+    let messages = [
+        XdndMessage::Enter {
+            source,
+            version: 5,
+            more_types: true,
+            types: [Atom::const_from_xid(1), Atom::const_from_xid(2), ATOM_NONE],
+        },
+        XdndMessage::Position {
+            source,
+            root_x: -5,
+            root_y: 300,
+            time: Time::Server(1234),
+            action: atoms.action_copy,
+        },
+        XdndMessage::Status {
+            target: source,
+            accept: true,
+            want_position: false,
+            x: -1,
+            y: 2,
+            width: 30,
+            height: 40,
+            action: atoms.action_move,
+        },
+        XdndMessage::Leave { source },
+        XdndMessage::Drop {
+            source,
+            time: Time::Current,
+        },
+        XdndMessage::Finished {
+            target: source,
+            accepted: true,
+            action: atoms.action_link,
+        },
+    ];
+
+    messages.iter().for_each(|message| {
+        let cme = message.to_client_message(&atoms, Window::const_from_xid(8));
+        assert_eq!(cme.window, Window::const_from_xid(8));
+        assert_eq!(
+            XdndMessage::from_client_message(&atoms, &cme).as_ref(),
+            Some(message)
+        );
+    });
+}
+
+#[test]
+fn enter_message_packs_version_and_flags() {
+    let atoms = test_atoms();
+    let cme = XdndMessage::Enter {
+        source: Window::const_from_xid(1),
+        version: 5,
+        more_types: true,
+        types: [ATOM_NONE; ENTER_TYPES],
+    }
+    .to_client_message(&atoms, Window::const_from_xid(2));
+    assert_eq!(cme.ty, atoms.enter);
+    assert_eq!(cme.data.longs()[1], 0x0500_0001);
+}
+
+#[test]
+fn target_chooses_its_preferred_type() {
+    let uri_list = Atom::const_from_xid(1);
+    let text = Atom::const_from_xid(2);
+    let png = Atom::const_from_xid(3);
+
+    assert_eq!(
+        choose_type(&[uri_list, text], &[png, text, uri_list]),
+        Some(uri_list)
+    );
+    assert_eq!(choose_type(&[text], &[png]), None);
+}
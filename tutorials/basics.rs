@@ -292,7 +292,7 @@
 //!
 //!     // NEW: intern an atom and set it to the window's WM protocol
 //!     let wm_delete_window = conn
-//!         .intern_atom_immediate("WM_DELETE_WINDOW".to_owned(), false)?;
+//!         .intern_atom_immediate("WM_DELETE_WINDOW", false)?;
 //!     window.set_wm_protocols(&mut conn, &[wm_delete_window])?;
 //!
 //!     loop {
@@ -56,7 +56,7 @@
 //!     window.set_title(&mut conn, "Event Mask")?;
 //!     window.map(&mut conn)?;
 //!     let wm_delete_window = conn
-//!         .intern_atom_immediate("WM_DELETE_WINDOW".to_owned(), false)?;
+//!         .intern_atom_immediate("WM_DELETE_WINDOW", false)?;
 //!     window.set_wm_protocols(&mut conn, &[wm_delete_window])?;
 //!
 //!     // NEW: create an event mask that listens for ButtonPress events